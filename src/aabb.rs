@@ -0,0 +1,78 @@
+use crate::ray::Ray;
+use crate::vec3::Vec3;
+
+// axis aligned bounding box
+#[derive(Debug, Copy, Clone)]
+pub struct Aabb {
+    pub min: Vec3,
+    pub max: Vec3
+}
+
+impl Aabb {
+    // the box spanned by two opposite corners in any order
+    pub fn new(a: Vec3, b: Vec3) -> Aabb {
+        Aabb{
+            min: Vec3{x: a.x.min(b.x), y: a.y.min(b.y), z: a.z.min(b.z)},
+            max: Vec3{x: a.x.max(b.x), y: a.y.max(b.y), z: a.z.max(b.z)}
+        }
+    }
+
+    pub fn from_points(points: &[Vec3]) -> Option<Aabb> {
+        let (first, rest) = points.split_first()?;
+        Some(rest.iter().fold(Aabb::new(*first, *first), |b, p| b.surrounding(&Aabb::new(*p, *p))))
+    }
+
+    pub fn surrounding(&self, other: &Aabb) -> Aabb {
+        Aabb::new(
+            Vec3{x: self.min.x.min(other.min.x), y: self.min.y.min(other.min.y), z: self.min.z.min(other.min.z)},
+            Vec3{x: self.max.x.max(other.max.x), y: self.max.y.max(other.max.y), z: self.max.z.max(other.max.z)}
+        )
+    }
+
+    // grows flat sides so that every extent is at least `delta`
+    pub fn pad(&self, delta: f32) -> Aabb {
+        let grow = |min: f32, max: f32| if max - min < delta { (min - delta / 2.0, max + delta / 2.0) } else { (min, max) };
+        let (x0, x1) = grow(self.min.x, self.max.x);
+        let (y0, y1) = grow(self.min.y, self.max.y);
+        let (z0, z1) = grow(self.min.z, self.max.z);
+        Aabb{ min: Vec3{x: x0, y: y0, z: z0}, max: Vec3{x: x1, y: y1, z: z1} }
+    }
+
+    pub fn corners(&self) -> [Vec3; 8] {
+        let (a, b) = (self.min, self.max);
+        [
+            Vec3{x: a.x, y: a.y, z: a.z}, Vec3{x: b.x, y: a.y, z: a.z},
+            Vec3{x: a.x, y: b.y, z: a.z}, Vec3{x: b.x, y: b.y, z: a.z},
+            Vec3{x: a.x, y: a.y, z: b.z}, Vec3{x: b.x, y: a.y, z: b.z},
+            Vec3{x: a.x, y: b.y, z: b.z}, Vec3{x: b.x, y: b.y, z: b.z}
+        ]
+    }
+
+    // slab test
+    pub fn hit(&self, r: &Ray, t_min: f32, t_max: f32) -> bool {
+        self.interval(r, t_min, t_max).is_some()
+    }
+
+    // the part of (t_min, t_max) the ray spends inside the box
+    pub fn interval(&self, r: &Ray, t_min: f32, t_max: f32) -> Option<(f32, f32)> {
+        let axes = [
+            (r.origin.x, r.direction.x, self.min.x, self.max.x),
+            (r.origin.y, r.direction.y, self.min.y, self.max.y),
+            (r.origin.z, r.direction.z, self.min.z, self.max.z)
+        ];
+        let (mut t0, mut t1) = (t_min, t_max);
+        for (origin, direction, min, max) in axes.iter() {
+            let inv = 1.0 / direction;
+            let (mut near, mut far) = ((min - origin) * inv, (max - origin) * inv);
+            if inv < 0.0 {
+                std::mem::swap(&mut near, &mut far);
+            }
+            t0 = if near > t0 { near } else { t0 };
+            t1 = if far < t1 { far } else { t1 };
+            if t1 <= t0 {
+                return None;
+            }
+        }
+        Some((t0, t1))
+    }
+}
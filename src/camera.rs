@@ -1,8 +1,48 @@
+use crate::lens::Aperture;
 use crate::ray::Ray;
+use crate::stereo::{StereoCamera, StereoLayout};
 use crate::vec3::Vec3;
-use js_sys::Math;
+use rand::Rng;
+use std::f32::consts;
 
-pub struct Camera {
+pub trait Camera {
+    // the ray through the image at s across and t up from the lower left corner, both in [0, 1]
+    fn get_ray(&self, s: f32, t: f32) -> Ray;
+
+    // the ray a renderer traces, None when the sample carries no light, like a ray the lens blocks
+    fn sample_ray(&self, s: f32, t: f32) -> Option<Ray> {
+        Some(self.get_ray(s, t))
+    }
+}
+
+// the shutter is open from open to close
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct Shutter {
+    pub(crate) open: f32,
+    pub(crate) close: f32
+}
+
+impl Shutter {
+    // rays get random times between opening and closing, moving objects blur over the interval
+    pub(crate) fn sample(&self) -> f32 {
+        if self.close > self.open {
+            rand::thread_rng().gen_range(self.open, self.close)
+        } else {
+            self.open
+        }
+    }
+}
+
+// orthonormal camera frame, w points backwards away from lookat
+pub(crate) fn frame(lookfrom: Vec3, lookat: Vec3, vup: Vec3) -> (Vec3, Vec3, Vec3) {
+    let w = (lookfrom - lookat).unit_vector();
+    let u = vup.cross(&w).unit_vector();
+    let v = w.cross(&u);
+    (u, v, w)
+}
+
+// thin lens camera
+pub struct PerspectiveCamera {
     origin: Vec3,
     lower_left_corner: Vec3,
     horizontal: Vec3,
@@ -10,7 +50,11 @@ pub struct Camera {
     u: Vec3,
     v: Vec3,
     w: Vec3,
-    lens_radius: f32
+    lens_radius: f32,
+    aperture: Aperture,
+    // how far the lens barrel cuts into the aperture at the image corners
+    cats_eye: f32,
+    shutter: Shutter
 }
 
 
@@ -37,20 +81,18 @@ fn degrees_to_radians(degrees: f32) -> f32 {
     return degrees * std::f32::consts::PI / 180.0;
 }
 
-impl Camera {
+impl PerspectiveCamera {
     pub fn new(lookfrom: Vec3, lookat: Vec3, vup: Vec3, vfov: f32, aspect_ratio: f32, aperture: f32, focus_dist: f32) -> Self {
         // let viewport_height = 2.0;
         // let viewport_width = aspect_ratio * viewport_height;
 
         let theta = degrees_to_radians(vfov);
-        let h = Math::tan((theta/2.0).into());
-        let viewport_height = 2.0 * h as f32;
+        let h = (theta / 2.0).tan();
+        let viewport_height = 2.0 * h;
         let viewport_width = aspect_ratio * viewport_height;
     
 
-        let w = (lookfrom - lookat).unit_vector();
-        let u = vup.cross(&w).unit_vector();
-        let v = w.cross(&u);
+        let (u, v, w) = frame(lookfrom, lookat, vup);
 
         let origin = lookfrom;
         let horizontal = u * (viewport_width * focus_dist);
@@ -66,10 +108,38 @@ impl Camera {
             u,
             v,
             w,
-            lens_radius
+            lens_radius,
+            aperture: Aperture::Circle,
+            cats_eye: 0.0,
+            shutter: Shutter{ open: 0.0, close: 0.0 }
         }
     }
 
+    // the shape of out of focus highlights
+    pub fn with_aperture(mut self, aperture: Aperture) -> Self {
+        self.aperture = aperture;
+        self
+    }
+
+    // bokeh turns into cat's eyes towards the corners, 0 for none and 1 for the barrel reaching the aperture's center
+    pub fn with_cats_eye(mut self, strength: f32) -> Self {
+        self.cats_eye = strength.clamp(0.0, 1.0);
+        self
+    }
+
+    pub fn with_shutter(mut self, open: f32, close: f32) -> Self {
+        self.shutter = Shutter{ open, close };
+        self
+    }
+
+    // moves the image `shift` image widths sideways without turning the camera, for off axis stereo pairs
+    pub fn with_lens_shift(mut self, shift: f32) -> Self {
+        self.lower_left_corner = self.lower_left_corner + self.horizontal * shift;
+        self
+    }
+}
+
+impl Camera for PerspectiveCamera {
     /*
     
     ray get_ray(double s, double t) const {
@@ -83,15 +153,192 @@ impl Camera {
         }
     
     */
-    pub fn get_ray(&self, s: f32, t: f32) -> Ray {
+    fn get_ray(&self, s: f32, t: f32) -> Ray {
 
-        let rd = Vec3::random_in_unit_disk() * self.lens_radius;
-        let offset = self.u * rd.x + self.v * rd.y;
+        let vignetting = (self.cats_eye * (2.0 * s - 1.0), self.cats_eye * (2.0 * t - 1.0));
+        let (x, y) = self.aperture.sample_vignetted(vignetting);
+        let offset = (self.u * x + self.v * y) * self.lens_radius;
 
         return  Ray{ 
             origin: self.origin + offset, 
-            direction: self.lower_left_corner + (self.horizontal * s)  + self.vertical * t - self.origin - offset 
+            direction: self.lower_left_corner + (self.horizontal * s)  + self.vertical * t - self.origin - offset,
+            wavelength: None,
+            time: self.shutter.sample()
         };
     }
 
-}
\ No newline at end of file
+}
+// parallel rays, `height` is the extent of the image in world units
+pub struct OrthographicCamera {
+    lower_left_corner: Vec3,
+    horizontal: Vec3,
+    vertical: Vec3,
+    direction: Vec3,
+    shutter: Shutter
+}
+
+impl OrthographicCamera {
+    pub fn new(lookfrom: Vec3, lookat: Vec3, vup: Vec3, height: f32, aspect_ratio: f32) -> Self {
+        let (u, v, w) = frame(lookfrom, lookat, vup);
+        let horizontal = u * (height * aspect_ratio);
+        let vertical = v * height;
+        Self {
+            lower_left_corner: lookfrom - horizontal / 2.0 - vertical / 2.0,
+            horizontal,
+            vertical,
+            direction: w * -1.0,
+            shutter: Shutter{ open: 0.0, close: 0.0 }
+        }
+    }
+
+    pub fn with_shutter(mut self, open: f32, close: f32) -> Self {
+        self.shutter = Shutter{ open, close };
+        self
+    }
+}
+
+impl Camera for OrthographicCamera {
+    fn get_ray(&self, s: f32, t: f32) -> Ray {
+        Ray{
+            origin: self.lower_left_corner + self.horizontal * s + self.vertical * t,
+            direction: self.direction,
+            wavelength: None,
+            time: self.shutter.sample()
+        }
+    }
+}
+
+// how the angle from the view direction maps to the distance from the image center
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum FisheyeMapping {
+    // distance proportional to the angle, straight lines through the center stay evenly spaced
+    Equidistant,
+    // equal solid angles cover equal image areas, like most real fisheye lenses
+    Equisolid
+}
+
+/*
+    Fisheye lens with `fov` degrees across the image height. The image is
+    filled, the corners see beyond the field of view up to straight behind.
+*/
+pub struct FisheyeCamera {
+    origin: Vec3,
+    u: Vec3,
+    v: Vec3,
+    w: Vec3,
+    // half the field of view in radians
+    half_fov: f32,
+    mapping: FisheyeMapping,
+    aspect_ratio: f32,
+    shutter: Shutter
+}
+
+impl FisheyeCamera {
+    pub fn new(lookfrom: Vec3, lookat: Vec3, vup: Vec3, fov: f32, mapping: FisheyeMapping, aspect_ratio: f32) -> Self {
+        let (u, v, w) = frame(lookfrom, lookat, vup);
+        Self {
+            origin: lookfrom,
+            u,
+            v,
+            w,
+            half_fov: fov.to_radians() / 2.0,
+            mapping,
+            aspect_ratio,
+            shutter: Shutter{ open: 0.0, close: 0.0 }
+        }
+    }
+
+    pub fn with_shutter(mut self, open: f32, close: f32) -> Self {
+        self.shutter = Shutter{ open, close };
+        self
+    }
+
+    // angle from the view direction at `radius` half image heights from the center
+    fn angle(&self, radius: f32) -> f32 {
+        let angle = match self.mapping {
+            FisheyeMapping::Equidistant => radius * self.half_fov,
+            FisheyeMapping::Equisolid => 2.0 * (radius * (self.half_fov / 2.0).sin()).min(1.0).asin()
+        };
+        angle.min(consts::PI)
+    }
+}
+
+impl Camera for FisheyeCamera {
+    fn get_ray(&self, s: f32, t: f32) -> Ray {
+        let x = (2.0 * s - 1.0) * self.aspect_ratio;
+        let y = 2.0 * t - 1.0;
+        let theta = self.angle((x * x + y * y).sqrt());
+        let phi = y.atan2(x);
+        let (sin_theta, cos_theta) = theta.sin_cos();
+        Ray{
+            origin: self.origin,
+            direction: (self.u * phi.cos() + self.v * phi.sin()) * sin_theta - self.w * cos_theta,
+            wavelength: None,
+            time: self.shutter.sample()
+        }
+    }
+}
+
+// the whole sphere around the camera, longitude across and latitude up the image
+pub struct EquirectangularCamera {
+    origin: Vec3,
+    u: Vec3,
+    v: Vec3,
+    w: Vec3,
+    shutter: Shutter
+}
+
+impl EquirectangularCamera {
+    // lookat is in the center of the image, aspect ratios other than 2 stretch it
+    pub fn new(lookfrom: Vec3, lookat: Vec3, vup: Vec3) -> Self {
+        let (u, v, w) = frame(lookfrom, lookat, vup);
+        Self { origin: lookfrom, u, v, w, shutter: Shutter{ open: 0.0, close: 0.0 } }
+    }
+
+    pub fn with_shutter(mut self, open: f32, close: f32) -> Self {
+        self.shutter = Shutter{ open, close };
+        self
+    }
+}
+
+impl Camera for EquirectangularCamera {
+    fn get_ray(&self, s: f32, t: f32) -> Ray {
+        let longitude = (s - 0.5) * 2.0 * consts::PI;
+        let latitude = (t - 0.5) * consts::PI;
+        let (sin_lat, cos_lat) = latitude.sin_cos();
+        let (sin_lon, cos_lon) = longitude.sin_cos();
+        Ray{
+            origin: self.origin,
+            direction: self.u * (cos_lat * sin_lon) + self.v * sin_lat - self.w * (cos_lat * cos_lon),
+            wavelength: None,
+            time: self.shutter.sample()
+        }
+    }
+}
+
+// the camera of a scene, angles in degrees
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Projection {
+    Perspective { vfov: f32, aperture: f32, focus_dist: f32 },
+    Orthographic { height: f32 },
+    Fisheye { fov: f32, mapping: FisheyeMapping },
+    Equirectangular,
+    // both eyes packed into one image, distances in scene units
+    StereoPerspective { vfov: f32, ipd: f32, convergence: f32, layout: StereoLayout },
+    Ods { ipd: f32, layout: StereoLayout }
+}
+
+impl Projection {
+    pub fn camera(&self, lookfrom: Vec3, lookat: Vec3, vup: Vec3, aspect_ratio: f32) -> Box<dyn Camera> {
+        match *self {
+            Projection::Perspective{ vfov, aperture, focus_dist } =>
+                Box::new(PerspectiveCamera::new(lookfrom, lookat, vup, vfov, aspect_ratio, aperture, focus_dist)),
+            Projection::Orthographic{ height } => Box::new(OrthographicCamera::new(lookfrom, lookat, vup, height, aspect_ratio)),
+            Projection::Fisheye{ fov, mapping } => Box::new(FisheyeCamera::new(lookfrom, lookat, vup, fov, mapping, aspect_ratio)),
+            Projection::Equirectangular => Box::new(EquirectangularCamera::new(lookfrom, lookat, vup)),
+            Projection::StereoPerspective{ vfov, ipd, convergence, layout } =>
+                Box::new(StereoCamera::perspective(lookfrom, lookat, vup, vfov, aspect_ratio, ipd, convergence, layout)),
+            Projection::Ods{ ipd, layout } => Box::new(StereoCamera::ods(lookfrom, lookat, vup, ipd, layout))
+        }
+    }
+}
@@ -33,6 +33,15 @@ impl Color {
         self.r * rhs.r + self.g * rhs.g + self.b * rhs.b
     }
 
+    // CIE XYZ to linear sRGB (D65)
+    pub fn from_xyz(x: f32, y: f32, z: f32) -> Color {
+        Color {
+            r: 3.2404542 * x - 1.5371385 * y - 0.4985314 * z,
+            g: -0.9692660 * x + 1.8760108 * y + 0.0415560 * z,
+            b: 0.0556434 * x - 0.2040259 * y + 1.0572252 * z
+        }
+    }
+
     pub fn to_str(&self) -> String {
     
         format!("(value r: {}, value g: {}, value b: {})", self.r, self.g, self.b)
@@ -74,6 +83,14 @@ impl Mul<f32> for Color {
     }
 }
 
+impl Mul<Color> for Color {
+    type Output = Color;
+
+    fn mul(self, rhs: Color) -> Self::Output {
+        Color {r: self.r * rhs.r, g: self.g * rhs.g, b: self.b * rhs.b}
+    }
+}
+
 impl Div<f32> for &Color {
     type Output = Color;
 
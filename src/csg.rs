@@ -0,0 +1,136 @@
+use crate::aabb::Aabb;
+use crate::hittable::{Hittable, HitRecord};
+use crate::material::Material;
+use crate::ray::Ray;
+
+// surfaces crossed along one ray before an operand is considered degenerate
+const MAX_CROSSINGS: usize = 64;
+// steps past a crossing before looking for the next one
+const CROSSING_EPSILON: f32 = 1e-4;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum CsgOperation {
+    Union,
+    Intersection,
+    // the left operand with the right one cut away
+    Difference
+}
+
+impl CsgOperation {
+    fn inside(&self, left: bool, right: bool) -> bool {
+        match self {
+            CsgOperation::Union => left || right,
+            CsgOperation::Intersection => left && right,
+            CsgOperation::Difference => left && !right
+        }
+    }
+}
+
+/*
+    Solid built from two closed operands. Every operand is crossed along the
+    ray to find the intervals where the ray is inside of it, the first change
+    of the combined inside state in range is the hit.
+
+    Surfaces keep the material of their operand unless the solid has its own.
+    A glass lens needs one material for all its surfaces, the integrator tells
+    the media a path is inside of apart by their material.
+*/
+pub struct Csg {
+    pub operation: CsgOperation,
+    pub left: Box<dyn Hittable>,
+    pub right: Box<dyn Hittable>,
+    pub material: Option<Box<dyn Material>>
+}
+
+impl Csg {
+    pub fn union(left: Box<dyn Hittable>, right: Box<dyn Hittable>) -> Self {
+        Self { operation: CsgOperation::Union, left, right, material: None }
+    }
+
+    pub fn intersection(left: Box<dyn Hittable>, right: Box<dyn Hittable>) -> Self {
+        Self { operation: CsgOperation::Intersection, left, right, material: None }
+    }
+
+    pub fn difference(left: Box<dyn Hittable>, right: Box<dyn Hittable>) -> Self {
+        Self { operation: CsgOperation::Difference, left, right, material: None }
+    }
+
+    pub fn with_material(mut self, material: Box<dyn Material>) -> Self {
+        self.material = Some(material);
+        self
+    }
+}
+
+/*
+    Surfaces of `object` from t_min on up to the first one past t_max, which
+    still tells whether the ray is inside at the end of the range.
+*/
+fn crossings<'a>(object: &'a dyn Hittable, r: &Ray, t_min: f32, t_max: f32) -> Vec<HitRecord<'a>> {
+    let mut result: Vec<HitRecord<'a>> = Vec::new();
+    let mut t = t_min;
+    while result.len() < MAX_CROSSINGS {
+        match object.hit(r, t, f32::INFINITY) {
+            Some(hit_record) => {
+                t = hit_record.t + CROSSING_EPSILON;
+                let past = hit_record.t > t_max;
+                result.push(hit_record);
+                if past {
+                    break;
+                }
+            },
+            None => break
+        }
+    }
+    result
+}
+
+// the ray starts inside a closed object when its first crossing leaves it
+fn starts_inside(crossings: &[HitRecord]) -> bool {
+    crossings.first().is_some_and(|c| !c.front_face)
+}
+
+impl Hittable for Csg {
+    fn hit(&self, r: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        let left = crossings(self.left.as_ref(), r, t_min, t_max);
+        let right = crossings(self.right.as_ref(), r, t_min, t_max);
+        let mut inside_left = starts_inside(&left);
+        let mut inside_right = starts_inside(&right);
+        let inside = self.operation.inside(inside_left, inside_right);
+
+        let mut events: Vec<(HitRecord, bool)> = left.into_iter().map(|c| (c, true))
+            .chain(right.into_iter().map(|c| (c, false)))
+            .collect();
+        events.sort_by(|a, b| a.0.t.total_cmp(&b.0.t));
+
+        for (mut hit_record, from_left) in events {
+            if hit_record.t >= t_max {
+                return None;
+            }
+            if from_left { inside_left = hit_record.front_face } else { inside_right = hit_record.front_face }
+            let now_inside = self.operation.inside(inside_left, inside_right);
+            if now_inside == inside {
+                continue;
+            }
+
+            let mut outward = if hit_record.front_face { hit_record.normal } else { hit_record.normal * -1.0 };
+            // the cut away solid is seen from its inside
+            if self.operation == CsgOperation::Difference && !from_left {
+                outward = outward * -1.0;
+            }
+            hit_record.set_face_normal(r, outward);
+            if let Some(material) = &self.material {
+                hit_record.material = material;
+            }
+            return Some(hit_record);
+        }
+        None
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        match self.operation {
+            CsgOperation::Union => Some(self.left.bounding_box()?.surrounding(&self.right.bounding_box()?)),
+            CsgOperation::Intersection => self.left.bounding_box().or_else(|| self.right.bounding_box()),
+            CsgOperation::Difference => self.left.bounding_box()
+        }
+    }
+}
@@ -0,0 +1,174 @@
+use crate::vec3::Vec3;
+
+// spatially varying density of a heterogeneous medium
+pub trait DensityField {
+    fn density(&self, p: &Vec3) -> f32;
+    // an upper bound of `density` everywhere, the majorant for delta tracking
+    fn max_density(&self) -> f32;
+}
+
+/*
+    Smoke and clouds from fractal gradient noise. The noise is hashed from the
+    lattice coordinates so every render of a scene sees the same volume.
+*/
+pub struct NoiseDensity {
+    pub max_density: f32,
+    pub scale: f32,
+    pub octaves: u32,
+    // noise values below the threshold are empty space
+    pub threshold: f32
+}
+
+fn hash(x: i32, y: i32, z: i32) -> u32 {
+    let mut h = (x as u32).wrapping_mul(0x8da6_b343)
+        ^ (y as u32).wrapping_mul(0xd816_3841)
+        ^ (z as u32).wrapping_mul(0xcb1a_b31f);
+    h ^= h >> 13;
+    h = h.wrapping_mul(0x5bd1_e995);
+    h ^ (h >> 15)
+}
+
+fn gradient(x: i32, y: i32, z: i32) -> Vec3 {
+    // one of the 12 edge directions of a cube, as in improved Perlin noise
+    match hash(x, y, z) % 12 {
+        0 => Vec3{x: 1.0, y: 1.0, z: 0.0},
+        1 => Vec3{x: -1.0, y: 1.0, z: 0.0},
+        2 => Vec3{x: 1.0, y: -1.0, z: 0.0},
+        3 => Vec3{x: -1.0, y: -1.0, z: 0.0},
+        4 => Vec3{x: 1.0, y: 0.0, z: 1.0},
+        5 => Vec3{x: -1.0, y: 0.0, z: 1.0},
+        6 => Vec3{x: 1.0, y: 0.0, z: -1.0},
+        7 => Vec3{x: -1.0, y: 0.0, z: -1.0},
+        8 => Vec3{x: 0.0, y: 1.0, z: 1.0},
+        9 => Vec3{x: 0.0, y: -1.0, z: 1.0},
+        10 => Vec3{x: 0.0, y: 1.0, z: -1.0},
+        _ => Vec3{x: 0.0, y: -1.0, z: -1.0}
+    }
+}
+
+fn fade(t: f32) -> f32 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+// gradient noise in about [-1, 1]
+pub fn noise(p: &Vec3) -> f32 {
+    let (fx, fy, fz) = (p.x.floor(), p.y.floor(), p.z.floor());
+    let (ix, iy, iz) = (fx as i32, fy as i32, fz as i32);
+    let d = Vec3{x: p.x - fx, y: p.y - fy, z: p.z - fz};
+
+    let corner = |i: i32, j: i32, k: i32| {
+        let offset = Vec3{x: d.x - i as f32, y: d.y - j as f32, z: d.z - k as f32};
+        gradient(ix + i, iy + j, iz + k).dot(&offset)
+    };
+    let (u, v, w) = (fade(d.x), fade(d.y), fade(d.z));
+    lerp(
+        lerp(lerp(corner(0, 0, 0), corner(1, 0, 0), u), lerp(corner(0, 1, 0), corner(1, 1, 0), u), v),
+        lerp(lerp(corner(0, 0, 1), corner(1, 0, 1), u), lerp(corner(0, 1, 1), corner(1, 1, 1), u), v),
+        w
+    )
+}
+
+// fractal sum of noise octaves
+pub fn fbm(p: &Vec3, octaves: u32) -> f32 {
+    let mut sum = 0.0;
+    let mut amplitude = 0.5;
+    let mut q = *p;
+    for _ in 0..octaves {
+        sum += amplitude * noise(&q);
+        q = q * 2.0;
+        amplitude *= 0.5;
+    }
+    sum
+}
+
+impl DensityField for NoiseDensity {
+    fn density(&self, p: &Vec3) -> f32 {
+        let n = 0.5 + fbm(&(*p * self.scale), self.octaves);
+        if n <= self.threshold {
+            return 0.0;
+        }
+        self.max_density * ((n - self.threshold) / (1.0 - self.threshold)).min(1.0)
+    }
+
+    fn max_density(&self) -> f32 {
+        self.max_density
+    }
+}
+
+/*
+    Densities on a regular grid spanning the box from `min` to `max`,
+    interpolated trilinearly and zero outside the box.
+*/
+pub struct VoxelGrid {
+    pub nx: usize,
+    pub ny: usize,
+    pub nz: usize,
+    pub min: Vec3,
+    pub max: Vec3,
+    // multiplies the stored values
+    pub scale: f32,
+    data: Vec<f32>,
+    max_value: f32
+}
+
+impl VoxelGrid {
+    pub fn new(nx: usize, ny: usize, nz: usize, data: Vec<f32>, min: Vec3, max: Vec3) -> Result<Self, String> {
+        if nx == 0 || ny == 0 || nz == 0 || data.len() != nx * ny * nz {
+            return Err(format!("voxel grid of {}x{}x{} needs {} values, got {}", nx, ny, nz, nx * ny * nz, data.len()));
+        }
+        let max_value = data.iter().cloned().fold(0.0, f32::max);
+        Ok(Self { nx, ny, nz, min, max, scale: 1.0, data, max_value })
+    }
+
+    // little endian 32 bit floats, x varying fastest then y then z
+    pub fn from_raw(bytes: &[u8], nx: usize, ny: usize, nz: usize, min: Vec3, max: Vec3) -> Result<Self, String> {
+        let chunks = bytes.chunks_exact(4);
+        if !chunks.remainder().is_empty() {
+            return Err(format!("raw float buffer has {} bytes, not a multiple of 4", bytes.len()));
+        }
+        let data = chunks
+            .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]).max(0.0))
+            .collect();
+        Self::new(nx, ny, nz, data, min, max)
+    }
+
+    fn voxel(&self, x: usize, y: usize, z: usize) -> f32 {
+        self.data[(z * self.ny + y) * self.nx + x]
+    }
+}
+
+impl DensityField for VoxelGrid {
+    fn density(&self, p: &Vec3) -> f32 {
+        let extent = self.max - self.min;
+        let local = *p - self.min;
+        // voxel centers are at half integer grid coordinates
+        let gx = local.x / extent.x * self.nx as f32 - 0.5;
+        let gy = local.y / extent.y * self.ny as f32 - 0.5;
+        let gz = local.z / extent.z * self.nz as f32 - 0.5;
+        if gx < -0.5 || gy < -0.5 || gz < -0.5
+            || gx > self.nx as f32 - 0.5 || gy > self.ny as f32 - 0.5 || gz > self.nz as f32 - 0.5 {
+            return 0.0;
+        }
+
+        let clamp = |g: f32, n: usize| g.clamp(0.0, (n - 1) as f32);
+        let (gx, gy, gz) = (clamp(gx, self.nx), clamp(gy, self.ny), clamp(gz, self.nz));
+        let (x0, y0, z0) = (gx as usize, gy as usize, gz as usize);
+        let (x1, y1, z1) = ((x0 + 1).min(self.nx - 1), (y0 + 1).min(self.ny - 1), (z0 + 1).min(self.nz - 1));
+        let (u, v, w) = (gx - x0 as f32, gy - y0 as f32, gz - z0 as f32);
+
+        let value = lerp(
+            lerp(lerp(self.voxel(x0, y0, z0), self.voxel(x1, y0, z0), u), lerp(self.voxel(x0, y1, z0), self.voxel(x1, y1, z0), u), v),
+            lerp(lerp(self.voxel(x0, y0, z1), self.voxel(x1, y0, z1), u), lerp(self.voxel(x0, y1, z1), self.voxel(x1, y1, z1), u), v),
+            w
+        );
+        value * self.scale
+    }
+
+    fn max_density(&self) -> f32 {
+        self.max_value * self.scale
+    }
+}
@@ -0,0 +1,172 @@
+/*
+    Turns the radiance gathered for a pixel into what a camera records, set
+    up the way photographers do: ISO, shutter speed and f-stop for the
+    exposure and a color temperature for the white balance.
+*/
+
+use crate::color::Color;
+use crate::spectrum::cie_xyz;
+
+// color temperature of the sRGB white point, neutral white balance
+pub const NEUTRAL_KELVIN: f32 = 6504.0;
+
+/*
+    Scene radiance is taken relative to a sunny day, exposed right by the
+    sunny 16 rule: f/16 with a shutter speed of 1/ISO seconds records it
+    unscaled. Every stop of ISO, shutter speed or f-stop doubles or halves it.
+*/
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Exposure {
+    iso: f32,
+    // seconds the shutter is open, 1/125 is 0.008
+    shutter_speed: f32,
+    f_number: f32
+}
+
+impl Default for Exposure {
+    fn default() -> Self {
+        Self { iso: 100.0, shutter_speed: 0.01, f_number: 16.0 }
+    }
+}
+
+impl Exposure {
+    pub fn new(iso: f32, shutter_speed: f32, f_number: f32) -> Result<Self, String> {
+        if iso <= 0.0 || shutter_speed <= 0.0 || f_number <= 0.0 {
+            return Err(format!("ISO {}, {} s at f/{} can't expose an image", iso, shutter_speed, f_number));
+        }
+        Ok(Self { iso, shutter_speed, f_number })
+    }
+
+    pub fn iso(&self) -> f32 {
+        self.iso
+    }
+
+    pub fn shutter_speed(&self) -> f32 {
+        self.shutter_speed
+    }
+
+    pub fn f_number(&self) -> f32 {
+        self.f_number
+    }
+
+    // exposure value at ISO 100, 0 is 1 s at f/1
+    pub fn ev100(&self) -> f32 {
+        (self.f_number * self.f_number / self.shutter_speed * 100.0 / self.iso).log2()
+    }
+
+    // factor on the scene radiance, 1 for the sunny 16 rule
+    pub fn scale(&self) -> f32 {
+        16.0 * 16.0 * self.shutter_speed * self.iso / (self.f_number * self.f_number)
+    }
+
+    /*
+        Diameter of the thin lens for `PerspectiveCamera::new`, a focal length
+        over the f-stop. The focal length is the one the field of view `vfov`
+        in degrees has on a sensor `sensor_height` tall, both in scene units,
+        0.024 for a full frame camera in a scene in meters.
+    */
+    pub fn aperture(&self, vfov: f32, sensor_height: f32) -> f32 {
+        let focal_length = sensor_height / (2.0 * (vfov.to_radians() / 2.0).tan());
+        focal_length / self.f_number
+    }
+}
+
+// Planck's law up to a constant factor, `lambda` in nanometers
+fn black_body(lambda: f32, kelvin: f32) -> f32 {
+    // second radiation constant in nanometer kelvin
+    const C2: f32 = 1.4388e7;
+    1.0 / (lambda.powi(5) * ((C2 / (lambda * kelvin)).exp() - 1.0)) * 1e15
+}
+
+// sRGB primaries to CIE XYZ
+const SRGB_TO_XYZ: [[f32; 3]; 3] = [
+    [0.4124564, 0.3575761, 0.1804375],
+    [0.2126729, 0.7151522, 0.0721750],
+    [0.0193339, 0.119192, 0.9503041]
+];
+// CIE XYZ to the cone responses of the Bradford transform and back
+const BRADFORD: [[f32; 3]; 3] = [
+    [0.8951, 0.2664, -0.1614],
+    [-0.7502, 1.7135, 0.0367],
+    [0.0389, -0.0685, 1.0296]
+];
+const BRADFORD_INVERSE: [[f32; 3]; 3] = [
+    [0.9869929, -0.1470543, 0.1599627],
+    [0.4323053, 0.5183603, 0.0492912],
+    [-0.0085287, 0.0400428, 0.9684867]
+];
+
+fn multiply(m: &[[f32; 3]; 3], (a, b, c): (f32, f32, f32)) -> (f32, f32, f32) {
+    (
+        m[0][0] * a + m[0][1] * b + m[0][2] * c,
+        m[1][0] * a + m[1][1] * b + m[1][2] * c,
+        m[2][0] * a + m[2][1] * b + m[2][2] * c
+    )
+}
+
+// CIE XYZ of a black body with a luminance of 1, from 1000 K up
+fn black_body_xyz(kelvin: f32) -> (f32, f32, f32) {
+    let kelvin = kelvin.max(1000.0);
+    let (mut x, mut y, mut z) = (0.0, 0.0, 0.0);
+    let mut lambda = 380.0;
+    while lambda <= 780.0 {
+        let radiance = black_body(lambda, kelvin);
+        let (cx, cy, cz) = cie_xyz(lambda);
+        x += radiance * cx;
+        y += radiance * cy;
+        z += radiance * cz;
+        lambda += 5.0;
+    }
+    (x / y, 1.0, z / y)
+}
+
+/*
+    Linear sRGB of a black body with a luminance of 1. Below about 1950 K it
+    is outside the sRGB gamut and its blue is negative.
+*/
+pub fn black_body_color(kelvin: f32) -> Color {
+    let (x, y, z) = black_body_xyz(kelvin);
+    Color::from_xyz(x, y, z)
+}
+
+/*
+    Exposure and white balance applied to the average radiance of a pixel.
+    The white balance makes light of `white_balance` kelvin come out
+    neutral, a von Kries adaptation scaling the Bradford cone responses. The
+    cone responses of black bodies stay positive where sRGB's don't.
+*/
+#[derive(Debug, Copy, Clone)]
+pub struct Film {
+    pub exposure: Exposure,
+    pub white_balance: f32,
+    // where exposure and white balance together take the sRGB primaries
+    primaries: [Color; 3]
+}
+
+impl Film {
+    pub fn new(exposure: Exposure, white_balance: f32) -> Self {
+        let neutral = multiply(&BRADFORD, black_body_xyz(NEUTRAL_KELVIN));
+        let light = multiply(&BRADFORD, black_body_xyz(white_balance));
+        let primary = |rgb: (f32, f32, f32)| {
+            let (l, m, s) = multiply(&BRADFORD, multiply(&SRGB_TO_XYZ, rgb));
+            let adapted = (l * neutral.0 / light.0, m * neutral.1 / light.1, s * neutral.2 / light.2);
+            let (x, y, z) = multiply(&BRADFORD_INVERSE, adapted);
+            Color::from_xyz(x, y, z) * exposure.scale()
+        };
+        let primaries = [primary((1.0, 0.0, 0.0)), primary((0.0, 1.0, 0.0)), primary((0.0, 0.0, 1.0))];
+        Self { exposure, white_balance, primaries }
+    }
+
+    // colors adapted out of the sRGB gamut, like white under a candle's white balance, are clipped to it
+    pub fn develop(&self, radiance: &Color) -> Color {
+        let [r, g, b] = self.primaries;
+        let developed = r * radiance.r + g * radiance.g + b * radiance.b;
+        Color{r: developed.r.max(0.0), g: developed.g.max(0.0), b: developed.b.max(0.0)}
+    }
+}
+
+impl Default for Film {
+    fn default() -> Self {
+        Self::new(Exposure::default(), NEUTRAL_KELVIN)
+    }
+}
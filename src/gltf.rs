@@ -0,0 +1,502 @@
+/*
+    glTF 2.0 import from the bytes of a .gltf or .glb file.
+
+    Meshes become `Mesh`es shared between the nodes using them through
+    `Instance`s, cameras become perspective and orthographic `Camera`s and
+    metallic roughness materials become `Principled` ones. Buffers have to
+    be embedded, either in the binary chunk of a .glb or as base64 data
+    URIs.
+
+    Whatever the renderer can't show, like textures or emission, is skipped
+    with a warning instead of failing the import.
+*/
+
+use crate::camera::{Camera, OrthographicCamera, PerspectiveCamera};
+use crate::color::Color;
+use crate::hittable::Hittable;
+use crate::hittable_list::HittableList;
+use crate::instance::Instance;
+use crate::json::Json;
+use crate::material::{Material, Named};
+use crate::principled::Principled;
+use crate::sky::Sky;
+use crate::texture::SolidColor;
+use crate::transform::{Mat4, Transform};
+use crate::triangle::Mesh;
+use crate::vec3::Vec3;
+use std::sync::Arc;
+
+const GLB_MAGIC: &[u8] = b"glTF";
+const CHUNK_JSON: u32 = 0x4e4f_534a;
+const CHUNK_BIN: u32 = 0x004e_4942;
+// values of an accessor without buffer view, a million 4x4 matrices
+const MAX_ZERO_VALUES: usize = 1 << 24;
+const SUPPORTED_EXTENSIONS: [&str; 3] = ["KHR_lights_punctual", "KHR_materials_transmission", "KHR_materials_ior"];
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum LightKind {
+    Directional,
+    Point,
+    // cone angles in radians
+    Spot { inner_cone_angle: f32, outer_cone_angle: f32 }
+}
+
+// a KHR_lights_punctual light placed in the world
+#[derive(Debug, Copy, Clone)]
+pub struct PunctualLight {
+    pub kind: LightKind,
+    pub color: Color,
+    pub intensity: f32,
+    pub position: Vec3,
+    // the direction the light shines along, for directional and spot lights
+    pub direction: Vec3,
+    pub range: Option<f32>
+}
+
+pub struct GltfScene {
+    pub objects: Vec<Box<dyn Hittable>>,
+    pub cameras: Vec<Box<dyn Camera>>,
+    pub lights: Vec<PunctualLight>,
+    pub warnings: Vec<String>
+}
+
+impl GltfScene {
+    /*
+        The integrator lights scenes by the sun and sky only, the first
+        directional light becomes the sun. Point and spot lights are imported
+        but not rendered.
+    */
+    pub fn sky(&self, turbidity: f32) -> Option<Sky> {
+        let light = self.lights.iter().find(|l| l.kind == LightKind::Directional)?;
+        Some(Sky::towards(&(light.direction * -1.0), turbidity))
+    }
+}
+
+fn base64_decode(text: &str) -> Result<Vec<u8>, String> {
+    let value = |c: u8| match c {
+        b'A'..=b'Z' => Some(c - b'A'),
+        b'a'..=b'z' => Some(c - b'a' + 26),
+        b'0'..=b'9' => Some(c - b'0' + 52),
+        b'+' | b'-' => Some(62),
+        b'/' | b'_' => Some(63),
+        _ => None
+    };
+    let mut out = Vec::with_capacity(text.len() * 3 / 4);
+    let (mut bits, mut count) = (0u32, 0);
+    for c in text.bytes().filter(|c| !c.is_ascii_whitespace() && *c != b'=') {
+        let v = value(c).ok_or_else(|| format!("invalid base64 character '{}'", c as char))?;
+        bits = (bits << 6) | v as u32;
+        count += 6;
+        if count >= 8 {
+            count -= 8;
+            out.push((bits >> count) as u8);
+        }
+    }
+    Ok(out)
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> Result<u32, String> {
+    bytes.get(offset..offset + 4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .ok_or_else(|| "truncated .glb file".to_string())
+}
+
+// the JSON document and the binary chunk of a .glb, or the whole text of a .gltf
+fn split_glb(bytes: &[u8]) -> Result<(Json, Option<&[u8]>), String> {
+    if !bytes.starts_with(GLB_MAGIC) {
+        let text = std::str::from_utf8(bytes).map_err(|_| "a .gltf file must be UTF-8 JSON".to_string())?;
+        return Ok((Json::parse(text)?, None));
+    }
+    let version = read_u32(bytes, 4)?;
+    if version != 2 {
+        return Err(format!("unsupported .glb version {}", version));
+    }
+    let mut offset = 12;
+    let (mut json, mut bin) = (None, None);
+    while offset + 8 <= bytes.len() {
+        let length = read_u32(bytes, offset)? as usize;
+        let kind = read_u32(bytes, offset + 4)?;
+        let end = (offset + 8).checked_add(length).ok_or_else(|| "truncated .glb chunk".to_string())?;
+        let data = bytes.get(offset + 8..end).ok_or_else(|| "truncated .glb chunk".to_string())?;
+        match kind {
+            CHUNK_JSON => json = Some(data),
+            CHUNK_BIN => bin = Some(data),
+            _ => ()
+        }
+        offset = end;
+    }
+    let json = json.ok_or_else(|| ".glb file without JSON chunk".to_string())?;
+    let text = std::str::from_utf8(json).map_err(|_| ".glb JSON chunk is not UTF-8".to_string())?;
+    Ok((Json::parse(text)?, bin))
+}
+
+fn list<'a>(document: &'a Json, key: &str) -> &'a [Json] {
+    document.get(key).and_then(|v| v.as_array()).unwrap_or(&[])
+}
+
+fn index(value: &Json, key: &str) -> Option<usize> {
+    value.get(key).and_then(|v| v.as_usize())
+}
+
+struct Importer<'a> {
+    document: &'a Json,
+    buffers: Vec<Vec<u8>>,
+    meshes: Vec<Option<Arc<dyn Hittable>>>,
+    scene: GltfScene,
+    aspect_ratio: f32
+}
+
+impl Importer<'_> {
+    fn warn(&mut self, warning: String) {
+        if !self.scene.warnings.contains(&warning) {
+            self.scene.warnings.push(warning);
+        }
+    }
+
+    // accessor values as floats, with the number of components per element
+    fn accessor(&self, i: usize) -> Result<(Vec<f32>, usize), String> {
+        let accessor = list(self.document, "accessors").get(i).ok_or_else(|| format!("accessor {} does not exist", i))?;
+        if accessor.get("sparse").is_some() {
+            return Err(format!("sparse accessor {} is not supported", i));
+        }
+        let count = index(accessor, "count").ok_or_else(|| format!("accessor {} has no count", i))?;
+        let components = match accessor.get("type").and_then(|t| t.as_str()) {
+            Some("SCALAR") => 1,
+            Some("VEC2") => 2,
+            Some("VEC3") => 3,
+            Some("VEC4") => 4,
+            other => return Err(format!("accessor {} has unsupported type {:?}", i, other))
+        };
+        let component_type = index(accessor, "componentType").unwrap_or(0);
+        let size = match component_type {
+            5120 | 5121 => 1,
+            5122 | 5123 => 2,
+            5125 | 5126 => 4,
+            _ => return Err(format!("accessor {} has unsupported component type {}", i, component_type))
+        };
+        let normalized = accessor.get("normalized").and_then(|n| n.as_bool()).unwrap_or(false);
+
+        let too_big = || format!("accessor {} is too big", i);
+        let length = count.checked_mul(components).ok_or_else(too_big)?;
+        // accessors without buffer view are all zeros, with nothing else to bound their count
+        let view = match index(accessor, "bufferView") {
+            Some(view) => view,
+            None if length <= MAX_ZERO_VALUES => return Ok((vec![0.0; length], components)),
+            None => return Err(too_big())
+        };
+        let view = list(self.document, "bufferViews").get(view).ok_or_else(|| format!("buffer view {} does not exist", view))?;
+        let buffer = index(view, "buffer").and_then(|b| self.buffers.get(b)).ok_or_else(|| format!("buffer of accessor {} does not exist", i))?;
+        let start = index(view, "byteOffset").unwrap_or(0) + index(accessor, "byteOffset").unwrap_or(0);
+        let stride = index(view, "byteStride").unwrap_or(size * components);
+        // the end of the last element, checked before anything is allocated for the count
+        let end = match count.checked_sub(1) {
+            None => Some(start),
+            Some(last) => last.checked_mul(stride)
+                .and_then(|offset| offset.checked_add(start))
+                .and_then(|offset| offset.checked_add(components * size))
+        }.ok_or_else(too_big)?;
+        if end > buffer.len() {
+            return Err(format!("accessor {} reads past its buffer", i));
+        }
+
+        let mut values = Vec::with_capacity(length);
+        for element in 0..count {
+            for component in 0..components {
+                let at = start + element * stride + component * size;
+                let b = buffer.get(at..at + size).ok_or_else(|| format!("accessor {} reads past its buffer", i))?;
+                let value = match component_type {
+                    5120 => if normalized { (b[0] as i8 as f32 / 127.0).max(-1.0) } else { b[0] as i8 as f32 },
+                    5121 => if normalized { b[0] as f32 / 255.0 } else { b[0] as f32 },
+                    5122 => {
+                        let v = i16::from_le_bytes([b[0], b[1]]) as f32;
+                        if normalized { (v / 32767.0).max(-1.0) } else { v }
+                    },
+                    5123 => {
+                        let v = u16::from_le_bytes([b[0], b[1]]) as f32;
+                        if normalized { v / 65535.0 } else { v }
+                    },
+                    5125 => u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f32,
+                    _ => f32::from_le_bytes([b[0], b[1], b[2], b[3]])
+                };
+                values.push(value);
+            }
+        }
+        Ok((values, components))
+    }
+
+    fn vectors(&self, i: usize) -> Result<Vec<Vec3>, String> {
+        let (values, components) = self.accessor(i)?;
+        if components != 3 {
+            return Err(format!("accessor {} should hold 3D vectors", i));
+        }
+        Ok(values.chunks(3).map(|v| Vec3{x: v[0], y: v[1], z: v[2]}).collect())
+    }
+
+    fn material(&mut self, i: Option<usize>) -> Box<dyn Material> {
+        let mut principled = Principled::new(Color{r: 1.0, g: 1.0, b: 1.0});
+        principled.metallic = Box::new(SolidColor::gray(1.0));
+        principled.roughness = Box::new(SolidColor::gray(1.0));
+        let material = match i.and_then(|i| list(self.document, "materials").get(i)) {
+            Some(material) => material,
+            None => return Box::new(principled)
+        };
+        let name = material.get("name").and_then(|n| n.as_str()).unwrap_or("unnamed").to_string();
+
+        if let Some(pbr) = material.get("pbrMetallicRoughness") {
+            if let Some(c) = pbr.get("baseColorFactor").and_then(|c| c.as_f32_array()).filter(|c| c.len() >= 3) {
+                principled.base_color = Box::new(SolidColor::new(c[0], c[1], c[2]));
+            }
+            if let Some(m) = pbr.get("metallicFactor").and_then(|m| m.as_f32()) {
+                principled.metallic = Box::new(SolidColor::gray(m));
+            }
+            if let Some(r) = pbr.get("roughnessFactor").and_then(|r| r.as_f32()) {
+                principled.roughness = Box::new(SolidColor::gray(r));
+            }
+            if pbr.get("baseColorTexture").is_some() || pbr.get("metallicRoughnessTexture").is_some() {
+                self.warn(format!("material '{}': textures are not supported, using its factors", name));
+            }
+        }
+        if let Some(extensions) = material.get("extensions") {
+            if let Some(t) = extensions.get("KHR_materials_transmission").and_then(|t| t.get("transmissionFactor")).and_then(|t| t.as_f32()) {
+                principled.transmission = Box::new(SolidColor::gray(t));
+            }
+            if let Some(ior) = extensions.get("KHR_materials_ior").and_then(|t| t.get("ior")).and_then(|t| t.as_f32()) {
+                principled.ior = ior;
+            }
+        }
+        if material.get("normalTexture").is_some() || material.get("occlusionTexture").is_some() {
+            self.warn(format!("material '{}': normal and occlusion textures are not supported", name));
+        }
+        let emissive = material.get("emissiveFactor").and_then(|e| e.as_f32_array()).unwrap_or_default();
+        if emissive.iter().any(|e| *e > 0.0) || material.get("emissiveTexture").is_some() {
+            self.warn(format!("material '{}': emission is not supported", name));
+        }
+        if let Some(mode) = material.get("alphaMode").and_then(|m| m.as_str()).filter(|m| *m != "OPAQUE") {
+            self.warn(format!("material '{}': alpha mode {} is rendered opaque", name, mode));
+        }
+        match material.get("name").and_then(|n| n.as_str()) {
+            Some(name) => Box::new(Named{ name: name.to_string(), material: Box::new(principled) }),
+            None => Box::new(principled)
+        }
+    }
+
+    fn primitive(&mut self, mesh: usize, primitive: &Json) -> Result<Option<Mesh>, String> {
+        let mode = index(primitive, "mode").unwrap_or(4);
+        if mode < 4 {
+            self.warn(format!("mesh {}: points and lines are skipped", mesh));
+            return Ok(None);
+        }
+        let attributes = primitive.get("attributes").ok_or_else(|| format!("mesh {} has a primitive without attributes", mesh))?;
+        let positions = self.vectors(index(attributes, "POSITION").ok_or_else(|| format!("mesh {} has a primitive without positions", mesh))?)?;
+        let indices: Vec<usize> = match index(primitive, "indices") {
+            Some(i) => self.accessor(i)?.0.iter().map(|i| *i as usize).collect(),
+            None => (0..positions.len()).collect()
+        };
+        let triangles: Vec<[usize; 3]> = match mode {
+            // strips alternate their winding
+            5 => (2..indices.len()).map(|i| if i % 2 == 0 {
+                [indices[i - 2], indices[i - 1], indices[i]]
+            } else {
+                [indices[i - 1], indices[i - 2], indices[i]]
+            }).collect(),
+            6 => (2..indices.len()).map(|i| [indices[0], indices[i - 1], indices[i]]).collect(),
+            _ => indices.chunks_exact(3).map(|t| [t[0], t[1], t[2]]).collect()
+        };
+
+        let material = self.material(index(primitive, "material"));
+        let mut result = Mesh::new(positions, triangles, material)?;
+        if let Some(i) = index(attributes, "NORMAL") {
+            result = result.with_normals(self.vectors(i)?)?;
+        }
+        if let Some(i) = index(attributes, "TEXCOORD_0") {
+            let (values, components) = self.accessor(i)?;
+            if components != 2 {
+                return Err(format!("accessor {} should hold 2D texture coordinates", i));
+            }
+            result = result.with_uvs(values.chunks(2).map(|uv| (uv[0], uv[1])).collect())?;
+        }
+        Ok(Some(result))
+    }
+
+    // every primitive of a mesh, built once and shared by all nodes using it
+    fn mesh(&mut self, i: usize) -> Result<Arc<dyn Hittable>, String> {
+        if let Some(Some(mesh)) = self.meshes.get(i) {
+            return Ok(mesh.clone());
+        }
+        let mesh = list(self.document, "meshes").get(i).ok_or_else(|| format!("mesh {} does not exist", i))?;
+        let mut hitables: Vec<Box<dyn Hittable>> = Vec::new();
+        for primitive in list(mesh, "primitives") {
+            if let Some(primitive) = self.primitive(i, primitive)? {
+                hitables.push(Box::new(primitive));
+            }
+        }
+        let shared = Instance::share(HittableList::new(hitables));
+        if self.meshes.len() <= i {
+            self.meshes.resize(i + 1, None);
+        }
+        self.meshes[i] = Some(shared.clone());
+        Ok(shared)
+    }
+
+    fn local_transform(node: &Json) -> Transform {
+        if let Some(m) = node.get("matrix").and_then(|m| m.as_f32_array()).filter(|m| m.len() == 16) {
+            let mut values = [0.0; 16];
+            values.copy_from_slice(&m);
+            return Transform::from_matrix(Mat4::from_column_major(&values)).unwrap_or_else(Transform::identity);
+        }
+        let vector = |key: &str, default: Vec3| match node.get(key).and_then(|v| v.as_f32_array()) {
+            Some(v) if v.len() == 3 => Vec3{x: v[0], y: v[1], z: v[2]},
+            _ => default
+        };
+        let translation = vector("translation", Vec3{x: 0.0, y: 0.0, z: 0.0});
+        let scale = vector("scale", Vec3{x: 1.0, y: 1.0, z: 1.0});
+        let rotation = match node.get("rotation").and_then(|r| r.as_f32_array()) {
+            Some(q) if q.len() == 4 => Transform::rotate_quaternion(q[0], q[1], q[2], q[3]),
+            _ => Transform::identity()
+        };
+        Transform::translate(translation) * rotation * Transform::scale(scale)
+    }
+
+    fn camera(&mut self, i: usize, world: &Transform) {
+        let camera = match list(self.document, "cameras").get(i) {
+            Some(camera) => camera,
+            None => return self.warn(format!("camera {} does not exist", i))
+        };
+        // glTF cameras look down -z with +y up
+        let lookfrom = world.point(&Vec3{x: 0.0, y: 0.0, z: 0.0});
+        let lookat = lookfrom + world.vector(&Vec3{x: 0.0, y: 0.0, z: -1.0});
+        let vup = world.vector(&Vec3{x: 0.0, y: 1.0, z: 0.0});
+        let number = |parameters: &Json, key: &str| parameters.get(key).and_then(|v| v.as_f32());
+        if let Some(perspective) = camera.get("perspective") {
+            let vfov = number(perspective, "yfov").unwrap_or(0.8).to_degrees();
+            let aspect_ratio = number(perspective, "aspectRatio").unwrap_or(self.aspect_ratio);
+            self.scene.cameras.push(Box::new(PerspectiveCamera::new(lookfrom, lookat, vup, vfov, aspect_ratio, 0.0, 1.0)));
+        } else if let Some(orthographic) = camera.get("orthographic") {
+            // the magnifications are half the width and height of the view
+            let ymag = number(orthographic, "ymag").unwrap_or(1.0);
+            let xmag = number(orthographic, "xmag").unwrap_or(ymag * self.aspect_ratio);
+            self.scene.cameras.push(Box::new(OrthographicCamera::new(lookfrom, lookat, vup, 2.0 * ymag, xmag / ymag)));
+        } else {
+            self.warn(format!("camera {} has neither perspective nor orthographic parameters", i));
+        }
+    }
+
+    fn light(&mut self, i: usize, world: &Transform) {
+        let lights = self.document.get("extensions")
+            .and_then(|e| e.get("KHR_lights_punctual"))
+            .map(|l| list(l, "lights"))
+            .unwrap_or(&[]);
+        let light = match lights.get(i) {
+            Some(light) => light,
+            None => return self.warn(format!("light {} does not exist", i))
+        };
+        let kind = match light.get("type").and_then(|t| t.as_str()) {
+            Some("directional") => LightKind::Directional,
+            Some("point") => LightKind::Point,
+            Some("spot") => {
+                let spot = light.get("spot");
+                let angle = |key: &str, default: f32| spot.and_then(|s| s.get(key)).and_then(|a| a.as_f32()).unwrap_or(default);
+                LightKind::Spot{ inner_cone_angle: angle("innerConeAngle", 0.0), outer_cone_angle: angle("outerConeAngle", std::f32::consts::FRAC_PI_4) }
+            },
+            other => return self.warn(format!("light {}: unknown type {:?}", i, other))
+        };
+        if kind != LightKind::Directional {
+            self.warn("point and spot lights are imported but not rendered".to_string());
+        }
+        let color = light.get("color").and_then(|c| c.as_f32_array()).filter(|c| c.len() == 3).unwrap_or_else(|| vec![1.0; 3]);
+        self.scene.lights.push(PunctualLight{
+            kind,
+            color: Color{r: color[0], g: color[1], b: color[2]},
+            intensity: light.get("intensity").and_then(|v| v.as_f32()).unwrap_or(1.0),
+            position: world.point(&Vec3{x: 0.0, y: 0.0, z: 0.0}),
+            direction: world.vector(&Vec3{x: 0.0, y: 0.0, z: -1.0}).unit_vector(),
+            range: light.get("range").and_then(|r| r.as_f32())
+        });
+    }
+
+    fn node(&mut self, i: usize, parent: &Transform, depth: usize) -> Result<(), String> {
+        if depth > 64 {
+            return Err("node hierarchy is too deep or cyclic".to_string());
+        }
+        let node = list(self.document, "nodes").get(i).ok_or_else(|| format!("node {} does not exist", i))?;
+        let world = *parent * Self::local_transform(node);
+        if let Some(mesh) = index(node, "mesh") {
+            let mesh = self.mesh(mesh)?;
+            self.scene.objects.push(Box::new(Instance::new(mesh, world)));
+        }
+        if let Some(camera) = index(node, "camera") {
+            self.camera(camera, &world);
+        }
+        if let Some(light) = node.get("extensions").and_then(|e| e.get("KHR_lights_punctual")).and_then(|l| index(l, "light")) {
+            self.light(light, &world);
+        }
+        if node.get("skin").is_some() || node.get("weights").is_some() {
+            self.warn(format!("node {}: skinning and morph targets are ignored", i));
+        }
+        for child in list(node, "children").iter().filter_map(|c| c.as_usize()) {
+            self.node(child, &world, depth + 1)?;
+        }
+        Ok(())
+    }
+}
+
+/*
+    Imports the default scene of a glTF file. `aspect_ratio` is used for
+    cameras which don't specify one, usually the aspect of the canvas.
+*/
+pub fn import_gltf(bytes: &[u8], aspect_ratio: f32) -> Result<GltfScene, String> {
+    let (document, bin) = split_glb(bytes)?;
+    let version = document.get("asset").and_then(|a| a.get("version")).and_then(|v| v.as_str()).unwrap_or("");
+    if !version.starts_with('2') {
+        return Err(format!("unsupported glTF version '{}'", version));
+    }
+
+    let mut warnings = Vec::new();
+    let required: Vec<&str> = list(&document, "extensionsRequired").iter().filter_map(|e| e.as_str()).collect();
+    for extension in list(&document, "extensionsUsed").iter().filter_map(|e| e.as_str()) {
+        if SUPPORTED_EXTENSIONS.contains(&extension) {
+            continue;
+        }
+        if required.contains(&extension) {
+            warnings.push(format!("required extension {} is not supported, the scene may look wrong", extension));
+        } else {
+            warnings.push(format!("extension {} is not supported and was ignored", extension));
+        }
+    }
+
+    let mut buffers = Vec::new();
+    for (i, buffer) in list(&document, "buffers").iter().enumerate() {
+        let data = match buffer.get("uri").and_then(|u| u.as_str()) {
+            Some(uri) if uri.starts_with("data:") => {
+                let (_, encoded) = uri.split_once(',').ok_or_else(|| format!("buffer {} has a malformed data URI", i))?;
+                base64_decode(encoded)?
+            },
+            Some(uri) => return Err(format!("buffer {} refers to the external file '{}', embed it or export a .glb", i, uri)),
+            None => bin.ok_or_else(|| format!("buffer {} has no URI and there is no binary chunk", i))?.to_vec()
+        };
+        buffers.push(data);
+    }
+
+    let mut importer = Importer{
+        document: &document,
+        buffers,
+        meshes: Vec::new(),
+        scene: GltfScene{ objects: Vec::new(), cameras: Vec::new(), lights: Vec::new(), warnings },
+        aspect_ratio
+    };
+    let roots: Vec<usize> = match index(&document, "scene").or(if list(&document, "scenes").is_empty() { None } else { Some(0) }) {
+        Some(scene) => list(&document, "scenes").get(scene)
+            .map(|s| list(s, "nodes").iter().filter_map(|n| n.as_usize()).collect())
+            .ok_or_else(|| format!("scene {} does not exist", scene))?,
+        // without scenes every node nobody has as child is a root
+        None => {
+            let nodes = list(&document, "nodes");
+            let children: Vec<usize> = nodes.iter().flat_map(|n| list(n, "children").iter().filter_map(|c| c.as_usize())).collect();
+            (0..nodes.len()).filter(|n| !children.contains(n)).collect()
+        }
+    };
+    for root in roots {
+        importer.node(root, &Transform::identity(), 0)?;
+    }
+    Ok(importer.scene)
+}
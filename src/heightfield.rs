@@ -0,0 +1,185 @@
+use crate::aabb::Aabb;
+use crate::hittable::{Hittable, HitRecord};
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::triangle::intersect_triangle;
+use crate::vec3::Vec3;
+
+/*
+    Terrain from a grid of heights between 0 and 1, spanning `size.x` by
+    `size.z` from the origin and rising up to `size.y`. Every cell is split
+    into two triangles with normals interpolated between the samples.
+
+    Rays walk the cells they cross with a 2D DDA and only test the triangles
+    of cells whose height range they pass through.
+    Amanatides, Woo. "A Fast Voxel Traversal Algorithm for Ray Tracing" (1987)
+
+    u is the height and v the slope, 0 where flat and 1 where vertical, for
+    textures like `TerrainTexture` picking grass, rock or snow.
+*/
+pub struct Heightfield {
+    nx: usize,
+    nz: usize,
+    heights: Vec<f32>,
+    normals: Vec<Vec3>,
+    // lowest and highest point of each cell
+    ranges: Vec<(f32, f32)>,
+    // of the whole field, kept so rays don't fold over every cell
+    bounds: Aabb,
+    pub size: Vec3,
+    pub material: Box<dyn Material>
+}
+
+impl Heightfield {
+    // `heights` has nx samples along x for each of the nz rows along z
+    pub fn new(nx: usize, nz: usize, heights: Vec<f32>, size: Vec3, material: Box<dyn Material>) -> Result<Self, String> {
+        if nx < 2 || nz < 2 || heights.len() != nx * nz {
+            return Err(format!("heightfield of {}x{} needs at least 2x2 and {} samples, got {}", nx, nz, nx * nz, heights.len()));
+        }
+        let origin = Vec3{x: 0.0, y: 0.0, z: 0.0};
+        let mut field = Self { nx, nz, heights, normals: Vec::new(), ranges: Vec::new(), bounds: Aabb::new(origin, origin), size, material };
+        field.normals = (0..nx * nz).map(|i| field.vertex_normal(i % nx, i / nx)).collect();
+        field.ranges = (0..(nx - 1) * (nz - 1)).map(|c| {
+            let (i, j) = (c % (nx - 1), c / (nx - 1));
+            let corners = [field.vertex(i, j).y, field.vertex(i + 1, j).y, field.vertex(i, j + 1).y, field.vertex(i + 1, j + 1).y];
+            (corners.iter().cloned().fold(f32::INFINITY, f32::min), corners.iter().cloned().fold(f32::NEG_INFINITY, f32::max))
+        }).collect();
+        let low = field.ranges.iter().map(|r| r.0).fold(f32::INFINITY, f32::min);
+        let high = field.ranges.iter().map(|r| r.1).fold(f32::NEG_INFINITY, f32::max);
+        field.bounds = Aabb::new(Vec3{x: 0.0, y: low, z: 0.0}, Vec3{x: size.x, y: high, z: size.z}).pad(1e-4);
+        Ok(field)
+    }
+
+    // one byte per pixel, row by row, black is the lowest point
+    pub fn from_gray(pixels: &[u8], width: usize, height: usize, size: Vec3, material: Box<dyn Material>) -> Result<Self, String> {
+        let heights = pixels.iter().map(|p| *p as f32 / 255.0).collect();
+        Self::new(width, height, heights, size, material)
+    }
+
+    // sampled from a function over [0, 1] x [0, 1], e.g. `density::fbm` for procedural terrain
+    pub fn from_fn(nx: usize, nz: usize, f: impl Fn(f32, f32) -> f32, size: Vec3, material: Box<dyn Material>) -> Result<Self, String> {
+        let heights = (0..nx * nz)
+            .map(|i| f((i % nx) as f32 / (nx.max(2) - 1) as f32, (i / nx) as f32 / (nz.max(2) - 1) as f32))
+            .collect();
+        Self::new(nx, nz, heights, size, material)
+    }
+
+    fn cell_size(&self) -> (f32, f32) {
+        (self.size.x / (self.nx - 1) as f32, self.size.z / (self.nz - 1) as f32)
+    }
+
+    fn height(&self, i: usize, j: usize) -> f32 {
+        self.heights[j * self.nx + i] * self.size.y
+    }
+
+    fn vertex(&self, i: usize, j: usize) -> Vec3 {
+        let (dx, dz) = self.cell_size();
+        Vec3{x: i as f32 * dx, y: self.height(i, j), z: j as f32 * dz}
+    }
+
+    // central differences, one sided at the borders
+    fn vertex_normal(&self, i: usize, j: usize) -> Vec3 {
+        let (dx, dz) = self.cell_size();
+        let (i0, i1) = (i.saturating_sub(1), (i + 1).min(self.nx - 1));
+        let (j0, j1) = (j.saturating_sub(1), (j + 1).min(self.nz - 1));
+        let slope_x = (self.height(i1, j) - self.height(i0, j)) / ((i1 - i0) as f32 * dx);
+        let slope_z = (self.height(i, j1) - self.height(i, j0)) / ((j1 - j0) as f32 * dz);
+        Vec3{x: -slope_x, y: 1.0, z: -slope_z}.unit_vector()
+    }
+
+    // the closest of the two triangles of a cell
+    fn hit_cell(&self, r: &Ray, i: usize, j: usize, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        let corners = [(i, j), (i + 1, j), (i + 1, j + 1), (i, j + 1)];
+        let triangles = [[corners[0], corners[3], corners[1]], [corners[1], corners[3], corners[2]]];
+        // ray parameter, triangle and barycentrics of the closest hit
+        let mut closest: Option<(f32, usize, f32, f32)> = None;
+        for (k, [a, b, c]) in triangles.iter().enumerate() {
+            let limit = closest.map_or(t_max, |h| h.0);
+            if let Some((t, b1, b2)) = intersect_triangle(r, &self.vertex(a.0, a.1), &self.vertex(b.0, b.1), &self.vertex(c.0, c.1), t_min, limit) {
+                closest = Some((t, k, b1, b2));
+            }
+        }
+        let (t, k, b1, b2) = closest?;
+        let [a, b, c] = triangles[k];
+
+        let (pa, pb, pc) = (self.vertex(a.0, a.1), self.vertex(b.0, b.1), self.vertex(c.0, c.1));
+        let geometric = (pb - pa).cross(&(pc - pa));
+        let normal = (self.normals[a.1 * self.nx + a.0] * (1.0 - b1 - b2)
+            + self.normals[b.1 * self.nx + b.0] * b1
+            + self.normals[c.1 * self.nx + c.0] * b2).unit_vector();
+        let p = r.point_at_parameter(t);
+        // the triangle decides the side, the interpolated normal the shading
+        let front_face = r.direction.dot(&geometric) < 0.0;
+        Some(HitRecord{
+            p,
+            normal: if front_face { normal } else { normal * -1.0 },
+            t,
+            u: if self.size.y > 0.0 { (p.y / self.size.y).clamp(0.0, 1.0) } else { 0.0 },
+            v: 1.0 - normal.y,
+            tangent: None,
+            front_face,
+            color: None,
+            instance: 0,
+            material: &self.material
+        })
+    }
+}
+
+impl Hittable for Heightfield {
+    fn hit(&self, r: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        let (t0, t1) = self.bounds.interval(r, t_min, t_max)?;
+        let (dx, dz) = self.cell_size();
+        let (cells_x, cells_z) = (self.nx - 1, self.nz - 1);
+
+        let start = r.point_at_parameter(t0);
+        let mut i = ((start.x / dx).floor().max(0.0) as usize).min(cells_x - 1);
+        let mut j = ((start.z / dz).floor().max(0.0) as usize).min(cells_z - 1);
+
+        // ray parameters of the next cell borders along x and z, and between two borders
+        let axis = |origin: f32, direction: f32, cell: usize, size: f32| -> (f32, f32) {
+            if direction.abs() < 1e-12 {
+                return (f32::INFINITY, f32::INFINITY);
+            }
+            let border = if direction > 0.0 { (cell + 1) as f32 * size } else { cell as f32 * size };
+            ((border - origin) / direction, size / direction.abs())
+        };
+        let (mut next_x, delta_x) = axis(r.origin.x, r.direction.x, i, dx);
+        let (mut next_z, delta_z) = axis(r.origin.z, r.direction.z, j, dz);
+
+        let mut t_enter = t0;
+        loop {
+            let t_exit = next_x.min(next_z).min(t1);
+            let (low, high) = self.ranges[j * cells_x + i];
+            let (y0, y1) = (r.origin.y + r.direction.y * t_enter, r.origin.y + r.direction.y * t_exit);
+            if y0.min(y1) <= high && y0.max(y1) >= low {
+                // triangles may be hit just across the border because of rounding
+                let margin = 1e-4 * (t_exit - t_enter).abs().max(1.0);
+                let t_from = (t_enter - margin).max(t_min);
+                if let Some(hit_record) = self.hit_cell(r, i, j, t_from, (t_exit + margin).min(t_max)) {
+                    return Some(hit_record);
+                }
+            }
+            if t_exit >= t1 {
+                return None;
+            }
+            if next_x < next_z {
+                if r.direction.x > 0.0 { i += 1 } else if i == 0 { return None } else { i -= 1 }
+                if i >= cells_x {
+                    return None;
+                }
+                next_x += delta_x;
+            } else {
+                if r.direction.z > 0.0 { j += 1 } else if j == 0 { return None } else { j -= 1 }
+                if j >= cells_z {
+                    return None;
+                }
+                next_z += delta_z;
+            }
+            t_enter = t_exit;
+        }
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(self.bounds)
+    }
+}
@@ -1,3 +1,5 @@
+use crate::aabb::Aabb;
+use crate::color::Color;
 use crate::material::Material;
 use crate::ray::Ray;
 use crate::vec3::Vec3;
@@ -6,7 +8,16 @@ pub struct HitRecord<'a> {
     pub p: Vec3,
     pub normal: Vec3,
     pub t: f32,
+    // surface coordinates for texture lookups
+    pub u: f32,
+    pub v: f32,
+    // direction in which u grows, orients anisotropic materials
+    pub tangent: Option<Vec3>,
     pub front_face: bool,
+    // interpolated vertex color, for meshes that have them
+    pub color: Option<Color>,
+    // tells apart the copies of shared geometry, 0 outside of instances
+    pub instance: usize,
     pub material: &'a Box<dyn Material>
 }
 
@@ -24,4 +35,9 @@ pub trait Hittable {
         println!("hittable");
         return None;
     }
+
+    // bounds of everything the object can be hit at, None for unbounded objects
+    fn bounding_box(&self) -> Option<Aabb> {
+        None
+    }
 }
\ No newline at end of file
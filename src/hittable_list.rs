@@ -1,30 +1,74 @@
+use crate::aabb::Aabb;
 use crate::ray::Ray;
 use crate::hittable::{Hittable,HitRecord};
 
 
+/*
+    Every object gets an id when it is added, which stays the same when
+    other objects are removed, so the viewer can refer to picked objects.
+*/
 pub struct HittableList {
-    pub hitables: Vec<Box<dyn Hittable>>
+    hitables: Vec<Box<dyn Hittable>>,
+    ids: Vec<u32>,
+    next_id: u32
 }
 
+impl HittableList {
+    // the objects get ids 0, 1, 2 and so on
+    pub fn new(hitables: Vec<Box<dyn Hittable>>) -> Self {
+        let ids = (0..hitables.len() as u32).collect();
+        Self { next_id: hitables.len() as u32, hitables, ids }
+    }
 
+    pub fn add(&mut self, object: Box<dyn Hittable>) -> u32 {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.hitables.push(object);
+        self.ids.push(id);
+        id
+    }
 
-impl Hittable for HittableList {
-    fn hit(&self, r: &Ray, t_min: f32, t_max:f32) -> Option<HitRecord> {
-        // let mut hit_anything = false;
-        let mut closest_so_far = t_max;
-        let mut hit_result: Option<HitRecord> = None;
+    pub fn remove(&mut self, id: u32) -> Option<Box<dyn Hittable>> {
+        let index = self.ids.iter().position(|i| *i == id)?;
+        self.ids.remove(index);
+        Some(self.hitables.remove(index))
+    }
 
-        for object in self.hitables.iter() {
-            //let hit_result = object.hit(r, t_min, closest_so_far);
+    pub fn ids(&self) -> &[u32] {
+        &self.ids
+    }
+
+    pub fn get(&self, id: u32) -> Option<&dyn Hittable> {
+        let index = self.ids.iter().position(|i| *i == id)?;
+        Some(self.hitables[index].as_ref())
+    }
+
+    // the closest hit and the id of the object hit
+    pub fn hit_object(&self, r: &Ray, t_min: f32, t_max: f32) -> Option<(u32, HitRecord<'_>)> {
+        let mut closest_so_far = t_max;
+        let mut hit_result = None;
+        for (id, object) in self.ids.iter().zip(self.hitables.iter()) {
             if let Some(hit_record) = object.hit(r, t_min, closest_so_far) {
                 closest_so_far = hit_record.t;
-                hit_result = Some(hit_record);
+                hit_result = Some((*id, hit_record));
             }
         }
         hit_result
     }
 }
 
+impl Hittable for HittableList {
+    fn hit(&self, r: &Ray, t_min: f32, t_max:f32) -> Option<HitRecord> {
+        self.hit_object(r, t_min, t_max).map(|(_, hit_record)| hit_record)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let mut boxes = self.hitables.iter().map(|object| object.bounding_box());
+        let first = boxes.next()??;
+        boxes.try_fold(first, |b, other| Some(b.surrounding(&other?)))
+    }
+}
+
 
 /*
 bool hittable_list::hit(const ray& r, double t_min, double t_max, hit_record& rec) const {
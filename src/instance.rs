@@ -0,0 +1,107 @@
+use crate::aabb::Aabb;
+use crate::hittable::{Hittable, HitRecord};
+use crate::ray::Ray;
+use crate::transform::{Transform, AnimatedTransform};
+use crate::vec3::Vec3;
+use std::sync::Arc;
+
+/*
+    Geometry shared between several places in the scene. Rays are moved into
+    the object's space, so the ray parameter `t` of a hit stays the same and
+    only the hit point and normal go back to world space.
+*/
+pub struct Instance {
+    pub object: Arc<dyn Hittable>,
+    pub transform: Transform
+}
+
+impl Instance {
+    pub fn new(object: Arc<dyn Hittable>, transform: Transform) -> Self {
+        Self { object, transform }
+    }
+
+    // scene objects are only shared within one thread
+    #[allow(clippy::arc_with_non_send_sync)]
+    pub fn share<T: Hittable + 'static>(object: T) -> Arc<dyn Hittable> {
+        Arc::new(object)
+    }
+}
+
+impl Hittable for Instance {
+    fn hit(&self, r: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        hit_transformed(self.object.as_ref(), &self.transform, instance_id(self), r, t_min, t_max)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        transformed_box(self.object.as_ref(), &self.transform)
+    }
+}
+
+fn transformed_box(object: &dyn Hittable, transform: &Transform) -> Option<Aabb> {
+    let corners = object.bounding_box()?.corners();
+    Aabb::from_points(&corners.iter().map(|c| transform.point(c)).collect::<Vec<_>>())
+}
+
+// the instance's address tells it apart from the other copies of its object
+fn instance_id<T>(instance: &T) -> usize {
+    instance as *const T as usize
+}
+
+fn hit_transformed<'a>(object: &'a dyn Hittable, transform: &Transform, id: usize, r: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'a>> {
+    let inverse = transform.invert();
+    let local = Ray{
+        origin: inverse.point(&r.origin),
+        direction: inverse.vector(&r.direction),
+        ..*r
+    };
+    let mut hit_record = object.hit(&local, t_min, t_max)?;
+    hit_record.p = transform.point(&hit_record.p);
+    // the face the ray hits is the same in both spaces, the normal keeps facing it
+    hit_record.normal = transform.normal(&hit_record.normal).unit_vector();
+    hit_record.tangent = hit_record.tangent.map(|tangent| transform.vector(&tangent));
+    // nested instances keep apart the copies of every level
+    hit_record.instance = hit_record.instance.wrapping_mul(31) ^ id;
+    Some(hit_record)
+}
+
+// shared geometry whose transform changes while the shutter is open
+pub struct MovingInstance {
+    pub object: Arc<dyn Hittable>,
+    pub motion: AnimatedTransform
+}
+
+impl MovingInstance {
+    pub fn new(object: Arc<dyn Hittable>, motion: AnimatedTransform) -> Self {
+        Self { object, motion }
+    }
+}
+
+impl Hittable for MovingInstance {
+    fn hit(&self, r: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        hit_transformed(self.object.as_ref(), &self.motion.at(r.time), instance_id(self), r, t_min, t_max)
+    }
+
+    /*
+        Without a change of rotation every point of the object moves and
+        scales along a straight line, so the boxes at both ends hold the whole
+        motion. A turning object stays in the sphere around its pivot through
+        its farthest corner, swept along the translation.
+    */
+    fn bounding_box(&self) -> Option<Aabb> {
+        let motion = &self.motion;
+        let (start, end) = (&motion.start, &motion.end);
+        let ends = transformed_box(self.object.as_ref(), &motion.at(motion.time0))?
+            .surrounding(&transformed_box(self.object.as_ref(), &motion.at(motion.time1))?);
+        let turns = start.degrees != end.degrees
+            || (start.axis.unit_vector() - end.axis.unit_vector()).squared_length() > 0.0;
+        if motion.time1 <= motion.time0 || !turns {
+            return Some(ends);
+        }
+        let largest = |s: Vec3| s.x.abs().max(s.y.abs()).max(s.z.abs());
+        let farthest = self.object.bounding_box()?.corners().iter().map(|c| c.length()).fold(0.0, f32::max);
+        let radius = farthest * largest(start.scale).max(largest(end.scale));
+        let reach = Vec3{x: radius, y: radius, z: radius};
+        let sphere = |center: Vec3| Aabb::new(center - reach, center + reach);
+        Some(sphere(start.translation).surrounding(&sphere(end.translation)))
+    }
+}
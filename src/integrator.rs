@@ -0,0 +1,304 @@
+use crate::color::Color;
+use crate::hittable::{Hittable, HitRecord};
+use crate::material::{Material, Interior};
+use crate::ray::Ray;
+use crate::scene::Scene;
+use crate::spectrum;
+use crate::vec3::Vec3;
+use rand::Rng;
+
+// boundaries a shadow ray may pass through before it counts as blocked
+const MAX_SHADOW_CROSSINGS: usize = 32;
+// tentative collisions before a ray is assumed to leave a heterogeneous medium
+const MAX_TRACKING_STEPS: usize = 1024;
+
+// a medium the current path is inside of: glass, water, fog, ...
+// the material is kept boxed like in `HitRecord`, its box and the instance identify the medium
+#[allow(clippy::borrowed_box)]
+#[derive(Clone, Copy)]
+struct Inside<'a> {
+    id: (usize, usize),
+    interior: Interior,
+    material: &'a Box<dyn Material>
+}
+
+fn black() -> Color {
+    Color{r: 0.0, g: 0.0, b: 0.0}
+}
+
+fn white() -> Color {
+    Color{r: 1.0, g: 1.0, b: 1.0}
+}
+
+// colors are sampled at the path's wavelengths when rendering spectrally
+fn spectral(c: Color, wavelength: Option<f32>) -> Color {
+    match wavelength {
+        Some(hero) => spectrum::sample_rgb(&c, hero),
+        None => c
+    }
+}
+
+fn transmittance(extinction: &Color, distance: f32) -> Color {
+    let channel = |a: f32| if a <= 0.0 { 1.0 } else { (-a * distance).exp() };
+    Color{r: channel(extinction.r), g: channel(extinction.g), b: channel(extinction.b)}
+}
+
+// the ray travels through the enclosing medium of highest priority
+fn current_medium<'a>(media: &[Inside<'a>]) -> Option<Inside<'a>> {
+    media.iter().copied().max_by_key(|m| m.interior.priority)
+}
+
+// copies of shared geometry share the material box, but each is a medium of its own
+fn medium_id(hitt: &HitRecord) -> (usize, usize) {
+    (hitt.material as *const Box<dyn Material> as usize, hitt.instance)
+}
+
+/*
+    The path crosses the boundary of a medium: the index of the medium on the
+    stack if the path was inside it, and the medium on the other side.
+*/
+fn boundary<'a>(media: &[Inside<'a>], id: (usize, usize)) -> (Option<usize>, Option<Interior>) {
+    let inside = media.iter().position(|m| m.id == id);
+    let outer = media.iter()
+        .filter(|m| m.id != id)
+        .max_by_key(|m| m.interior.priority)
+        .map(|m| m.interior);
+    (inside, outer)
+}
+
+fn cross<'a>(media: &mut Vec<Inside<'a>>, inside: Option<usize>, entry: Inside<'a>) {
+    match inside {
+        Some(index) => { media.remove(index); },
+        None => media.push(entry)
+    }
+}
+
+// the boundaries of participating media, surfaces hidden by a medium of higher
+// priority and boundaries between media of the same index don't bend or reflect rays
+fn passes_through(interior: &Interior, outer: &Option<Interior>, wavelength: Option<f32>) -> bool {
+    if interior.index_matched {
+        return true;
+    }
+    match outer {
+        Some(outer) => outer.priority > interior.priority || outer.ior(wavelength) == interior.ior(wavelength),
+        None => interior.ior(wavelength) == 1.0
+    }
+}
+
+pub fn ray_color(r: &Ray, scene: &Scene, depth: i32) -> Color {
+    trace(r, scene, depth, true, &mut Vec::new())
+}
+
+fn trace<'a>(r: &Ray, scene: &'a Scene, depth: i32, sun_visible: bool, media: &mut Vec<Inside<'a>>) -> Color {
+    if depth <= 0 {
+        return black();
+    }
+
+    let medium = current_medium(media);
+    let hit = scene.world.hit(r, 0.001, f32::INFINITY);
+    let distance = hit.as_ref().map_or(f32::INFINITY, |h| h.t * r.direction.length());
+    let absorption = medium.map_or(black(), |m| spectral(m.interior.absorption, r.wavelength));
+
+    // free flight sampling, the path may scatter inside the medium before the next surface
+    if let Some(m) = medium {
+        if let Some(flight) = sample_flight(r, distance, &m) {
+            return transmittance(&absorption, flight) * medium_color(r, flight, m.material, scene, depth, media);
+        }
+    }
+    // outside of all media the path travels through the scene's fog
+    if let (None, Some(fog)) = (medium, &scene.fog) {
+        let xi = rand::thread_rng().gen::<f32>();
+        if let Some(flight) = fog.sample_distance(&r.origin, &r.direction.unit_vector(), distance, xi) {
+            return medium_color(r, flight, &fog.phase_function, scene, depth, media);
+        }
+    }
+
+    let absorbed = transmittance(&absorption, distance);
+    let hitt = match hit {
+        Some(hitt) => hitt,
+        None => {
+            let sky_color = if sun_visible { scene.sky.radiance_with_sun(&r.direction) } else { scene.sky.radiance(&r.direction) };
+            return absorbed * spectral(sky_color, r.wavelength);
+        }
+    };
+
+    if let Some(interior) = hitt.material.interior() {
+        return absorbed * interface_color(r, &hitt, interior, scene, depth, sun_visible, media);
+    }
+
+    let direct = direct_sun(r, &hitt, scene, media);
+    if let Some(scatt) = hitt.material.scatter(r, &hitt) {
+        let a = trace(&scatt.scattered, scene, depth - 1, scatt.specular, media);
+        return absorbed * (direct + a * spectral(scatt.attenuation, r.wavelength));
+    }
+    absorbed * direct
+}
+
+/*
+    Distance to the next real collision in a scattering medium, None when the
+    ray reaches `distance` first. Heterogeneous media use delta tracking with
+    the majorant, where tentative collisions are real with probability
+    density / majorant.
+    Woodcock et al. "Techniques Used in the GEM Code" (1965)
+*/
+fn sample_flight(r: &Ray, distance: f32, medium: &Inside) -> Option<f32> {
+    let majorant = medium.interior.scattering;
+    if majorant <= 0.0 {
+        return None;
+    }
+    let mut rng = rand::thread_rng();
+    let direction = r.direction.unit_vector();
+    let mut t = 0.0;
+    for _ in 0..MAX_TRACKING_STEPS {
+        t -= (1.0 - rng.gen::<f32>()).ln() / majorant;
+        if t >= distance {
+            return None;
+        }
+        match medium.material.density(&(r.origin + direction * t)) {
+            None => return Some(t),
+            Some(density) => if rng.gen::<f32>() * majorant < density {
+                return Some(t);
+            }
+        }
+    }
+    None
+}
+
+/*
+    Transmittance of a scattering medium along `distance`, estimated with ratio
+    tracking when the medium is heterogeneous.
+    Novák et al. "Residual Ratio Tracking for Estimating Attenuation in
+    Participating Media" (2014)
+*/
+fn medium_transmittance(origin: &Vec3, direction: &Vec3, distance: f32, medium: &Inside) -> f32 {
+    let majorant = medium.interior.scattering;
+    if majorant <= 0.0 {
+        return 1.0;
+    }
+    if medium.material.density(origin).is_none() {
+        return (-majorant * distance).exp();
+    }
+    let mut rng = rand::thread_rng();
+    let direction = direction.unit_vector();
+    let mut t = 0.0;
+    let mut visible = 1.0;
+    for _ in 0..MAX_TRACKING_STEPS {
+        t -= (1.0 - rng.gen::<f32>()).ln() / majorant;
+        if t >= distance {
+            break;
+        }
+        let density = medium.material.density(&(*origin + direction * t)).unwrap_or(majorant);
+        visible *= 1.0 - (density / majorant).min(1.0);
+        if visible <= 0.0 {
+            break;
+        }
+    }
+    visible
+}
+
+// scattering by the phase function of a medium, `distance` along the ray
+#[allow(clippy::borrowed_box)]
+fn medium_color<'a>(r: &Ray, distance: f32, material: &'a Box<dyn Material>, scene: &'a Scene, depth: i32, media: &mut Vec<Inside<'a>>) -> Color {
+    let direction = r.direction.unit_vector();
+    let record = HitRecord{
+        p: r.origin + direction * distance,
+        normal: direction * -1.0,
+        t: distance / r.direction.length(),
+        u: 0.0,
+        v: 0.0,
+        tangent: None,
+        front_face: true,
+        color: None,
+        instance: 0,
+        material
+    };
+
+    let direct = direct_sun(r, &record, scene, media);
+    if let Some(scatt) = material.scatter(r, &record) {
+        let a = trace(&scatt.scattered, scene, depth - 1, scatt.specular, media);
+        return direct + a * spectral(scatt.attenuation, r.wavelength);
+    }
+    direct
+}
+
+// sample the sun directly, rays bounced off non specular lobes then must not count it again
+fn direct_sun<'a>(r: &Ray, hitt: &HitRecord, scene: &'a Scene, media: &[Inside<'a>]) -> Color {
+    let sky = &scene.sky;
+    let sun_direction = sky.sample_sun();
+    match hitt.material.eval(r, hitt, &sun_direction) {
+        Some(f) => {
+            let visible = shadow_transmittance(r, hitt.p, sun_direction, scene, media);
+            visible * spectral(f * sky.sun_radiance(), r.wavelength) * sky.sun_solid_angle()
+        },
+        None => black()
+    }
+}
+
+// fraction of light reaching `origin` from `direction`, through media but not through surfaces,
+// at the wavelengths and time of the path `r`
+fn shadow_transmittance<'a>(r: &Ray, origin: Vec3, direction: Vec3, scene: &'a Scene, media: &[Inside<'a>]) -> Color {
+    let wavelength = r.wavelength;
+    let mut media = media.to_vec();
+    let mut ray = Ray{ origin, direction, ..*r };
+    let mut visible = white();
+    for _ in 0..MAX_SHADOW_CROSSINGS {
+        let hit = scene.world.hit(&ray, 0.001, f32::INFINITY);
+        let distance = hit.as_ref().map_or(f32::INFINITY, |h| h.t * ray.direction.length());
+        match (current_medium(&media), &scene.fog) {
+            (Some(m), _) => {
+                let absorption = spectral(m.interior.absorption, wavelength);
+                visible = visible * transmittance(&absorption, distance)
+                    * medium_transmittance(&ray.origin, &ray.direction, distance, &m);
+            },
+            (None, Some(fog)) => visible = visible * fog.transmittance(&ray.origin, &ray.direction.unit_vector(), distance),
+            (None, None) => ()
+        }
+
+        let hitt = match hit {
+            Some(hitt) => hitt,
+            None => return visible
+        };
+        let interior = match hitt.material.interior() {
+            Some(interior) => interior,
+            None => return black()
+        };
+        let id = medium_id(&hitt);
+        let (inside, outer) = boundary(&media, id);
+        if !passes_through(&interior, &outer, wavelength) {
+            return black();
+        }
+        cross(&mut media, inside, Inside{ id, interior, material: hitt.material });
+        ray.origin = hitt.p;
+    }
+    black()
+}
+
+// refraction between the medium behind a dielectric surface and the one in front of it
+fn interface_color<'a>(r: &Ray, hitt: &HitRecord<'a>, interior: Interior, scene: &'a Scene, depth: i32, sun_visible: bool, media: &mut Vec<Inside<'a>>) -> Color {
+    let id = medium_id(hitt);
+    let (inside, outer) = boundary(media, id);
+    let entry = Inside{ id, interior, material: hitt.material };
+
+    if passes_through(&interior, &outer, r.wavelength) {
+        cross(media, inside, entry);
+        let continued = Ray{ origin: hitt.p, direction: r.direction, ..*r };
+        return trace(&continued, scene, depth, sun_visible, media);
+    }
+
+    let inner_idx = interior.ior(r.wavelength);
+    let outer_idx = outer.map_or(1.0, |m| m.ior(r.wavelength));
+    let etai_over_etat = if inside.is_some() { inner_idx / outer_idx } else { outer_idx / inner_idx };
+    if let Some(scatt) = hitt.material.scatter_interface(r, hitt, etai_over_etat) {
+        if scatt.scattered.direction.dot(&hitt.normal) < 0.0 {
+            cross(media, inside, entry);
+        }
+        let mut attenuation = spectral(scatt.attenuation, r.wavelength);
+        // the path only follows the hero wavelength after a dispersive refraction
+        let dispersive = interior.dispersion.is_dispersive() || outer.is_some_and(|m| m.dispersion.is_dispersive());
+        if r.wavelength.is_some() && dispersive {
+            attenuation = attenuation * Color{r: 3.0, g: 0.0, b: 0.0};
+        }
+        return trace(&scatt.scattered, scene, depth - 1, scatt.specular, media) * attenuation;
+    }
+    black()
+}
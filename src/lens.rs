@@ -0,0 +1,416 @@
+/*
+    Aperture shapes for bokeh and a lens system traced element by element.
+
+    Out of focus highlights take the shape of the aperture: a circle, the
+    polygon of the diaphragm's blades or any mask. Towards the edges of the
+    image the lens barrel cuts the aperture down to a cat's eye.
+*/
+
+use crate::camera::{frame, Camera, Shutter};
+use crate::ray::Ray;
+use crate::vec3::Vec3;
+use rand::Rng;
+use std::f32::consts;
+
+// lens samples drawn before giving up on a path through the lens
+const MAX_LENS_SAMPLES: usize = 64;
+
+// grayscale transmission of an aperture, covering [-1, 1] in both directions
+#[derive(Debug, Clone)]
+pub struct ApertureMask {
+    width: usize,
+    height: usize,
+    transmission: Vec<f32>,
+    // running sum of the transmission, to pick pixels by it
+    cdf: Vec<f32>
+}
+
+impl ApertureMask {
+    // rows from top to bottom, 0 is opaque and 255 open
+    pub fn from_gray(pixels: &[u8], width: usize, height: usize) -> Result<Self, String> {
+        if pixels.len() != width * height {
+            return Err(format!("{} pixels for a {}x{} aperture mask", pixels.len(), width, height));
+        }
+        let transmission: Vec<f32> = pixels.iter().map(|p| *p as f32 / 255.0).collect();
+        let cdf: Vec<f32> = transmission.iter().scan(0.0, |sum, t| { *sum += t; Some(*sum) }).collect();
+        if cdf.last().copied().unwrap_or(0.0) <= 0.0 {
+            return Err("the aperture mask lets no light through".to_string());
+        }
+        Ok(Self { width, height, transmission, cdf })
+    }
+
+    fn sample(&self) -> (f32, f32) {
+        let mut rng = rand::thread_rng();
+        let total = self.cdf[self.cdf.len() - 1];
+        let xi = rng.gen::<f32>() * total;
+        let index = self.cdf.partition_point(|c| *c <= xi).min(self.cdf.len() - 1);
+        let (i, j) = (index % self.width, index / self.width);
+        let x = (i as f32 + rng.gen::<f32>()) / self.width as f32;
+        let y = (j as f32 + rng.gen::<f32>()) / self.height as f32;
+        (2.0 * x - 1.0, 1.0 - 2.0 * y)
+    }
+
+    fn contains(&self, x: f32, y: f32) -> bool {
+        if !(-1.0..1.0).contains(&x) || !(-1.0..1.0).contains(&y) {
+            return false;
+        }
+        let i = ((x + 1.0) / 2.0 * self.width as f32) as usize;
+        let j = ((1.0 - y) / 2.0 * self.height as f32) as usize;
+        self.transmission[j.min(self.height - 1) * self.width + i.min(self.width - 1)] > 0.0
+    }
+}
+
+// the shape of the opening, in units of the aperture radius
+#[derive(Debug, Clone)]
+pub enum Aperture {
+    Circle,
+    // regular polygon with a corner at `rotation` degrees counterclockwise from the right
+    Polygon { blades: usize, rotation: f32 },
+    Mask(ApertureMask)
+}
+
+impl Aperture {
+    // uniformly distributed point of the opening
+    pub fn sample(&self) -> (f32, f32) {
+        match self {
+            Aperture::Circle => {
+                let p = Vec3::random_in_unit_disk();
+                (p.x, p.y)
+            },
+            Aperture::Polygon{ blades, rotation } => {
+                let mut rng = rand::thread_rng();
+                let blades = (*blades).max(3);
+                // one of the equal triangles between the center and two corners
+                let corner = |k: usize| {
+                    let angle = rotation.to_radians() + 2.0 * consts::PI * k as f32 / blades as f32;
+                    (angle.cos(), angle.sin())
+                };
+                let k = rng.gen_range(0, blades);
+                let (a, b) = (corner(k), corner(k + 1));
+                let r = rng.gen::<f32>().sqrt();
+                let v = rng.gen::<f32>();
+                (r * ((1.0 - v) * a.0 + v * b.0), r * ((1.0 - v) * a.1 + v * b.1))
+            },
+            Aperture::Mask(mask) => mask.sample()
+        }
+    }
+
+    pub fn contains(&self, x: f32, y: f32) -> bool {
+        match self {
+            Aperture::Circle => x * x + y * y <= 1.0,
+            Aperture::Polygon{ blades, rotation } => {
+                let blades = (*blades).max(3) as f32;
+                let sector = 2.0 * consts::PI / blades;
+                let angle = y.atan2(x) - rotation.to_radians();
+                // distance along the direction to the middle of the nearest side
+                let middle = ((angle / sector).floor() + 0.5) * sector + rotation.to_radians();
+                x * middle.cos() + y * middle.sin() <= (consts::PI / blades).cos()
+            },
+            Aperture::Mask(mask) => mask.contains(x, y)
+        }
+    }
+
+    /*
+        Point of the opening still visible through a lens barrel which is
+        shifted by `vignetting` aperture radii, a cat's eye off the image
+        center. Samples are drawn again until one passes, so the shape is
+        right but the brightness falloff is left out.
+    */
+    pub fn sample_vignetted(&self, vignetting: (f32, f32)) -> (f32, f32) {
+        let (dx, dy) = vignetting;
+        if dx == 0.0 && dy == 0.0 {
+            return self.sample();
+        }
+        for _ in 0..MAX_LENS_SAMPLES {
+            let (x, y) = self.sample();
+            if (x - dx) * (x - dx) + (y - dy) * (y - dy) <= 1.0 {
+                return (x, y);
+            }
+        }
+        (dx / 2.0, dy / 2.0)
+    }
+}
+
+/*
+    One refracting surface of a lens prescription, lengths in millimeters.
+    `thickness` is the distance to the next surface towards the film and `ior`
+    the index of the glass behind the surface, 0 or 1 for air. A radius of 0
+    is the aperture stop, `aperture` is the diameter of the opening.
+*/
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct LensElement {
+    pub radius: f32,
+    pub thickness: f32,
+    pub ior: f32,
+    pub aperture: f32
+}
+
+// a ray in lens space: millimeters, the film at z = 0 and the scene towards -z
+#[derive(Debug, Copy, Clone)]
+struct LensRay {
+    origin: Vec3,
+    direction: Vec3
+}
+
+impl LensRay {
+    fn at(&self, t: f32) -> Vec3 {
+        self.origin + self.direction * t
+    }
+}
+
+fn medium_ior(ior: f32) -> f32 {
+    if ior == 0.0 { 1.0 } else { ior }
+}
+
+// None on total internal reflection
+fn refract(direction: &Vec3, normal: &Vec3, eta: f32) -> Option<Vec3> {
+    let d = direction.unit_vector();
+    let cos_i = -d.dot(normal);
+    let sin2_t = eta * eta * (1.0 - cos_i * cos_i).max(0.0);
+    if sin2_t >= 1.0 {
+        return None;
+    }
+    let cos_t = (1.0 - sin2_t).sqrt();
+    Some(d * eta + *normal * (eta * cos_i - cos_t))
+}
+
+/*
+    Lens system in the layout of pbrt's lens files, first surface facing the
+    scene. Rays are traced through every surface and blocked by the rims.
+    Kolb et al. "A Realistic Camera Model for Computer Graphics" (1995)
+*/
+#[derive(Debug, Clone)]
+pub struct LensSystem {
+    pub elements: Vec<LensElement>,
+    pub stop: Aperture
+}
+
+impl LensSystem {
+    pub fn new(elements: Vec<LensElement>) -> Result<Self, String> {
+        if elements.is_empty() {
+            return Err("a lens system needs at least one element".to_string());
+        }
+        if let Some(e) = elements.iter().find(|e| e.aperture <= 0.0 || e.thickness < 0.0) {
+            return Err(format!("lens element {:?} has no opening or a negative thickness", e));
+        }
+        Ok(Self { elements, stop: Aperture::Circle })
+    }
+
+    // rows of radius, thickness, index and aperture diameter, '#' starts a comment
+    pub fn parse(table: &str) -> Result<Self, String> {
+        let mut elements = Vec::new();
+        for (number, line) in table.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("");
+            let values: Result<Vec<f32>, _> = line.split_whitespace().map(|v| v.parse::<f32>()).collect();
+            match values {
+                Ok(v) if v.is_empty() => (),
+                Ok(v) if v.len() == 4 => elements.push(LensElement{ radius: v[0], thickness: v[1], ior: v[2], aperture: v[3] }),
+                _ => return Err(format!("line {}: expected radius, thickness, index and aperture", number + 1))
+            }
+        }
+        Self::new(elements)
+    }
+
+    // the shape of the stop's opening, scaled to its diameter, lenses without a stop ignore it
+    pub fn with_stop(mut self, stop: Aperture) -> Self {
+        self.stop = stop;
+        self
+    }
+
+    fn front_z(&self) -> f32 {
+        -self.elements.iter().map(|e| e.thickness).sum::<f32>()
+    }
+
+    fn rear(&self) -> &LensElement {
+        &self.elements[self.elements.len() - 1]
+    }
+
+    // where the ray meets surface i, whose vertex is at z, with the normal facing the ray
+    fn intersect(&self, i: usize, z: f32, r: &LensRay) -> Option<(Vec3, Option<Vec3>)> {
+        let element = &self.elements[i];
+        if element.radius == 0.0 {
+            let t = (z - r.origin.z) / r.direction.z;
+            let p = r.at(t);
+            let half = element.aperture / 2.0;
+            return if t > 0.0 && self.stop.contains(p.x / half, p.y / half) { Some((p, None)) } else { None };
+        }
+        let center = Vec3{x: 0.0, y: 0.0, z: z + element.radius};
+        let oc = r.origin - center;
+        let a = r.direction.squared_length();
+        let b = 2.0 * r.direction.dot(&oc);
+        let c = oc.squared_length() - element.radius * element.radius;
+        let discriminant = b * b - 4.0 * a * c;
+        if discriminant < 0.0 {
+            return None;
+        }
+        let (t0, t1) = ((-b - discriminant.sqrt()) / (2.0 * a), (-b + discriminant.sqrt()) / (2.0 * a));
+        // the part of the sphere near the vertex
+        let closer = (r.direction.z > 0.0) != (element.radius < 0.0);
+        let t = if closer { t0.min(t1) } else { t0.max(t1) };
+        if t <= 0.0 {
+            return None;
+        }
+        let p = r.at(t);
+        if p.x * p.x + p.y * p.y > element.aperture * element.aperture / 4.0 {
+            return None;
+        }
+        let n = (p - center).unit_vector();
+        let n = if n.dot(&r.direction) > 0.0 { n * -1.0 } else { n };
+        Some((p, Some(n)))
+    }
+
+    // from the film out of the front element, None when a rim or the stop blocks the ray
+    fn trace_from_film(&self, r: &LensRay) -> Option<LensRay> {
+        let mut r = *r;
+        let mut z = 0.0;
+        for i in (0..self.elements.len()).rev() {
+            z -= self.elements[i].thickness;
+            let (p, normal) = self.intersect(i, z, &r)?;
+            r.origin = p;
+            if let Some(n) = normal {
+                let ior_after = if i > 0 { medium_ior(self.elements[i - 1].ior) } else { 1.0 };
+                r.direction = refract(&r.direction, &n, medium_ior(self.elements[i].ior) / ior_after)?;
+            }
+        }
+        Some(r)
+    }
+
+    fn trace_from_scene(&self, r: &LensRay) -> Option<LensRay> {
+        let mut r = *r;
+        let mut z = self.front_z();
+        for i in 0..self.elements.len() {
+            let (p, normal) = self.intersect(i, z, &r)?;
+            r.origin = p;
+            if let Some(n) = normal {
+                let ior_before = if i > 0 { medium_ior(self.elements[i - 1].ior) } else { 1.0 };
+                r.direction = refract(&r.direction, &n, ior_before / medium_ior(self.elements[i].ior))?;
+            }
+            z += self.elements[i].thickness;
+        }
+        Some(r)
+    }
+
+    /*
+        Principal plane and focal point of the lens treated as a thick lens,
+        from a paraxial ray through either side: [scene side, film side].
+    */
+    fn cardinal_points(&self) -> Option<([f32; 2], [f32; 2])> {
+        let height = 0.01 * self.rear().aperture;
+        let points = |r_in: LensRay, r_out: LensRay| {
+            let focal = r_out.at(-r_out.origin.x / r_out.direction.x).z;
+            let principal = r_out.at((r_in.origin.x - r_out.origin.x) / r_out.direction.x).z;
+            (principal, focal)
+        };
+        let from_scene = LensRay{ origin: Vec3{x: height, y: 0.0, z: self.front_z() - 1.0}, direction: Vec3{x: 0.0, y: 0.0, z: 1.0} };
+        let (p0, f0) = points(from_scene, self.trace_from_scene(&from_scene)?);
+        let from_film = LensRay{ origin: Vec3{x: height, y: 0.0, z: 1.0 - self.rear().thickness}, direction: Vec3{x: 0.0, y: 0.0, z: -1.0} };
+        let (p1, f1) = points(from_film, self.trace_from_film(&from_film)?);
+        Some(([p0, p1], [f0, f1]))
+    }
+
+    // effective focal length in millimeters
+    pub fn focal_length(&self) -> Option<f32> {
+        let (principal, focal) = self.cardinal_points()?;
+        Some(focal[0] - principal[0])
+    }
+
+    // moves the film so objects `distance` millimeters in front of it are sharp
+    pub fn focus(mut self, distance: f32) -> Result<Self, String> {
+        let (p, f) = self.cardinal_points().ok_or_else(|| "the lens system doesn't pass paraxial rays".to_string())?;
+        let focal_length = f[0] - p[0];
+        let z = -distance;
+        let c = (p[1] - z - p[0]) * (p[1] - z - 4.0 * focal_length - p[0]);
+        if c < 0.0 {
+            return Err(format!("can't focus at {} mm, closer than the lens allows", distance));
+        }
+        let delta = 0.5 * (p[1] - z + p[0] - c.sqrt());
+        let last = self.elements.len() - 1;
+        self.elements[last].thickness += delta;
+        if self.elements[last].thickness <= 0.0 {
+            return Err(format!("can't focus at {} mm", distance));
+        }
+        Ok(self)
+    }
+}
+
+/*
+    Camera behind a lens system. The film is `film_diagonal` millimeters
+    across and a millimeter is `units_per_mm` scene units, 0.001 for scenes
+    in meters. Rays the lens blocks carry no light, so the corners darken
+    where the lens vignettes.
+*/
+pub struct RealisticCamera {
+    origin: Vec3,
+    u: Vec3,
+    v: Vec3,
+    w: Vec3,
+    lens: LensSystem,
+    film_width: f32,
+    film_height: f32,
+    units_per_mm: f32,
+    shutter: Shutter
+}
+
+impl RealisticCamera {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(lookfrom: Vec3, lookat: Vec3, vup: Vec3, lens: LensSystem, film_diagonal: f32, aspect_ratio: f32, focus_dist: f32, units_per_mm: f32) -> Result<Self, String> {
+        let (u, v, w) = frame(lookfrom, lookat, vup);
+        let film_height = film_diagonal / (1.0 + aspect_ratio * aspect_ratio).sqrt();
+        let lens = lens.focus(focus_dist / units_per_mm)?;
+        Ok(Self { origin: lookfrom, u, v, w, lens, film_width: film_height * aspect_ratio, film_height, units_per_mm,
+            shutter: Shutter{ open: 0.0, close: 0.0 } })
+    }
+
+    pub fn with_shutter(mut self, open: f32, close: f32) -> Self {
+        self.shutter = Shutter{ open, close };
+        self
+    }
+
+    pub fn lens(&self) -> &LensSystem {
+        &self.lens
+    }
+
+    fn to_world(&self, p: &Vec3) -> Vec3 {
+        self.u * p.x + self.v * p.y + self.w * p.z
+    }
+
+    // the lens turns the image upside down
+    fn film_point(&self, s: f32, t: f32) -> Vec3 {
+        Vec3{x: (0.5 - s) * self.film_width, y: (0.5 - t) * self.film_height, z: 0.0}
+    }
+
+    fn world_ray(&self, r: &LensRay) -> Ray {
+        Ray{
+            origin: self.origin + self.to_world(&r.origin) * self.units_per_mm,
+            direction: self.to_world(&r.direction),
+            wavelength: None,
+            time: self.shutter.sample()
+        }
+    }
+}
+
+impl Camera for RealisticCamera {
+    // aims through the lens for picking, a point of the film the lens fully blocks looks straight through it
+    fn get_ray(&self, s: f32, t: f32) -> Ray {
+        for _ in 0..MAX_LENS_SAMPLES {
+            if let Some(r) = self.sample_ray(s, t) {
+                return r;
+            }
+        }
+        let film = self.film_point(s, t);
+        self.world_ray(&LensRay{
+            origin: Vec3{x: -film.x, y: -film.y, z: self.lens.front_z()},
+            direction: Vec3{x: -film.x, y: -film.y, z: -self.lens.rear().thickness}
+        })
+    }
+
+    // one point on the rear element, None when the lens blocks the ray
+    fn sample_ray(&self, s: f32, t: f32) -> Option<Ray> {
+        let film = self.film_point(s, t);
+        let rear = self.lens.rear();
+        let p = Vec3::random_in_unit_disk() * (rear.aperture / 2.0);
+        let target = Vec3{x: p.x, y: p.y, z: -rear.thickness};
+        let r = self.lens.trace_from_film(&LensRay{ origin: film, direction: target - film })?;
+        Some(self.world_ray(&r))
+    }
+}
@@ -3,21 +3,50 @@ pub mod vec3;
 pub mod ray;
 pub mod color;
 pub mod hittable;
+pub mod aabb;
 pub mod sphere;
+pub mod planar;
+pub mod quadric;
+pub mod csg;
+pub mod sdf;
+pub mod triangle;
+pub mod heightfield;
+pub mod json;
+pub mod gltf;
+pub mod ply;
+pub mod stl;
+pub mod pbrt;
+pub mod transform;
+pub mod instance;
 pub mod hittable_list;
 pub mod camera;
+pub mod stereo;
+pub mod lens;
+pub mod film;
+pub mod viewer;
+pub mod orbit;
 pub mod material;
-
-use crate::camera::Camera;
+pub mod microfacet;
+pub mod principled;
+pub mod texture;
+pub mod sky;
+pub mod integrator;
+pub mod medium;
+pub mod density;
+pub mod scene;
+pub mod spectrum;
+
+use crate::film::Film;
 use crate::hittable_list::HittableList;
 use js_sys::Math;
 use crate::sphere::Sphere;
 use crate::hittable::Hittable;
 use crate::vec3::Vec3;
-use crate::ray::Ray;
 use crate::color::Color;
 use crate::material::{Lambertian,Metal,Dielectric};
-use rand::Rng;
+use crate::sky::Sky;
+use crate::scene::Scene;
+use crate::viewer::Viewer;
 
 use wasm_bindgen::Clamped;
 use wasm_bindgen::prelude::*;
@@ -34,33 +63,6 @@ use web_sys::{ImageData};
 #[global_allocator]
 static ALLOC: wee_alloc::WeeAlloc = wee_alloc::WeeAlloc::INIT;
 
-fn ray_color(r: &Ray, world: &dyn Hittable, depth: i32) -> Color {
-    //console::log_1(&JsValue::from_str( &format!("{}",depth) ));
-    if depth <= 0 {
-        return Color{r: 0.0, g: 0.0, b: 0.0};
-    }
-
-    if let Some(hitt) = world.hit(r, 0.001, std::f32::INFINITY) {
-        if let Some(scatt) = hitt.material.scatter(r, &hitt) {
-            let a = ray_color(&scatt.scattered, world, depth -1);
-            let b = scatt.attenuation;
-            return Color{
-                r: a.r * b.r,
-                g: a.g * b.g,
-                b: a.b * b.b
-            }
-        }
-        return Color{
-            r: 0.0,
-            g: 0.0,
-            b: 0.0
-        }
-    }
-    let unit_direction = r.direction.unit_vector();
-    let t = 0.5 * (unit_direction.y + 1.0);
-    return Color{r:1.0, g: 1.0, b:1.0} * (1.0 - t)  + Color{r:0.5, g: 0.7, b:1.0} * (t);
-}
-
 fn clamp(input: f32, min: f32, max: f32) -> f32 {
     if input < min { 
         return min 
@@ -72,11 +74,11 @@ fn clamp(input: f32, min: f32, max: f32) -> f32 {
 }
 
 
-fn write_color(data: &mut Vec<u8>, color :&Color, samples_per_pixel: f32) {
-    let scale = 1.0 / samples_per_pixel;
-    let r = clamp((color.r * scale).sqrt(), 0.0, 0.999);
-    let g = clamp((color.g * scale).sqrt(), 0.0, 0.999);
-    let b = clamp((color.b * scale).sqrt(), 0.0, 0.999);
+pub(crate) fn write_color(data: &mut Vec<u8>, color :&Color, samples_per_pixel: f32, film: &Film) {
+    let color = film.develop(&(color / samples_per_pixel));
+    let r = clamp(color.r.sqrt(), 0.0, 0.999);
+    let g = clamp(color.g.sqrt(), 0.0, 0.999);
+    let b = clamp(color.b.sqrt(), 0.0, 0.999);
 
     data.push((r * 255.0) as u8);
     data.push((g * 255.0) as u8);
@@ -85,20 +87,14 @@ fn write_color(data: &mut Vec<u8>, color :&Color, samples_per_pixel: f32) {
 }
  
 
-fn plot(width: u32, height: u32) -> Vec<u8> {
-    let mut rng = rand::thread_rng();
-    // Image
-    let nx = width;
-    let ny = height; 
-    let samples_per_pixel = 50.0;
-    let max_depth = 50;
-    let mut data: Vec<u8> = Vec::new();
-
+// the spheres the page shows
+pub(crate) fn demo_scene() -> Scene {
     // World
     let material_ground = Lambertian{ albedo: Color{r: 0.8, g: 0.8, b: 0.0 } };
     let material_center = Lambertian{ albedo: Color{r: 0.1, g: 0.2, b: 0.5 } };
-    let material_left   = Dielectric{ ref_idx: 1.5}; //Metal{ albedo: Color{r: 0.8, g: 0.8, b: 0.8 }, fuzz: 0.3 };
-    let material_left2   = Dielectric{ ref_idx: 1.5};
+    let material_left   = Dielectric::new(1.5); //Metal{ albedo: Color{r: 0.8, g: 0.8, b: 0.8 }, fuzz: 0.3 };
+    // the air bubble inside the glass ball wins over the glass around it
+    let material_left2   = Dielectric{ priority: 1, ..Dielectric::new(1.0) };
     let material_right  = Metal{ albedo: Color{r: 0.8, g: 0.6, b: 0.2 }, fuzz: 0.0 };
 
     let mut hitables: Vec<Box<dyn Hittable>> = Vec::new();
@@ -122,7 +118,7 @@ fn plot(width: u32, height: u32) -> Vec<u8> {
 
     hitables.push(Box::new(Sphere {
         center: Vec3{x: -1.0, y: 0.0, z: -1.0},
-        radius: -0.45,
+        radius: 0.45,
         material: Box::new(material_left2)
     }));
 
@@ -156,35 +152,12 @@ fn plot(width: u32, height: u32) -> Vec<u8> {
 
 
 
-    let world = HittableList{hitables};
-    let aspect_ratio = 3.0 / 2.0;
-    let lookfrom = Vec3{x: 3.0, y: 3.0, z: 2.0};
-    let lookat = Vec3{x: 0.0, y: 0.0, z: -1.0};
-    // Camera
-    let cam = Camera::new(
-            lookfrom,
-            lookat,
-            Vec3{x: 0.0, y: 1.0, z: 0.0},
-            20.0, 
-            aspect_ratio,
-            2.0,
-            (lookfrom - lookat).length()
-        );
-
-    for nj in 0..ny {
-        let j = ny - nj - 1;
-        for i in 0..nx {
-            let mut col = Color{ r:0.0, g:0.0, b: 0.0};
-            for _k in 0..(samples_per_pixel as i32) {
-                let u = ((i as f32) + rng.gen_range(0.0, 1.0))  / (nx as f32);
-                let v = ((j as f32) + rng.gen_range(0.0, 1.0)) / (ny as f32);
-                let r = cam.get_ray(u, v);
-                col = ray_color(&r, &world, max_depth) + col;
-            }
-            write_color(&mut data, &col, samples_per_pixel);
-        }
+    Scene{
+        world: HittableList::new(hitables),
+        sky: Sky::new(35.0, 30.0, 3.0),
+        // e.g. Some(Fog::exponential(0.1, 0.0, 2.0, Box::new(HenyeyGreenstein{ albedo: Color{r: 0.9, g: 0.9, b: 0.9}, g: 0.3 })))
+        fog: None
     }
-    data
 }
 
 
@@ -216,7 +189,8 @@ pub fn main_js() -> Result<(), JsValue> {
         .dyn_into::<web_sys::CanvasRenderingContext2d>()
         .unwrap();
 
-    let mut data = plot(width, height);
+    let viewer = Viewer::new(width, height);
+    let mut data = viewer.render();
     let data = ImageData::new_with_u8_clamped_array_and_sh(Clamped(&mut data), width, height)?;
     context.put_image_data(&data, 0.0, 0.0)?;
 
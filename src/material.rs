@@ -3,15 +3,139 @@ use crate::hittable::HitRecord;
 use crate::color::Color;
 use crate::ray::Ray;
 use crate::vec3::Vec3;
+use crate::microfacet::{Frame, Ggx, fresnel_conductor_color, fresnel_dielectric};
+use rand::Rng;
 
 pub struct ScatterRecord {
     pub scattered: Ray,
     pub attenuation: Color,
+    // sampled from a lobe that `eval` leaves out, so lights hit by the
+    // scattered ray still count
+    pub specular: bool,
 }
 
 
 pub trait Material {
+    // what kind of material it is, or its name from the scene file, shown when picking objects
+    fn name(&self) -> &str;
+
     fn scatter(&self, r_in: &Ray, hit_record: &HitRecord) -> Option<ScatterRecord>;
+
+    // BRDF times cosine for light arriving from `direction`, used to sample
+    // lights directly. Specular materials can't be lit that way and return None.
+    fn eval(&self, _r_in: &Ray, _hit_record: &HitRecord, _direction: &Vec3) -> Option<Color> {
+        None
+    }
+
+    // The medium enclosed by a dielectric surface, tracked while a path is inside it
+    fn interior(&self) -> Option<Interior> {
+        None
+    }
+
+    // Scatters at the boundary between two media whose ratio of refractive
+    // indices is given by the integrator's medium stack
+    fn scatter_interface(&self, r_in: &Ray, hit_record: &HitRecord, _etai_over_etat: f32) -> Option<ScatterRecord> {
+        self.scatter(r_in, hit_record)
+    }
+
+    // Extinction at `p` inside a heterogeneous medium, bounded by `Interior::scattering`.
+    // Homogeneous media return None.
+    fn density(&self, _p: &Vec3) -> Option<f32> {
+        None
+    }
+}
+
+// a material under the name the scene file gives it, shown when picking instead of its kind
+pub struct Named {
+    pub name: String,
+    pub material: Box<dyn Material>
+}
+
+impl Material for Named {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn scatter(&self, r_in: &Ray, hit_record: &HitRecord) -> Option<ScatterRecord> {
+        self.material.scatter(r_in, hit_record)
+    }
+
+    fn eval(&self, r_in: &Ray, hit_record: &HitRecord, direction: &Vec3) -> Option<Color> {
+        self.material.eval(r_in, hit_record, direction)
+    }
+
+    fn interior(&self) -> Option<Interior> {
+        self.material.interior()
+    }
+
+    fn scatter_interface(&self, r_in: &Ray, hit_record: &HitRecord, etai_over_etat: f32) -> Option<ScatterRecord> {
+        self.material.scatter_interface(r_in, hit_record, etai_over_etat)
+    }
+
+    fn density(&self, p: &Vec3) -> Option<f32> {
+        self.material.density(p)
+    }
+}
+
+/*
+    Nested dielectrics (a liquid inside a glass) are resolved with interface
+    priorities: inside a medium of higher priority the surfaces of lower
+    priority media are ignored.
+    Schmidt, Budge. "Simple Nested Dielectrics in Ray Traced Images" (2002)
+*/
+#[derive(Debug, Copy, Clone)]
+pub struct Interior {
+    pub ref_idx: f32,
+    pub dispersion: Dispersion,
+    // Beer-Lambert absorption coefficient per unit length
+    pub absorption: Color,
+    // extinction coefficient of a medium scattering light inside, or its
+    // majorant when it varies. The material's `scatter` is its phase function.
+    pub scattering: f32,
+    // the surface only marks where a medium starts, rays always pass it unbent
+    pub index_matched: bool,
+    pub priority: i32
+}
+
+impl Interior {
+    pub fn ior(&self, wavelength: Option<f32>) -> f32 {
+        self.dispersion.ior(self.ref_idx, wavelength)
+    }
+}
+
+// wavelength of the helium d line, where `ref_idx` is measured
+const LAMBDA_D: f32 = 0.5876;
+
+/*
+    Wavelength dependent index of refraction, only spectral rendering can
+    show it. Wavelengths in the formulas are in micrometers.
+*/
+#[derive(Debug, Copy, Clone)]
+pub enum Dispersion {
+    None,
+    // Cauchy: n = ref_idx + B (1 / lambda^2 - 1 / lambda_d^2)
+    Cauchy(f32),
+    // Sellmeier: n^2 = 1 + sum B lambda^2 / (lambda^2 - C), replaces ref_idx
+    Sellmeier([f32; 3], [f32; 3])
+}
+
+impl Dispersion {
+    pub fn ior(&self, ref_idx: f32, wavelength: Option<f32>) -> f32 {
+        let lambda = wavelength.map_or(LAMBDA_D, |nm| nm / 1000.0);
+        match self {
+            Dispersion::None => ref_idx,
+            Dispersion::Cauchy(b) => ref_idx + b * (1.0 / (lambda * lambda) - 1.0 / (LAMBDA_D * LAMBDA_D)),
+            Dispersion::Sellmeier(b, c) => {
+                let l2 = lambda * lambda;
+                let sum: f32 = (0..3).map(|i| b[i] * l2 / (l2 - c[i])).sum();
+                (1.0 + sum).sqrt()
+            }
+        }
+    }
+
+    pub fn is_dispersive(&self) -> bool {
+        !matches!(self, Dispersion::None)
+    }
 }
 
 pub struct Lambertian {
@@ -19,15 +143,62 @@ pub struct Lambertian {
 }
 
 impl Material for Lambertian {
-    fn scatter(&self, _r_in: &Ray, hit_record: &HitRecord) -> Option<ScatterRecord> {
+    fn name(&self) -> &str {
+        "lambertian"
+    }
+
+    fn scatter(&self, r_in: &Ray, hit_record: &HitRecord) -> Option<ScatterRecord> {
         return Some(ScatterRecord{
             attenuation: self.albedo,
+            specular: false,
             scattered: Ray{
                 origin: hit_record.p,
-                direction: hit_record.normal + Vec3::random_in_unit_sphere()
+                direction: hit_record.normal + Vec3::random_in_unit_sphere(),
+                wavelength: r_in.wavelength,
+                time: r_in.time
             }
         });
     }
+
+    fn eval(&self, _r_in: &Ray, hit_record: &HitRecord, direction: &Vec3) -> Option<Color> {
+        let cosine = hit_record.normal.dot(&direction.unit_vector()).max(0.0);
+        Some(self.albedo * (cosine / std::f32::consts::PI))
+    }
+}
+
+// diffuse in the vertex colors of a mesh, `albedo` where the hit has none
+pub struct VertexLambertian {
+    pub albedo: Color
+}
+
+impl VertexLambertian {
+    fn albedo(&self, hit_record: &HitRecord) -> Color {
+        hit_record.color.unwrap_or(self.albedo)
+    }
+}
+
+impl Material for VertexLambertian {
+    fn name(&self) -> &str {
+        "vertex lambertian"
+    }
+
+    fn scatter(&self, r_in: &Ray, hit_record: &HitRecord) -> Option<ScatterRecord> {
+        Some(ScatterRecord{
+            attenuation: self.albedo(hit_record),
+            specular: false,
+            scattered: Ray{
+                origin: hit_record.p,
+                direction: hit_record.normal + Vec3::random_in_unit_sphere(),
+                wavelength: r_in.wavelength,
+                time: r_in.time
+            }
+        })
+    }
+
+    fn eval(&self, _r_in: &Ray, hit_record: &HitRecord, direction: &Vec3) -> Option<Color> {
+        let cosine = hit_record.normal.dot(&direction.unit_vector()).max(0.0);
+        Some(self.albedo(hit_record) * (cosine / std::f32::consts::PI))
+    }
 }
 
 
@@ -37,14 +208,21 @@ pub struct Metal {
 }
 
 impl Material for Metal {
+    fn name(&self) -> &str {
+        "metal"
+    }
+
     fn scatter(&self, r_in: &Ray, hit_record: &HitRecord) -> Option<ScatterRecord> {
         let reflected = r_in.direction.unit_vector().reflect(&hit_record.normal);
         if reflected.dot(&hit_record.normal) > 0.0 {
             return Some(ScatterRecord{
                 attenuation: self.albedo,
+                specular: true,
                 scattered: Ray{
                     origin: hit_record.p,
-                    direction: reflected + Vec3::random_in_unit_sphere() * self.fuzz
+                    direction: reflected + Vec3::random_in_unit_sphere() * self.fuzz,
+                    wavelength: r_in.wavelength,
+                    time: r_in.time
                 }
             });
         } else {
@@ -55,7 +233,51 @@ impl Material for Metal {
 
 
 pub struct Dielectric {
-    pub ref_idx: f32
+    pub ref_idx: f32,
+    pub dispersion: Dispersion,
+    // color of the transmitted light after travelling 1 / density inside
+    pub absorption: Color,
+    pub density: f32,
+    pub priority: i32
+}
+
+impl Dielectric {
+    // clear glass, water, ...
+    pub fn new(ref_idx: f32) -> Self {
+        Self {
+            ref_idx,
+            dispersion: Dispersion::None,
+            absorption: Color{r: 1.0, g: 1.0, b: 1.0},
+            density: 0.0,
+            priority: 0
+        }
+    }
+
+    // Schott N-BK7 crown glass
+    pub fn bk7() -> Self {
+        Self {
+            dispersion: Dispersion::Sellmeier(
+                [1.039_612, 0.231_792_3, 1.010_469_5],
+                [0.006_000_699, 0.020_017_914, 103.560_65]
+            ),
+            ..Self::new(1.5168)
+        }
+    }
+
+    // Schott SF11 dense flint glass, strongly dispersive
+    pub fn dense_flint() -> Self {
+        Self {
+            dispersion: Dispersion::Sellmeier(
+                [1.737_597, 0.313_747_3, 1.898_781],
+                [0.013_188_707, 0.062_306_814, 155.236_3]
+            ),
+            ..Self::new(1.7847)
+        }
+    }
+
+    pub fn ior(&self, wavelength: Option<f32>) -> f32 {
+        self.dispersion.ior(self.ref_idx, wavelength)
+    }
 }
 
 /*
@@ -91,9 +313,33 @@ fn schlick(cosine: f32, ref_idx: f32) -> f32 {
 
 
 impl Material for Dielectric {
+    fn name(&self) -> &str {
+        "dielectric"
+    }
+
     fn scatter(&self, r_in: &Ray, hit_record: &HitRecord) -> Option<ScatterRecord> {
+        let ref_idx = self.ior(r_in.wavelength);
+        let etai_over_etat = if hit_record.front_face { 1.0 / ref_idx } else { ref_idx };
+        self.scatter_interface(r_in, hit_record, etai_over_etat)
+    }
 
-        let etai_over_etat = if hit_record.front_face { (1.0 / self.ref_idx) } else { self.ref_idx};
+    fn interior(&self) -> Option<Interior> {
+        let coefficient = |c: f32| -c.max(1e-4).ln() * self.density;
+        Some(Interior{
+            ref_idx: self.ref_idx,
+            dispersion: self.dispersion,
+            absorption: Color{
+                r: coefficient(self.absorption.r),
+                g: coefficient(self.absorption.g),
+                b: coefficient(self.absorption.b)
+            },
+            scattering: 0.0,
+            index_matched: false,
+            priority: self.priority
+        })
+    }
+
+    fn scatter_interface(&self, r_in: &Ray, hit_record: &HitRecord, etai_over_etat: f32) -> Option<ScatterRecord> {
         let unit_direction = r_in.direction.unit_vector();
 
         /*
@@ -111,9 +357,12 @@ impl Material for Dielectric {
             let reflected = unit_direction.reflect(&hit_record.normal);
             return Some(ScatterRecord{
                 attenuation: Color{r:1.0,g:1.0,b:1.0},
+                specular: true,
                 scattered: Ray{
                     origin: hit_record.p,
-                    direction: reflected
+                    direction: reflected,
+                    wavelength: r_in.wavelength,
+                    time: r_in.time
                 }
             });
         }
@@ -131,9 +380,12 @@ impl Material for Dielectric {
             let reflected = unit_direction.reflect(&hit_record.normal);
             return Some(ScatterRecord{
                 attenuation: Color{r:1.0,g:1.0,b:1.0},
+                specular: true,
                 scattered: Ray{
                     origin: hit_record.p,
-                    direction: reflected
+                    direction: reflected,
+                    wavelength: r_in.wavelength,
+                    time: r_in.time
                 }
             });
         }
@@ -142,11 +394,172 @@ impl Material for Dielectric {
         
         return Some(ScatterRecord{
             attenuation: Color{r:1.0,g:1.0,b:1.0},
+            specular: true,
             scattered: Ray{
                 origin: hit_record.p,
-                direction: refracted
+                direction: refracted,
+                wavelength: r_in.wavelength,
+                time: r_in.time
             }
         });
 
     }
 }
+
+
+/*
+    Physically based GGX materials. Roughness is perceptual (alpha = roughness^2),
+    and sampling visible normals keeps the weights below one.
+*/
+
+pub struct RoughConductor {
+    // complex index of refraction eta + ik per channel
+    pub eta: Color,
+    pub k: Color,
+    pub roughness: f32
+}
+
+impl RoughConductor {
+    pub fn gold(roughness: f32) -> Self {
+        Self {
+            eta: Color{r: 0.143, g: 0.374, b: 1.442},
+            k: Color{r: 3.983, g: 2.385, b: 1.603},
+            roughness
+        }
+    }
+
+    pub fn copper(roughness: f32) -> Self {
+        Self {
+            eta: Color{r: 0.200, g: 0.924, b: 1.102},
+            k: Color{r: 3.912, g: 2.452, b: 2.142},
+            roughness
+        }
+    }
+
+    pub fn aluminum(roughness: f32) -> Self {
+        Self {
+            eta: Color{r: 1.657, g: 0.880, b: 0.521},
+            k: Color{r: 9.224, g: 6.270, b: 4.837},
+            roughness
+        }
+    }
+}
+
+impl Material for RoughConductor {
+    fn name(&self) -> &str {
+        "rough conductor"
+    }
+
+    fn scatter(&self, r_in: &Ray, hit_record: &HitRecord) -> Option<ScatterRecord> {
+        let mut rng = rand::thread_rng();
+        let ggx = Ggx::new(self.roughness);
+        let frame = Frame::new(hit_record.normal);
+        let wo = frame.to_local(&(r_in.direction.unit_vector() * -1.0));
+        if wo.z <= 0.0 {
+            return None;
+        }
+
+        let m = ggx.sample_visible_normal(&wo, rng.gen(), rng.gen());
+        let wi = (wo * -1.0).reflect(&m);
+        if wi.z <= 0.0 {
+            return None;
+        }
+
+        let fresnel = fresnel_conductor_color(wo.dot(&m), &self.eta, &self.k);
+        Some(ScatterRecord{
+            attenuation: fresnel * (ggx.g2(&wo, &wi) / ggx.g1(&wo)),
+            specular: ggx.is_smooth(),
+            scattered: Ray{
+                origin: hit_record.p,
+                direction: frame.to_world(&wi),
+                wavelength: r_in.wavelength,
+                time: r_in.time
+            }
+        })
+    }
+
+    fn eval(&self, r_in: &Ray, hit_record: &HitRecord, direction: &Vec3) -> Option<Color> {
+        let ggx = Ggx::new(self.roughness);
+        if ggx.is_smooth() {
+            return None;
+        }
+        let frame = Frame::new(hit_record.normal);
+        let wo = frame.to_local(&(r_in.direction.unit_vector() * -1.0));
+        let wi = frame.to_local(&direction.unit_vector());
+        if wo.z <= 0.0 || wi.z <= 0.0 {
+            return Some(Color{r: 0.0, g: 0.0, b: 0.0});
+        }
+
+        let h = (wo + wi).unit_vector();
+        let fresnel = fresnel_conductor_color(wo.dot(&h), &self.eta, &self.k);
+        Some(fresnel * (ggx.d(&h) * ggx.g2(&wo, &wi) / (4.0 * wo.z)))
+    }
+}
+
+
+pub struct RoughDielectric {
+    pub ref_idx: f32,
+    pub roughness: f32
+}
+
+impl Material for RoughDielectric {
+    fn name(&self) -> &str {
+        "rough dielectric"
+    }
+
+    fn scatter(&self, r_in: &Ray, hit_record: &HitRecord) -> Option<ScatterRecord> {
+        let etai_over_etat = if hit_record.front_face { 1.0 / self.ref_idx } else { self.ref_idx };
+        self.scatter_interface(r_in, hit_record, etai_over_etat)
+    }
+
+    fn interior(&self) -> Option<Interior> {
+        Some(Interior{
+            ref_idx: self.ref_idx,
+            dispersion: Dispersion::None,
+            absorption: Color{r: 0.0, g: 0.0, b: 0.0},
+            scattering: 0.0,
+            index_matched: false,
+            priority: 0
+        })
+    }
+
+    fn scatter_interface(&self, r_in: &Ray, hit_record: &HitRecord, etai_over_etat: f32) -> Option<ScatterRecord> {
+        let mut rng = rand::thread_rng();
+        let ggx = Ggx::new(self.roughness);
+        let frame = Frame::new(hit_record.normal);
+        let wo = frame.to_local(&(r_in.direction.unit_vector() * -1.0));
+        if wo.z <= 0.0 {
+            return None;
+        }
+
+        let m = ggx.sample_visible_normal(&wo, rng.gen(), rng.gen());
+        let cos_theta = wo.dot(&m);
+        let reflect_prob = fresnel_dielectric(cos_theta, etai_over_etat);
+
+        // choosing the lobe with the Fresnel probability cancels F out of the weight
+        let wi = if rng.gen::<f32>() < reflect_prob {
+            let wi = (wo * -1.0).reflect(&m);
+            if wi.z <= 0.0 {
+                return None;
+            }
+            wi
+        } else {
+            let wi = (wo * -1.0).refract(&m, etai_over_etat);
+            if wi.z >= 0.0 {
+                return None;
+            }
+            wi
+        };
+
+        Some(ScatterRecord{
+            attenuation: Color{r: 1.0, g: 1.0, b: 1.0} * (ggx.g2(&wo, &wi) / ggx.g1(&wo)),
+            specular: true,
+            scattered: Ray{
+                origin: hit_record.p,
+                direction: frame.to_world(&wi),
+                wavelength: r_in.wavelength,
+                time: r_in.time
+            }
+        })
+    }
+}
@@ -0,0 +1,285 @@
+use crate::aabb::Aabb;
+use crate::color::Color;
+use crate::density::DensityField;
+use crate::hittable::{Hittable, HitRecord};
+use crate::material::{Material, ScatterRecord, Interior, Dispersion};
+use crate::ray::Ray;
+use crate::microfacet::Frame;
+use crate::vec3::Vec3;
+use rand::Rng;
+use std::f32::consts;
+
+// phase function scattering light equally into all directions
+pub struct Isotropic {
+    pub albedo: Color
+}
+
+impl Material for Isotropic {
+    fn name(&self) -> &str {
+        "isotropic"
+    }
+
+    fn scatter(&self, r_in: &Ray, hit_record: &HitRecord) -> Option<ScatterRecord> {
+        Some(ScatterRecord{
+            attenuation: self.albedo,
+            specular: false,
+            scattered: Ray{
+                origin: hit_record.p,
+                direction: Vec3::random_in_unit_sphere().unit_vector(),
+                wavelength: r_in.wavelength,
+                time: r_in.time
+            }
+        })
+    }
+
+    fn eval(&self, _r_in: &Ray, _hit_record: &HitRecord, _direction: &Vec3) -> Option<Color> {
+        Some(self.albedo / (4.0 * consts::PI))
+    }
+}
+
+/*
+    Henyey-Greenstein phase function, g > 0 scatters forward like haze and
+    clouds, g < 0 backwards and g = 0 is isotropic.
+*/
+pub struct HenyeyGreenstein {
+    pub albedo: Color,
+    pub g: f32
+}
+
+impl HenyeyGreenstein {
+    // cosine between the directions the light travels along before and after scattering
+    fn phase(&self, cos_theta: f32) -> f32 {
+        let g = self.g;
+        let denom = 1.0 + g * g - 2.0 * g * cos_theta;
+        (1.0 - g * g) / (4.0 * consts::PI * denom * denom.sqrt())
+    }
+}
+
+impl Material for HenyeyGreenstein {
+    fn name(&self) -> &str {
+        "henyey greenstein"
+    }
+
+    fn scatter(&self, r_in: &Ray, hit_record: &HitRecord) -> Option<ScatterRecord> {
+        let mut rng = rand::thread_rng();
+        let g = self.g;
+        let xi = rng.gen::<f32>();
+        let cos_theta = if g.abs() < 1e-3 {
+            1.0 - 2.0 * xi
+        } else {
+            let sqr = (1.0 - g * g) / (1.0 - g + 2.0 * g * xi);
+            (1.0 + g * g - sqr * sqr) / (2.0 * g)
+        };
+        let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+        let phi = 2.0 * consts::PI * rng.gen::<f32>();
+
+        // around the direction the path travels, light comes from further ahead
+        let frame = Frame::new(r_in.direction.unit_vector());
+        let local = Vec3{x: sin_theta * phi.cos(), y: sin_theta * phi.sin(), z: cos_theta};
+        Some(ScatterRecord{
+            attenuation: self.albedo,
+            specular: false,
+            scattered: Ray{
+                origin: hit_record.p,
+                direction: frame.to_world(&local),
+                wavelength: r_in.wavelength,
+                time: r_in.time
+            }
+        })
+    }
+
+    fn eval(&self, r_in: &Ray, _hit_record: &HitRecord, direction: &Vec3) -> Option<Color> {
+        let cos_theta = r_in.direction.unit_vector().dot(&direction.unit_vector());
+        Some(self.albedo * self.phase(cos_theta))
+    }
+}
+
+/*
+    Fog, smoke or a cloudy blob filling a closed boundary. The boundary itself
+    is invisible, the integrator samples where light scatters inside it.
+*/
+pub struct ConstantMedium {
+    pub boundary: Box<dyn Hittable>,
+    material: Box<dyn Material>
+}
+
+impl ConstantMedium {
+    pub fn new(boundary: Box<dyn Hittable>, density: f32, phase_function: Box<dyn Material>) -> Self {
+        Self {
+            boundary,
+            material: Box::new(MediumBoundary{ density, field: None, phase_function })
+        }
+    }
+}
+
+impl Hittable for ConstantMedium {
+    fn hit(&self, r: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        let mut hit_record = self.boundary.hit(r, t_min, t_max)?;
+        hit_record.material = &self.material;
+        Some(hit_record)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        self.boundary.bounding_box()
+    }
+}
+
+/*
+    Smoke and clouds whose density varies inside the boundary, sampled with
+    delta tracking and ratio tracking by the integrator.
+*/
+pub struct HeterogeneousMedium {
+    pub boundary: Box<dyn Hittable>,
+    material: Box<dyn Material>
+}
+
+impl HeterogeneousMedium {
+    pub fn new(boundary: Box<dyn Hittable>, field: Box<dyn DensityField>, phase_function: Box<dyn Material>) -> Self {
+        Self {
+            boundary,
+            material: Box::new(MediumBoundary{ density: field.max_density(), field: Some(field), phase_function })
+        }
+    }
+}
+
+impl Hittable for HeterogeneousMedium {
+    fn hit(&self, r: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        let mut hit_record = self.boundary.hit(r, t_min, t_max)?;
+        hit_record.material = &self.material;
+        Some(hit_record)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        self.boundary.bounding_box()
+    }
+}
+
+// index matched boundary of a medium, scattering inside follows the phase function
+struct MediumBoundary {
+    // constant density, or the majorant of the field
+    density: f32,
+    field: Option<Box<dyn DensityField>>,
+    phase_function: Box<dyn Material>
+}
+
+impl Material for MediumBoundary {
+    fn name(&self) -> &str {
+        "medium boundary"
+    }
+
+    fn scatter(&self, r_in: &Ray, hit_record: &HitRecord) -> Option<ScatterRecord> {
+        self.phase_function.scatter(r_in, hit_record)
+    }
+
+    fn eval(&self, r_in: &Ray, hit_record: &HitRecord, direction: &Vec3) -> Option<Color> {
+        self.phase_function.eval(r_in, hit_record, direction)
+    }
+
+    fn interior(&self) -> Option<Interior> {
+        Some(Interior{
+            ref_idx: 1.0,
+            dispersion: Dispersion::None,
+            absorption: Color{r: 0.0, g: 0.0, b: 0.0},
+            scattering: self.density,
+            index_matched: true,
+            priority: 0
+        })
+    }
+
+    fn density(&self, p: &Vec3) -> Option<f32> {
+        self.field.as_ref().map(|field| field.density(p))
+    }
+}
+
+/*
+    Fog filling the whole scene outside of all other media, the camera sits in
+    it. The extinction falls off exponentially with height above `base_height`
+    and is zero above `top`, so both the optical depth along a ray and the
+    distance to a collision have closed forms.
+*/
+pub struct Fog {
+    // extinction at `base_height`
+    pub density: f32,
+    pub base_height: f32,
+    // zero for homogeneous fog
+    pub falloff: f32,
+    pub top: f32,
+    pub phase_function: Box<dyn Material>
+}
+
+impl Fog {
+    // a layer of constant density below `top`
+    pub fn homogeneous(density: f32, top: f32, phase_function: Box<dyn Material>) -> Self {
+        Self { density, base_height: 0.0, falloff: 0.0, top, phase_function }
+    }
+
+    // height fog thinning out without bound, like haze in the atmosphere
+    pub fn exponential(density: f32, base_height: f32, falloff: f32, phase_function: Box<dyn Material>) -> Self {
+        Self { density, base_height, falloff, top: f32::INFINITY, phase_function }
+    }
+
+    pub fn density(&self, p: &Vec3) -> f32 {
+        if p.y > self.top {
+            return 0.0;
+        }
+        self.profile(p.y)
+    }
+
+    fn profile(&self, y: f32) -> f32 {
+        self.density * (-self.falloff * (y - self.base_height)).exp()
+    }
+
+    // the part of the ray below `top`, `direction` has unit length
+    fn span(&self, origin: &Vec3, direction: &Vec3, distance: f32) -> Option<(f32, f32)> {
+        if direction.y.abs() < 1e-6 {
+            return if origin.y <= self.top { Some((0.0, distance)) } else { None };
+        }
+        let t_top = (self.top - origin.y) / direction.y;
+        let (t0, t1) = if direction.y > 0.0 { (0.0, t_top.min(distance)) } else { (t_top.max(0.0), distance) };
+        if t0 < t1 { Some((t0, t1)) } else { None }
+    }
+
+    // integral of exp(-b t) from 0 to `length`
+    fn integral(b: f32, length: f32) -> f32 {
+        if b.abs() < 1e-6 { length } else { (1.0 - (-b * length).exp()) / b }
+    }
+
+    pub fn optical_depth(&self, origin: &Vec3, direction: &Vec3, distance: f32) -> f32 {
+        let (t0, t1) = match self.span(origin, direction, distance) {
+            Some(span) => span,
+            None => return 0.0
+        };
+        let sigma = self.profile(origin.y + direction.y * t0);
+        if sigma <= 0.0 {
+            return 0.0;
+        }
+        sigma * Self::integral(self.falloff * direction.y, t1 - t0)
+    }
+
+    pub fn transmittance(&self, origin: &Vec3, direction: &Vec3, distance: f32) -> f32 {
+        (-self.optical_depth(origin, direction, distance)).exp()
+    }
+
+    // distance to a collision for a uniform random `xi`, None if the ray gets past `distance`
+    pub fn sample_distance(&self, origin: &Vec3, direction: &Vec3, distance: f32, xi: f32) -> Option<f32> {
+        let (t0, t1) = self.span(origin, direction, distance)?;
+        let sigma = self.profile(origin.y + direction.y * t0);
+        if sigma <= 0.0 {
+            return None;
+        }
+        let tau = -(1.0 - xi).ln() / sigma;
+        let b = self.falloff * direction.y;
+        let length = if b.abs() < 1e-6 {
+            tau
+        } else {
+            // upward rays may leave before ever reaching the optical depth
+            let x = 1.0 - tau * b;
+            if x <= 0.0 {
+                return None;
+            }
+            -x.ln() / b
+        };
+        let t = t0 + length;
+        if t < t1 { Some(t) } else { None }
+    }
+}
@@ -0,0 +1,154 @@
+// GGX / Trowbridge-Reitz microfacet distribution
+// Heitz. "Sampling the GGX Distribution of Visible Normals" (JCGT 2018)
+
+use crate::color::Color;
+use crate::vec3::Vec3;
+use std::f32::consts;
+
+// shading frame with the normal along +z
+pub struct Frame {
+    pub s: Vec3,
+    pub t: Vec3,
+    pub n: Vec3
+}
+
+impl Frame {
+    pub fn new(n: Vec3) -> Self {
+        let (s, t) = n.orthonormal_basis();
+        Self { s, t, n }
+    }
+
+    // s along the tangent, made perpendicular to n
+    pub fn with_tangent(n: Vec3, tangent: Vec3) -> Self {
+        let s = tangent - n * n.dot(&tangent);
+        if s.squared_length() < 1e-12 {
+            return Self::new(n);
+        }
+        let s = s.unit_vector();
+        Self { s, t: n.cross(&s), n }
+    }
+
+    pub fn to_local(&self, v: &Vec3) -> Vec3 {
+        Vec3{x: v.dot(&self.s), y: v.dot(&self.t), z: v.dot(&self.n)}
+    }
+
+    pub fn to_world(&self, v: &Vec3) -> Vec3 {
+        self.s * v.x + self.t * v.y + self.n * v.z
+    }
+}
+
+pub struct Ggx {
+    pub alpha_x: f32,
+    pub alpha_y: f32
+}
+
+impl Ggx {
+    // perceptual roughness is squared, the same convention as most DCC tools
+    pub fn new(roughness: f32) -> Self {
+        let alpha = (roughness * roughness).max(1e-4);
+        Self { alpha_x: alpha, alpha_y: alpha }
+    }
+
+    pub fn anisotropic(roughness: f32, anisotropic: f32) -> Self {
+        let aspect = (1.0 - 0.9 * anisotropic).sqrt();
+        let alpha = roughness * roughness;
+        Self {
+            alpha_x: (alpha / aspect).max(1e-4),
+            alpha_y: (alpha * aspect).max(1e-4)
+        }
+    }
+
+    // nearly specular lobes are too narrow for light sampling to find them
+    pub fn is_smooth(&self) -> bool {
+        self.alpha_x.max(self.alpha_y) < 0.05
+    }
+
+    pub fn d(&self, m: &Vec3) -> f32 {
+        if m.z <= 0.0 {
+            return 0.0;
+        }
+        let x = m.x / self.alpha_x;
+        let y = m.y / self.alpha_y;
+        let e = x * x + y * y + m.z * m.z;
+        1.0 / (consts::PI * self.alpha_x * self.alpha_y * e * e)
+    }
+
+    fn lambda(&self, w: &Vec3) -> f32 {
+        let z2 = w.z * w.z;
+        if z2 == 0.0 {
+            return f32::INFINITY;
+        }
+        let a2 = (self.alpha_x * w.x).powi(2) + (self.alpha_y * w.y).powi(2);
+        ((1.0 + a2 / z2).sqrt() - 1.0) / 2.0
+    }
+
+    pub fn g1(&self, w: &Vec3) -> f32 {
+        1.0 / (1.0 + self.lambda(w))
+    }
+
+    // height correlated masking-shadowing
+    pub fn g2(&self, wo: &Vec3, wi: &Vec3) -> f32 {
+        1.0 / (1.0 + self.lambda(wo) + self.lambda(wi))
+    }
+
+    // samples a microfacet normal visible from `wo`, which must be above the surface
+    pub fn sample_visible_normal(&self, wo: &Vec3, u1: f32, u2: f32) -> Vec3 {
+        let vh = Vec3{x: self.alpha_x * wo.x, y: self.alpha_y * wo.y, z: wo.z}.unit_vector();
+        let lensq = vh.x * vh.x + vh.y * vh.y;
+        let t1 = if lensq > 0.0 {
+            Vec3{x: -vh.y, y: vh.x, z: 0.0} / lensq.sqrt()
+        } else {
+            Vec3{x: 1.0, y: 0.0, z: 0.0}
+        };
+        let t2 = vh.cross(&t1);
+
+        let r = u1.sqrt();
+        let phi = 2.0 * consts::PI * u2;
+        let p1 = r * phi.cos();
+        let s = 0.5 * (1.0 + vh.z);
+        let p2 = (1.0 - s) * (1.0 - p1 * p1).sqrt() + s * r * phi.sin();
+        let nh = t1 * p1 + t2 * p2 + vh * (1.0 - p1 * p1 - p2 * p2).max(0.0).sqrt();
+
+        Vec3{x: self.alpha_x * nh.x, y: self.alpha_y * nh.y, z: nh.z.max(0.0)}.unit_vector()
+    }
+}
+
+// unpolarized Fresnel reflectance of a conductor with complex IOR eta + ik
+pub fn fresnel_conductor(cos_i: f32, eta: f32, k: f32) -> f32 {
+    let cos2 = cos_i * cos_i;
+    let sin2 = 1.0 - cos2;
+    let eta2 = eta * eta;
+    let k2 = k * k;
+
+    let t0 = eta2 - k2 - sin2;
+    let a2plusb2 = (t0 * t0 + 4.0 * eta2 * k2).sqrt();
+    let t1 = a2plusb2 + cos2;
+    let a = (0.5 * (a2plusb2 + t0)).max(0.0).sqrt();
+    let t2 = 2.0 * cos_i * a;
+    let rs = (t1 - t2) / (t1 + t2);
+
+    let t3 = cos2 * a2plusb2 + sin2 * sin2;
+    let t4 = t2 * sin2;
+    let rp = rs * (t3 - t4) / (t3 + t4);
+    0.5 * (rp + rs)
+}
+
+pub fn fresnel_conductor_color(cos_i: f32, eta: &Color, k: &Color) -> Color {
+    Color{
+        r: fresnel_conductor(cos_i, eta.r, k.r),
+        g: fresnel_conductor(cos_i, eta.g, k.g),
+        b: fresnel_conductor(cos_i, eta.b, k.b)
+    }
+}
+
+// unpolarized Fresnel reflectance of a dielectric, `eta` is etai / etat
+pub fn fresnel_dielectric(cos_i: f32, eta: f32) -> f32 {
+    let sin2_t = eta * eta * (1.0 - cos_i * cos_i);
+    if sin2_t >= 1.0 {
+        return 1.0;
+    }
+    let cos_t = (1.0 - sin2_t).sqrt();
+    let rs = (eta * cos_i - cos_t) / (eta * cos_i + cos_t);
+    let rp = (cos_i - eta * cos_t) / (cos_i + eta * cos_t);
+    0.5 * (rs * rs + rp * rp)
+}
@@ -0,0 +1,71 @@
+/*
+    Camera controller for dragging around a scene: orbits around the point
+    looked at, pans it sideways, dollies towards it and rolls the camera.
+    The world's up direction is +y.
+*/
+
+use crate::vec3::Vec3;
+
+// keeps the camera from flipping over the poles
+const MAX_PITCH: f32 = 89.0;
+const MIN_DISTANCE: f32 = 1e-3;
+
+#[derive(Debug, Copy, Clone)]
+pub struct OrbitController {
+    pub lookat: Vec3,
+    pub distance: f32,
+    // degrees around the up axis from +z and above the horizon, of the camera seen from `lookat`
+    pub yaw: f32,
+    pub pitch: f32,
+    // degrees the camera is turned counterclockwise around the view direction
+    pub roll: f32
+}
+
+impl OrbitController {
+    pub fn new(lookfrom: Vec3, lookat: Vec3) -> Self {
+        let offset = lookfrom - lookat;
+        let distance = offset.length().max(MIN_DISTANCE);
+        let pitch = (offset.y / distance).clamp(-1.0, 1.0).asin().to_degrees();
+        let yaw = offset.x.atan2(offset.z).to_degrees();
+        Self { lookat, distance, yaw, pitch: pitch.clamp(-MAX_PITCH, MAX_PITCH), roll: 0.0 }
+    }
+
+    pub fn orbit(&mut self, yaw: f32, pitch: f32) {
+        self.yaw = (self.yaw + yaw) % 360.0;
+        self.pitch = (self.pitch + pitch).clamp(-MAX_PITCH, MAX_PITCH);
+    }
+
+    // moves `lookat` and the camera together along the image, `right` and `up` in units of the distance
+    pub fn pan(&mut self, right: f32, up: f32) {
+        let (u, v) = self.frame();
+        self.lookat = self.lookat + (u * right + v * up) * self.distance;
+    }
+
+    // towards `lookat` for factors below 1, away above
+    pub fn dolly(&mut self, factor: f32) {
+        self.distance = (self.distance * factor).max(MIN_DISTANCE);
+    }
+
+    pub fn roll(&mut self, degrees: f32) {
+        self.roll = (self.roll + degrees) % 360.0;
+    }
+
+    pub fn lookfrom(&self) -> Vec3 {
+        let (yaw, pitch) = (self.yaw.to_radians(), self.pitch.to_radians());
+        let offset = Vec3{x: pitch.cos() * yaw.sin(), y: pitch.sin(), z: pitch.cos() * yaw.cos()};
+        self.lookat + offset * self.distance
+    }
+
+    pub fn vup(&self) -> Vec3 {
+        self.frame().1
+    }
+
+    // right and up of the image, turned by the roll
+    fn frame(&self) -> (Vec3, Vec3) {
+        let w = (self.lookfrom() - self.lookat).unit_vector();
+        let u = Vec3{x: 0.0, y: 1.0, z: 0.0}.cross(&w).unit_vector();
+        let v = w.cross(&u);
+        let (sin, cos) = self.roll.to_radians().sin_cos();
+        (u * cos + v * sin, v * cos - u * sin)
+    }
+}
@@ -0,0 +1,547 @@
+/*
+    Imports the part of pbrt-v3 scene files this renderer can show, to compare
+    renders with the reference renderer: perspective, orthographic and
+    environment cameras, transforms, spheres, triangle meshes, matte, metal
+    and glass materials and a distant light as the sun. Every other
+    directive is skipped and listed with its line.
+
+    pbrt's coordinate system is left handed. When its camera is right handed
+    the scene is mirrored in x on import, so images come out the same way
+    round as pbrt's.
+*/
+
+use crate::camera::{Camera, EquirectangularCamera, OrthographicCamera, PerspectiveCamera};
+use crate::color::Color;
+use crate::hittable::Hittable;
+use crate::instance::Instance;
+use crate::material::{Dielectric, Lambertian, Material, Named, RoughConductor, RoughDielectric};
+use crate::sky::Sky;
+use crate::sphere::Sphere;
+use crate::transform::{Mat4, Transform};
+use crate::triangle::Mesh;
+use crate::vec3::Vec3;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Directive(String),
+    Str(String),
+    Num(f32),
+    Open,
+    Close
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Num(f32),
+    Str(String)
+}
+
+#[derive(Debug, Clone)]
+struct Param {
+    ty: String,
+    name: String,
+    values: Vec<Value>
+}
+
+#[derive(Debug, Clone, Default)]
+struct ParamList(Vec<Param>);
+
+impl ParamList {
+    fn find(&self, name: &str) -> Option<&Param> {
+        self.0.iter().find(|p| p.name == name)
+    }
+
+    fn floats(&self, name: &str) -> Option<Vec<f32>> {
+        let values = &self.find(name)?.values;
+        Some(values.iter().filter_map(|v| match v { Value::Num(n) => Some(*n), _ => None }).collect())
+    }
+
+    fn float(&self, name: &str, default: f32) -> f32 {
+        self.floats(name).and_then(|v| v.first().copied()).unwrap_or(default)
+    }
+
+    fn vector(&self, name: &str, default: Vec3) -> Vec3 {
+        match self.floats(name) {
+            Some(v) if v.len() == 3 => Vec3{x: v[0], y: v[1], z: v[2]},
+            _ => default
+        }
+    }
+
+    // colors given as RGB, spectra and textures aren't supported
+    fn rgb(&self, name: &str, default: Color) -> Color {
+        match (self.find(name).map(|p| p.ty.as_str()), self.floats(name)) {
+            (Some("rgb"), Some(c)) | (Some("color"), Some(c)) if c.len() == 3 => Color{r: c[0], g: c[1], b: c[2]},
+            _ => default
+        }
+    }
+
+    fn string(&self, name: &str) -> Option<&str> {
+        match self.find(name)?.values.first() {
+            Some(Value::Str(s)) => Some(s.as_str()),
+            _ => None
+        }
+    }
+
+    fn bool(&self, name: &str, default: bool) -> bool {
+        match self.find(name).and_then(|p| p.values.first()) {
+            Some(Value::Str(s)) => s == "true",
+            _ => default
+        }
+    }
+}
+
+fn tokenize(text: &str) -> Result<Vec<(Token, usize)>, String> {
+    let mut tokens = Vec::new();
+    for (number, line) in text.lines().enumerate() {
+        let number = number + 1;
+        let mut chars = line.char_indices().peekable();
+        while let Some((start, c)) = chars.next() {
+            match c {
+                '#' => break,
+                '[' => tokens.push((Token::Open, number)),
+                ']' => tokens.push((Token::Close, number)),
+                '"' => {
+                    let end = line[start + 1..].find('"').ok_or_else(|| format!("line {}: unterminated string", number))?;
+                    tokens.push((Token::Str(line[start + 1..start + 1 + end].to_string()), number));
+                    while chars.peek().is_some_and(|(i, _)| *i <= start + 1 + end) {
+                        chars.next();
+                    }
+                },
+                c if c.is_whitespace() => (),
+                _ => {
+                    let mut end = line.len();
+                    while let Some((i, c)) = chars.peek() {
+                        if c.is_whitespace() || "[]\"#".contains(*c) {
+                            end = *i;
+                            break;
+                        }
+                        chars.next();
+                    }
+                    let word = &line[start..end];
+                    if c.is_ascii_alphabetic() {
+                        tokens.push((Token::Directive(word.to_string()), number));
+                    } else {
+                        let n = word.parse().map_err(|_| format!("line {}: invalid number '{}'", number, word))?;
+                        tokens.push((Token::Num(n), number));
+                    }
+                }
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+// one directive with its arguments, before they are told apart into positional ones and parameters
+struct Statement {
+    name: String,
+    line: usize,
+    numbers: Vec<f32>,
+    strings: Vec<String>,
+    params: ParamList
+}
+
+impl Statement {
+    fn kind(&self) -> &str {
+        self.strings.first().map_or("", |s| s.as_str())
+    }
+
+    fn describe(&self) -> String {
+        match self.strings.first() {
+            Some(kind) => format!("line {}: {} \"{}\"", self.line, self.name, kind),
+            None => format!("line {}: {}", self.line, self.name)
+        }
+    }
+}
+
+fn statements(tokens: &[(Token, usize)]) -> Result<Vec<Statement>, String> {
+    let mut result = Vec::new();
+    let mut i = 0;
+    while i < tokens.len() {
+        let (name, line) = match &tokens[i] {
+            (Token::Directive(name), line) => (name.clone(), *line),
+            (_, line) => return Err(format!("line {}: expected a directive", line))
+        };
+        i += 1;
+        // textures are the only directive with three positional strings, names may contain spaces
+        let positional = if name == "Texture" { 3 } else { 1 };
+        let mut statement = Statement{ name, line, numbers: Vec::new(), strings: Vec::new(), params: ParamList::default() };
+        while i < tokens.len() {
+            match &tokens[i].0 {
+                Token::Directive(_) => break,
+                Token::Num(n) => statement.numbers.push(*n),
+                // the numbers of Transform and ConcatTransform are bracketed
+                Token::Open | Token::Close if statement.strings.is_empty() => (),
+                Token::Str(s) if statement.strings.len() < positional && statement.params.0.is_empty() => statement.strings.push(s.clone()),
+                Token::Str(declaration) => {
+                    let mut words = declaration.split_whitespace();
+                    let (ty, param) = match (words.next(), words.next()) {
+                        (Some(ty), Some(param)) => (ty.to_string(), param.to_string()),
+                        _ => return Err(format!("line {}: malformed parameter \"{}\"", line, declaration))
+                    };
+                    i += 1;
+                    let mut values = Vec::new();
+                    let bracketed = tokens.get(i).map(|t| &t.0) == Some(&Token::Open);
+                    if bracketed {
+                        i += 1;
+                    }
+                    while let Some((token, _)) = tokens.get(i) {
+                        match token {
+                            Token::Num(n) => values.push(Value::Num(*n)),
+                            Token::Str(s) => values.push(Value::Str(s.clone())),
+                            Token::Close if bracketed => break,
+                            _ => return Err(format!("line {}: malformed value of \"{}\"", line, declaration))
+                        }
+                        if !bracketed {
+                            break;
+                        }
+                        i += 1;
+                    }
+                    statement.params.0.push(Param{ ty, name: param, values });
+                },
+                _ => return Err(format!("line {}: unexpected bracket", line))
+            }
+            i += 1;
+        }
+        result.push(statement);
+    }
+    Ok(result)
+}
+
+// pbrt's world to camera transform of LookAt
+fn look_at(numbers: &[f32]) -> Option<Transform> {
+    let v = |i: usize| Vec3{x: numbers[i], y: numbers[i + 1], z: numbers[i + 2]};
+    let (eye, target, up) = (v(0), v(3), v(6));
+    let dir = (target - eye).unit_vector();
+    let right = up.unit_vector().cross(&dir);
+    if right.length() == 0.0 {
+        return None;
+    }
+    let right = right.unit_vector();
+    let new_up = dir.cross(&right);
+    let mut camera_to_world = Mat4::identity();
+    for (j, column) in [right, new_up, dir, eye].iter().enumerate() {
+        camera_to_world.m[0][j] = column.x;
+        camera_to_world.m[1][j] = column.y;
+        camera_to_world.m[2][j] = column.z;
+    }
+    Some(Transform::from_matrix(camera_to_world)?.invert())
+}
+
+// mirroring transforms turn the winding of triangles around
+fn swaps_handedness(transform: &Transform) -> bool {
+    let m = &transform.matrix.m;
+    let det = m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+        - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+        + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0]);
+    det < 0.0
+}
+
+// pbrt-v3's mapping of perceptual roughness to the microfacet alpha
+fn roughness_to_alpha(roughness: f32) -> f32 {
+    let x = roughness.max(1e-3).ln();
+    1.62142 + 0.819955 * x + 0.1734 * x * x + 0.0171201 * x * x * x + 0.000640711 * x * x * x * x
+}
+
+// roughness of this renderer for a pbrt roughness, which is alpha unless remapped
+fn roughness(params: &ParamList, default: f32) -> f32 {
+    let r = match (params.floats("uroughness"), params.floats("vroughness")) {
+        (Some(u), Some(v)) if !u.is_empty() && !v.is_empty() => (u[0] + v[0]) / 2.0,
+        _ => params.float("roughness", default)
+    };
+    let alpha = if params.bool("remaproughness", true) { roughness_to_alpha(r) } else { r };
+    alpha.max(0.0).sqrt()
+}
+
+#[derive(Clone)]
+struct GraphicsState {
+    transform: Transform,
+    reverse_orientation: bool,
+    material: String,
+    material_params: ParamList,
+    // set by NamedMaterial
+    material_name: Option<String>
+}
+
+pub struct PbrtScene {
+    pub objects: Vec<Box<dyn Hittable>>,
+    pub camera: Box<dyn Camera>,
+    // of the film, width over height
+    pub aspect_ratio: f32,
+    // towards the first distant light
+    pub sun_direction: Option<Vec3>,
+    // directives and parameters which were not imported
+    pub skipped: Vec<String>
+}
+
+impl PbrtScene {
+    // the sky lit by the first distant light of the file
+    pub fn sky(&self, turbidity: f32) -> Option<Sky> {
+        self.sun_direction.map(|d| Sky::towards(&d, turbidity))
+    }
+}
+
+struct Importer {
+    state: GraphicsState,
+    stack: Vec<GraphicsState>,
+    camera_from_world: Transform,
+    // the type and parameters of the camera
+    camera: (String, ParamList),
+    film: (f32, f32),
+    // applied after everything else, mirrors the scene for right handed cameras
+    world: Transform,
+    objects: Vec<Box<dyn Hittable>>,
+    // type and parameters of the materials made by MakeNamedMaterial
+    named_materials: HashMap<String, (String, ParamList)>,
+    sun_direction: Option<Vec3>,
+    skipped: Vec<String>
+}
+
+impl Importer {
+    fn material(&mut self, line: usize) -> Box<dyn Material> {
+        let material = self.unnamed_material(line);
+        match &self.state.material_name {
+            Some(name) => Box::new(Named{ name: name.clone(), material }),
+            None => material
+        }
+    }
+
+    fn unnamed_material(&mut self, line: usize) -> Box<dyn Material> {
+        let params = &self.state.material_params;
+        match self.state.material.as_str() {
+            "matte" => {
+                if params.find("Kd").is_some_and(|p| p.ty == "texture") {
+                    self.skipped.push(format!("line {}: texture \"Kd\" of material \"matte\"", line));
+                }
+                Box::new(Lambertian{ albedo: params.rgb("Kd", Color{r: 0.5, g: 0.5, b: 0.5}) })
+            },
+            // pbrt's default is copper
+            "metal" => Box::new(RoughConductor{
+                eta: params.rgb("eta", Color{r: 0.200438, g: 0.924033, b: 1.10221}),
+                k: params.rgb("k", Color{r: 3.91295, g: 2.45285, b: 2.14219}),
+                roughness: roughness(params, 0.01)
+            }),
+            "glass" => {
+                let ior = params.float("eta", params.float("index", 1.5));
+                let rough = params.find("roughness").is_some() || params.find("uroughness").is_some();
+                if rough {
+                    Box::new(RoughDielectric{ ref_idx: ior, roughness: roughness(params, 0.0) })
+                } else {
+                    Box::new(Dielectric::new(ior))
+                }
+            },
+            other => {
+                let note = format!("line {}: Material \"{}\" (rendered as matte)", line, other);
+                if !self.skipped.contains(&note) {
+                    self.skipped.push(note);
+                }
+                Box::new(Lambertian{ albedo: Color{r: 0.5, g: 0.5, b: 0.5} })
+            }
+        }
+    }
+
+    fn shape(&mut self, statement: &Statement) -> Result<(), String> {
+        let world = self.world * self.state.transform;
+        let params = &statement.params;
+        match statement.kind() {
+            "sphere" => {
+                if ["zmin", "zmax", "phimax"].iter().any(|p| params.find(p).is_some()) {
+                    self.skipped.push(format!("line {}: partial sphere, rendered whole", statement.line));
+                }
+                let radius = params.float("radius", 1.0);
+                let sphere = Instance::share(Sphere{
+                    center: Vec3{x: 0.0, y: 0.0, z: 0.0},
+                    radius,
+                    material: self.material(statement.line)
+                });
+                self.objects.push(Box::new(Instance::new(sphere, world)));
+            },
+            "trianglemesh" => {
+                let p = params.floats("P").unwrap_or_default();
+                let positions: Vec<Vec3> = p.chunks_exact(3).map(|p| world.point(&Vec3{x: p[0], y: p[1], z: p[2]})).collect();
+                let indices: Vec<usize> = match params.floats("indices") {
+                    Some(indices) => indices.iter().map(|i| *i as usize).collect(),
+                    None if positions.len() == 3 => vec![0, 1, 2],
+                    None => return Err(format!("line {}: trianglemesh without indices", statement.line))
+                };
+                /*
+                    pbrt faces triangles away from their winding when its transform
+                    swaps handedness or the orientation is reversed, mirroring the
+                    scene swaps it once more
+                */
+                let flip = (swaps_handedness(&self.state.transform) != swaps_handedness(&self.world)) != self.state.reverse_orientation;
+                let triangles = indices.chunks_exact(3).map(|t| if flip { [t[0], t[2], t[1]] } else { [t[0], t[1], t[2]] }).collect();
+
+                let normals = params.floats("N");
+                let uvs = params.floats("uv").or_else(|| params.floats("st"));
+                let material = self.material(statement.line);
+                let mut mesh = Mesh::new(positions, triangles, material).map_err(|e| format!("line {}: {}", statement.line, e))?;
+                if let Some(n) = normals {
+                    let normals = n.chunks_exact(3).map(|n| world.normal(&Vec3{x: n[0], y: n[1], z: n[2]}).unit_vector()).collect();
+                    mesh = mesh.with_normals(normals).map_err(|e| format!("line {}: {}", statement.line, e))?;
+                }
+                if let Some(uv) = uvs {
+                    mesh = mesh.with_uvs(uv.chunks_exact(2).map(|uv| (uv[0], uv[1])).collect()).map_err(|e| format!("line {}: {}", statement.line, e))?;
+                }
+                self.objects.push(Box::new(mesh));
+            },
+            _ => self.skipped.push(statement.describe())
+        }
+        Ok(())
+    }
+
+    fn light(&mut self, statement: &Statement) {
+        match statement.kind() {
+            "distant" if self.sun_direction.is_none() => {
+                let from = statement.params.vector("from", Vec3{x: 0.0, y: 0.0, z: 0.0});
+                let to = statement.params.vector("to", Vec3{x: 0.0, y: 0.0, z: 1.0});
+                let world = self.world * self.state.transform;
+                self.sun_direction = Some(world.vector(&(from - to)).unit_vector());
+            },
+            // the sky model stands in for the environment
+            "infinite" if statement.params.find("mapname").is_none() => (),
+            _ => self.skipped.push(statement.describe())
+        }
+    }
+
+    fn transform(&mut self, statement: &Statement) -> Result<(), String> {
+        let n = &statement.numbers;
+        let expect = |count: usize| if n.len() == count {
+            Ok(())
+        } else {
+            Err(format!("line {}: {} needs {} numbers", statement.line, statement.name, count))
+        };
+        let singular = || format!("line {}: singular transform", statement.line);
+        let t = match statement.name.as_str() {
+            "Translate" => { expect(3)?; Transform::translate(Vec3{x: n[0], y: n[1], z: n[2]}) },
+            "Scale" => { expect(3)?; Transform::scale(Vec3{x: n[0], y: n[1], z: n[2]}) },
+            "Rotate" => { expect(4)?; Transform::rotate(Vec3{x: n[1], y: n[2], z: n[3]}, n[0]) },
+            "LookAt" => { expect(9)?; look_at(n).ok_or_else(singular)? },
+            _ => {
+                expect(16)?;
+                let mut values = [0.0; 16];
+                values.copy_from_slice(n);
+                Transform::from_matrix(Mat4::from_column_major(&values)).ok_or_else(singular)?
+            }
+        };
+        self.state.transform = if statement.name == "Transform" { t } else { self.state.transform * t };
+        Ok(())
+    }
+
+    fn camera(&self) -> Box<dyn Camera> {
+        let (kind, params) = &self.camera;
+        let aspect_ratio = params.float("frameaspectratio", self.film.0 / self.film.1);
+        let camera_to_world = self.world * self.camera_from_world.invert();
+        let lookfrom = camera_to_world.point(&Vec3{x: 0.0, y: 0.0, z: 0.0});
+        let up = camera_to_world.vector(&Vec3{x: 0.0, y: 1.0, z: 0.0});
+        // the environment camera has -x in the center of its image
+        if kind == "environment" {
+            let forward = camera_to_world.vector(&Vec3{x: -1.0, y: 0.0, z: 0.0});
+            return Box::new(EquirectangularCamera::new(lookfrom, lookfrom + forward, up));
+        }
+
+        let forward = camera_to_world.vector(&Vec3{x: 0.0, y: 0.0, z: 1.0});
+        if kind == "orthographic" {
+            // the screen window is [-1, 1] along the shorter side of the image
+            let height = match params.floats("screenwindow") {
+                Some(window) if window.len() == 4 => window[3] - window[2],
+                _ => if aspect_ratio >= 1.0 { 2.0 } else { 2.0 / aspect_ratio }
+            };
+            return Box::new(OrthographicCamera::new(lookfrom, lookfrom + forward, up, height, aspect_ratio));
+        }
+        // the field of view is along the shorter side of the image
+        let fov = params.float("fov", 90.0).to_radians();
+        let vfov = if aspect_ratio >= 1.0 { fov } else { 2.0 * ((fov / 2.0).tan() / aspect_ratio).atan() };
+        let lens_radius = params.float("lensradius", 0.0);
+        let focus_dist = if lens_radius > 0.0 { params.float("focaldistance", 1e6) } else { 1.0 };
+        Box::new(PerspectiveCamera::new(lookfrom, lookfrom + forward, up, vfov.to_degrees(), aspect_ratio, 2.0 * lens_radius, focus_dist))
+    }
+}
+
+pub fn import_pbrt(text: &str) -> Result<PbrtScene, String> {
+    let statements = statements(&tokenize(text)?)?;
+    let mut importer = Importer{
+        state: GraphicsState{
+            transform: Transform::identity(),
+            reverse_orientation: false,
+            material: "matte".to_string(),
+            material_params: ParamList::default(),
+            material_name: None
+        },
+        stack: Vec::new(),
+        camera_from_world: Transform::identity(),
+        camera: ("perspective".to_string(), ParamList::default()),
+        film: (1280.0, 720.0),
+        world: Transform::identity(),
+        objects: Vec::new(),
+        named_materials: HashMap::new(),
+        sun_direction: None,
+        skipped: Vec::new()
+    };
+
+    for statement in &statements {
+        match statement.name.as_str() {
+            "Identity" => importer.state.transform = Transform::identity(),
+            "Translate" | "Scale" | "Rotate" | "LookAt" | "Transform" | "ConcatTransform" => importer.transform(statement)?,
+            "ReverseOrientation" => importer.state.reverse_orientation = !importer.state.reverse_orientation,
+            "AttributeBegin" | "TransformBegin" => importer.stack.push(importer.state.clone()),
+            "AttributeEnd" | "TransformEnd" => {
+                let saved = importer.stack.pop().ok_or_else(|| format!("line {}: unmatched {}", statement.line, statement.name))?;
+                if statement.name == "TransformEnd" {
+                    importer.state.transform = saved.transform;
+                } else {
+                    importer.state = saved;
+                }
+            },
+            "Camera" if ["perspective", "orthographic", "environment"].contains(&statement.kind()) => {
+                importer.camera_from_world = importer.state.transform;
+                importer.camera = (statement.kind().to_string(), statement.params.clone());
+            },
+            "Film" => importer.film = (
+                statement.params.float("xresolution", 1280.0),
+                statement.params.float("yresolution", 720.0)
+            ),
+            "WorldBegin" => {
+                /*
+                    a right handed camera would see pbrt's image mirrored. The
+                    environment camera's space is right handed already, the
+                    others' is left handed.
+                */
+                let left_handed = importer.camera.0 != "environment";
+                if swaps_handedness(&importer.camera_from_world) != left_handed {
+                    importer.world = Transform::scale(Vec3{x: -1.0, y: 1.0, z: 1.0});
+                }
+                importer.state.transform = Transform::identity();
+            },
+            "WorldEnd" => (),
+            "Material" => {
+                importer.state.material = statement.kind().to_string();
+                importer.state.material_params = statement.params.clone();
+                importer.state.material_name = None;
+            },
+            "MakeNamedMaterial" => {
+                let kind = statement.params.string("type").unwrap_or("matte").to_string();
+                importer.named_materials.insert(statement.kind().to_string(), (kind, statement.params.clone()));
+            },
+            "NamedMaterial" => match importer.named_materials.get(statement.kind()) {
+                Some((kind, params)) => {
+                    importer.state.material = kind.clone();
+                    importer.state.material_params = params.clone();
+                    importer.state.material_name = Some(statement.kind().to_string());
+                },
+                None => return Err(format!("line {}: NamedMaterial \"{}\" was never made", statement.line, statement.kind()))
+            },
+            "Shape" => importer.shape(statement)?,
+            "LightSource" => importer.light(statement),
+            _ => importer.skipped.push(statement.describe())
+        }
+    }
+
+    Ok(PbrtScene{
+        camera: importer.camera(),
+        aspect_ratio: importer.film.0 / importer.film.1,
+        objects: importer.objects,
+        sun_direction: importer.sun_direction,
+        skipped: importer.skipped
+    })
+}
@@ -0,0 +1,216 @@
+use crate::aabb::Aabb;
+use crate::hittable::{Hittable, HitRecord};
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::vec3::Vec3;
+
+// flat boxes still get some thickness so they can be bounded
+const PAD: f32 = 1e-4;
+
+/*
+    Infinite plane through `point`. The texture coordinates repeat every unit
+    along two directions in the plane.
+*/
+pub struct Plane {
+    pub point: Vec3,
+    pub normal: Vec3,
+    pub material: Box<dyn Material>
+}
+
+impl Hittable for Plane {
+    fn hit(&self, r: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        let normal = self.normal.unit_vector();
+        let denom = normal.dot(&r.direction);
+        if denom.abs() < 1e-8 {
+            return None;
+        }
+        let t = (self.point - r.origin).dot(&normal) / denom;
+        if t <= t_min || t >= t_max {
+            return None;
+        }
+        let p = r.point_at_parameter(t);
+        let (s, tangent) = normal.orthonormal_basis();
+        let offset = p - self.point;
+        let mut hit_record = HitRecord{
+            p,
+            normal,
+            t,
+            u: offset.dot(&s).rem_euclid(1.0),
+            v: offset.dot(&tangent).rem_euclid(1.0),
+            tangent: Some(s),
+            front_face: true,
+            color: None,
+            instance: 0,
+            material: &self.material
+        };
+        hit_record.set_face_normal(r, normal);
+        Some(hit_record)
+    }
+}
+
+/*
+    Parallelogram with a corner at `q` and the edges `u` and `v`, facing along
+    u x v. Texture coordinates run from 0 to 1 along both edges.
+*/
+pub struct Quad {
+    pub q: Vec3,
+    pub u: Vec3,
+    pub v: Vec3,
+    pub material: Box<dyn Material>
+}
+
+impl Hittable for Quad {
+    fn hit(&self, r: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        hit_quad(self.q, self.u, self.v, &self.material, r, t_min, t_max)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(quad_box(self.q, self.u, self.v))
+    }
+}
+
+fn quad_box(q: Vec3, u: Vec3, v: Vec3) -> Aabb {
+    let diagonals = Aabb::new(q, q + u + v).surrounding(&Aabb::new(q + u, q + v));
+    diagonals.pad(PAD)
+}
+
+#[allow(clippy::borrowed_box)]
+fn hit_quad<'a>(q: Vec3, u: Vec3, v: Vec3, material: &'a Box<dyn Material>, r: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'a>> {
+    let n = u.cross(&v);
+    let normal = n.unit_vector();
+    let denom = normal.dot(&r.direction);
+    if denom.abs() < 1e-8 {
+        return None;
+    }
+    let t = (q - r.origin).dot(&normal) / denom;
+    if t <= t_min || t >= t_max {
+        return None;
+    }
+
+    // coordinates of the hit point in the frame of the edges
+    let p = r.point_at_parameter(t);
+    let w = n / n.dot(&n);
+    let planar = p - q;
+    let alpha = w.dot(&planar.cross(&v));
+    let beta = w.dot(&u.cross(&planar));
+    if !(0.0..=1.0).contains(&alpha) || !(0.0..=1.0).contains(&beta) {
+        return None;
+    }
+
+    let mut hit_record = HitRecord{
+        p,
+        normal,
+        t,
+        u: alpha,
+        v: beta,
+        tangent: None,
+        front_face: true,
+        color: None,
+        instance: 0,
+        material
+    };
+    hit_record.set_face_normal(r, normal);
+    Some(hit_record)
+}
+
+/*
+    Disk around `center` facing along `normal`. u is the angle around the
+    center and v the distance from it, both scaled to [0, 1].
+*/
+pub struct Disk {
+    pub center: Vec3,
+    pub normal: Vec3,
+    pub radius: f32,
+    pub material: Box<dyn Material>
+}
+
+impl Hittable for Disk {
+    fn hit(&self, r: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        let normal = self.normal.unit_vector();
+        let denom = normal.dot(&r.direction);
+        if denom.abs() < 1e-8 {
+            return None;
+        }
+        let t = (self.center - r.origin).dot(&normal) / denom;
+        if t <= t_min || t >= t_max {
+            return None;
+        }
+        let p = r.point_at_parameter(t);
+        let offset = p - self.center;
+        let distance = offset.length();
+        if distance > self.radius {
+            return None;
+        }
+
+        let (s, tangent) = normal.orthonormal_basis();
+        let phi = offset.dot(&tangent).atan2(offset.dot(&s)) + std::f32::consts::PI;
+        let mut hit_record = HitRecord{
+            p,
+            normal,
+            t,
+            u: phi / (2.0 * std::f32::consts::PI),
+            v: distance / self.radius,
+            tangent: None,
+            front_face: true,
+            color: None,
+            instance: 0,
+            material: &self.material
+        };
+        hit_record.set_face_normal(r, normal);
+        Some(hit_record)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let n = self.normal.unit_vector();
+        // extent of a tilted circle along each axis
+        let extent = |c: f32| self.radius * (1.0 - c * c).max(0.0).sqrt();
+        let e = Vec3{x: extent(n.x), y: extent(n.y), z: extent(n.z)};
+        Some(Aabb::new(self.center - e, self.center + e).pad(PAD))
+    }
+}
+
+/*
+    Axis aligned box from `min` to `max` made of six quads facing outwards,
+    each face has its own texture coordinates.
+*/
+pub struct Cuboid {
+    pub min: Vec3,
+    pub max: Vec3,
+    pub material: Box<dyn Material>
+}
+
+impl Cuboid {
+    // corner and edges of the six faces
+    fn faces(&self) -> [(Vec3, Vec3, Vec3); 6] {
+        let (a, b) = (self.min, self.max);
+        let dx = Vec3{x: b.x - a.x, y: 0.0, z: 0.0};
+        let dy = Vec3{x: 0.0, y: b.y - a.y, z: 0.0};
+        let dz = Vec3{x: 0.0, y: 0.0, z: b.z - a.z};
+        [
+            (Vec3{x: a.x, y: a.y, z: b.z}, dx, dy),
+            (Vec3{x: b.x, y: a.y, z: b.z}, -dz, dy),
+            (Vec3{x: b.x, y: a.y, z: a.z}, -dx, dy),
+            (Vec3{x: a.x, y: a.y, z: a.z}, dz, dy),
+            (Vec3{x: a.x, y: b.y, z: b.z}, dx, -dz),
+            (Vec3{x: a.x, y: a.y, z: a.z}, dx, dz)
+        ]
+    }
+}
+
+impl Hittable for Cuboid {
+    fn hit(&self, r: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        let mut closest_so_far = t_max;
+        let mut hit_result = None;
+        for (q, u, v) in self.faces().iter() {
+            if let Some(hit_record) = hit_quad(*q, *u, *v, &self.material, r, t_min, closest_so_far) {
+                closest_so_far = hit_record.t;
+                hit_result = Some(hit_record);
+            }
+        }
+        hit_result
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(Aabb::new(self.min, self.max).pad(PAD))
+    }
+}
@@ -0,0 +1,250 @@
+/*
+    Loads triangle meshes from ASCII and binary PLY files, the format of most
+    3D scanners. Polygons are split into fans of triangles, vertex normals,
+    texture coordinates and colors are kept when the file has them. Elements
+    other than vertices and faces are skipped.
+*/
+
+use crate::color::Color;
+use crate::material::Material;
+use crate::triangle::{Mesh, MeshOptions};
+use crate::vec3::Vec3;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Format {
+    Ascii,
+    LittleEndian,
+    BigEndian
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Scalar {
+    I8, U8, I16, U16, I32, U32, F32, F64
+}
+
+impl Scalar {
+    fn parse(name: &str) -> Result<Self, String> {
+        match name {
+            "char" | "int8" => Ok(Scalar::I8),
+            "uchar" | "uint8" => Ok(Scalar::U8),
+            "short" | "int16" => Ok(Scalar::I16),
+            "ushort" | "uint16" => Ok(Scalar::U16),
+            "int" | "int32" => Ok(Scalar::I32),
+            "uint" | "uint32" => Ok(Scalar::U32),
+            "float" | "float32" => Ok(Scalar::F32),
+            "double" | "float64" => Ok(Scalar::F64),
+            _ => Err(format!("unknown PLY type '{}'", name))
+        }
+    }
+
+    fn size(&self) -> usize {
+        match self {
+            Scalar::I8 | Scalar::U8 => 1,
+            Scalar::I16 | Scalar::U16 => 2,
+            Scalar::I32 | Scalar::U32 | Scalar::F32 => 4,
+            Scalar::F64 => 8
+        }
+    }
+
+    // the largest value of integer types, which stands for full intensity
+    fn max(&self) -> Option<f64> {
+        match self {
+            Scalar::I8 => Some(i8::MAX as f64),
+            Scalar::U8 => Some(u8::MAX as f64),
+            Scalar::I16 => Some(i16::MAX as f64),
+            Scalar::U16 => Some(u16::MAX as f64),
+            Scalar::I32 => Some(i32::MAX as f64),
+            Scalar::U32 => Some(u32::MAX as f64),
+            Scalar::F32 | Scalar::F64 => None
+        }
+    }
+}
+
+struct Property {
+    name: String,
+    // the type of the length of list properties
+    count: Option<Scalar>,
+    value: Scalar
+}
+
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<Property>
+}
+
+// the data after the header, read one value at a time
+struct Body<'a> {
+    format: Format,
+    bytes: &'a [u8],
+    offset: usize
+}
+
+impl Body<'_> {
+    fn token(&mut self) -> Result<&str, String> {
+        while self.offset < self.bytes.len() && self.bytes[self.offset].is_ascii_whitespace() {
+            self.offset += 1;
+        }
+        let start = self.offset;
+        while self.offset < self.bytes.len() && !self.bytes[self.offset].is_ascii_whitespace() {
+            self.offset += 1;
+        }
+        if start == self.offset {
+            return Err("PLY file ends early".to_string());
+        }
+        std::str::from_utf8(&self.bytes[start..self.offset]).map_err(|_| "PLY data is not ASCII".to_string())
+    }
+
+    fn read(&mut self, scalar: Scalar) -> Result<f64, String> {
+        if self.format == Format::Ascii {
+            let token = self.token()?;
+            return token.parse::<f64>().map_err(|_| format!("invalid PLY number '{}'", token));
+        }
+        let size = scalar.size();
+        let mut b = [0u8; 8];
+        b[..size].copy_from_slice(self.bytes.get(self.offset..self.offset + size).ok_or_else(|| "PLY file ends early".to_string())?);
+        self.offset += size;
+        if self.format == Format::BigEndian {
+            b[..size].reverse();
+        }
+        Ok(match scalar {
+            Scalar::I8 => b[0] as i8 as f64,
+            Scalar::U8 => b[0] as f64,
+            Scalar::I16 => i16::from_le_bytes([b[0], b[1]]) as f64,
+            Scalar::U16 => u16::from_le_bytes([b[0], b[1]]) as f64,
+            Scalar::I32 => i32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64,
+            Scalar::U32 => u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64,
+            Scalar::F32 => f32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64,
+            Scalar::F64 => f64::from_le_bytes(b)
+        })
+    }
+}
+
+// the elements of the header and where the body starts
+fn header(bytes: &[u8]) -> Result<(Format, Vec<Element>, usize), String> {
+    const END: &[u8] = b"end_header";
+    let end = bytes.windows(END.len()).position(|w| w == END).ok_or_else(|| "PLY header without end_header".to_string())?;
+    let mut body = end + END.len();
+    while body < bytes.len() && bytes[body] != b'\n' {
+        body += 1;
+    }
+    let text = std::str::from_utf8(&bytes[..end]).map_err(|_| "PLY header is not ASCII".to_string())?;
+
+    let mut lines = text.lines().map(|l| l.split_whitespace().collect::<Vec<_>>()).filter(|l| !l.is_empty());
+    if lines.next() != Some(vec!["ply"]) {
+        return Err("not a PLY file".to_string());
+    }
+    let mut format = None;
+    let mut elements: Vec<Element> = Vec::new();
+    for line in lines {
+        match line.as_slice() {
+            ["format", "ascii", _] => format = Some(Format::Ascii),
+            ["format", "binary_little_endian", _] => format = Some(Format::LittleEndian),
+            ["format", "binary_big_endian", _] => format = Some(Format::BigEndian),
+            ["element", name, count] => elements.push(Element{
+                name: name.to_string(),
+                count: count.parse().map_err(|_| format!("invalid count of PLY element {}", name))?,
+                properties: Vec::new()
+            }),
+            ["property", "list", count, value, name] => elements.last_mut()
+                .ok_or_else(|| "PLY property before any element".to_string())?
+                .properties.push(Property{ name: name.to_string(), count: Some(Scalar::parse(count)?), value: Scalar::parse(value)? }),
+            ["property", value, name] => elements.last_mut()
+                .ok_or_else(|| "PLY property before any element".to_string())?
+                .properties.push(Property{ name: name.to_string(), count: None, value: Scalar::parse(value)? }),
+            ["comment", ..] | ["obj_info", ..] => (),
+            _ => return Err(format!("unexpected PLY header line '{}'", line.join(" ")))
+        }
+    }
+    let format = format.ok_or_else(|| "PLY header without format".to_string())?;
+    Ok((format, elements, body + 1))
+}
+
+// colors are stored for display, squared to undo the gamma 2 of the output.
+// Integers are normalized by their type's maximum, negative ones are black.
+fn channel(value: f64, scalar: Scalar) -> f32 {
+    let display = match scalar.max() {
+        Some(max) => (value / max).max(0.0) as f32,
+        None => value as f32
+    };
+    display * display
+}
+
+// list values are read as floats, vertex indices have to be whole and not negative
+fn vertex_index(value: f64, face: usize) -> Result<usize, String> {
+    if value < 0.0 || value.fract() != 0.0 {
+        return Err(format!("PLY face {} has invalid vertex index {}", face, value));
+    }
+    Ok(value as usize)
+}
+
+pub fn load_ply(bytes: &[u8], options: &MeshOptions, material: Box<dyn Material>) -> Result<Mesh, String> {
+    let (format, elements, start) = header(bytes)?;
+    let mut body = Body{ format, bytes, offset: start.min(bytes.len()) };
+
+    let mut positions = Vec::new();
+    let mut normals = Vec::new();
+    let mut uvs = Vec::new();
+    let mut colors = Vec::new();
+    let mut triangles = Vec::new();
+    for element in &elements {
+        let position = |names: &[&str]| element.properties.iter().position(|p| names.contains(&p.name.as_str()));
+        let x = [position(&["x"]), position(&["y"]), position(&["z"])];
+        let n = [position(&["nx"]), position(&["ny"]), position(&["nz"])];
+        let uv = [position(&["u", "s", "texture_u"]), position(&["v", "t", "texture_v"])];
+        let rgb = [position(&["red", "diffuse_red"]), position(&["green", "diffuse_green"]), position(&["blue", "diffuse_blue"])];
+        let indices = position(&["vertex_indices", "vertex_index"]);
+        if element.name == "vertex" && x.iter().any(|i| i.is_none()) {
+            return Err("PLY vertices without x, y and z".to_string());
+        }
+
+        let mut values = vec![0.0; element.properties.len()];
+        let mut list = Vec::new();
+        for row in 0..element.count {
+            for (i, property) in element.properties.iter().enumerate() {
+                match property.count {
+                    None => values[i] = body.read(property.value)?,
+                    Some(count) => {
+                        let count = body.read(count)? as usize;
+                        list.clear();
+                        for _ in 0..count {
+                            list.push(body.read(property.value)?);
+                        }
+                        if element.name == "face" && Some(i) == indices {
+                            let corners = list.iter().map(|&value| vertex_index(value, row)).collect::<Result<Vec<_>, _>>()?;
+                            triangles.extend((2..count).map(|k| [corners[0], corners[k - 1], corners[k]]));
+                        }
+                    }
+                }
+            }
+            if element.name != "vertex" {
+                continue;
+            }
+            let get = |i: Option<usize>| i.map_or(0.0, |i| values[i] as f32);
+            positions.push(Vec3{x: get(x[0]), y: get(x[1]), z: get(x[2])});
+            if n.iter().all(|i| i.is_some()) {
+                normals.push(Vec3{x: get(n[0]), y: get(n[1]), z: get(n[2])});
+            }
+            if uv.iter().all(|i| i.is_some()) {
+                uvs.push((get(uv[0]), get(uv[1])));
+            }
+            if let [Some(r), Some(g), Some(b)] = rgb {
+                let channel = |i: usize| channel(values[i], element.properties[i].value);
+                colors.push(Color{r: channel(r), g: channel(g), b: channel(b)});
+            }
+        }
+    }
+
+    options.apply(&mut positions);
+    let mut mesh = Mesh::new(positions, triangles, material)?;
+    if !normals.is_empty() {
+        mesh = mesh.with_normals(normals)?;
+    }
+    if !uvs.is_empty() {
+        mesh = mesh.with_uvs(uvs)?;
+    }
+    if !colors.is_empty() {
+        mesh = mesh.with_colors(colors)?;
+    }
+    Ok(mesh)
+}
@@ -0,0 +1,303 @@
+// Burley. "Physically Based Shading at Disney" (SIGGRAPH 2012 course)
+// and "Extending the Disney BRDF to a BSDF with Integrated Subsurface Scattering" (2015)
+
+use crate::color::Color;
+use crate::hittable::HitRecord;
+use crate::material::{Material, ScatterRecord};
+use crate::microfacet::{Frame, Ggx, fresnel_dielectric};
+use crate::ray::Ray;
+use crate::texture::{SolidColor, Texture};
+use crate::vec3::Vec3;
+use rand::Rng;
+use std::f32::consts;
+
+// the clear coat is a fixed, almost mirror like varnish layer
+const CLEARCOAT_ROUGHNESS: f32 = 0.1;
+
+/*
+    One material covering plastics, metals, glass and cloth. Every parameter is
+    a texture, scalar parameters read the red channel and are in [0, 1].
+*/
+pub struct Principled {
+    pub base_color: Box<dyn Texture>,
+    pub metallic: Box<dyn Texture>,
+    pub roughness: Box<dyn Texture>,
+    pub specular: Box<dyn Texture>,
+    pub clearcoat: Box<dyn Texture>,
+    pub sheen: Box<dyn Texture>,
+    pub transmission: Box<dyn Texture>,
+    pub anisotropic: Box<dyn Texture>,
+    pub ior: f32
+}
+
+// parameters looked up at one hit point
+struct Params {
+    base_color: Color,
+    metallic: f32,
+    roughness: f32,
+    specular: f32,
+    clearcoat: f32,
+    sheen: f32,
+    transmission: f32,
+    anisotropic: f32
+}
+
+// weights and selection probabilities of the lobes of the opaque part
+struct Lobes {
+    diffuse_weight: f32,
+    p_diffuse: f32,
+    p_specular: f32,
+    p_clearcoat: f32,
+    specular: Ggx,
+    clearcoat: Ggx
+}
+
+impl Principled {
+    pub fn new(base_color: Color) -> Self {
+        Self {
+            base_color: Box::new(SolidColor{ color_value: base_color }),
+            metallic: Box::new(SolidColor::gray(0.0)),
+            roughness: Box::new(SolidColor::gray(0.5)),
+            specular: Box::new(SolidColor::gray(0.5)),
+            clearcoat: Box::new(SolidColor::gray(0.0)),
+            sheen: Box::new(SolidColor::gray(0.0)),
+            transmission: Box::new(SolidColor::gray(0.0)),
+            anisotropic: Box::new(SolidColor::gray(0.0)),
+            ior: 1.5
+        }
+    }
+
+    fn params(&self, hit_record: &HitRecord) -> Params {
+        let (u, v, p) = (hit_record.u, hit_record.v, &hit_record.p);
+        let scalar = |texture: &dyn Texture| texture.value(u, v, p).r.clamp(0.0, 1.0);
+        Params {
+            base_color: self.base_color.value(u, v, p),
+            metallic: scalar(self.metallic.as_ref()),
+            roughness: scalar(self.roughness.as_ref()),
+            specular: scalar(self.specular.as_ref()),
+            clearcoat: scalar(self.clearcoat.as_ref()),
+            sheen: scalar(self.sheen.as_ref()),
+            transmission: scalar(self.transmission.as_ref()),
+            // without a tangent there is no direction to stretch the highlight along
+            anisotropic: if hit_record.tangent.is_some() { scalar(self.anisotropic.as_ref()) } else { 0.0 }
+        }
+    }
+}
+
+// the anisotropic highlight stretches along the surface's tangent
+fn shading_frame(hit_record: &HitRecord) -> Frame {
+    match hit_record.tangent {
+        Some(tangent) => Frame::with_tangent(hit_record.normal, tangent),
+        None => Frame::new(hit_record.normal)
+    }
+}
+
+fn schlick_weight(cosine: f32) -> f32 {
+    (1.0 - cosine).clamp(0.0, 1.0).powi(5)
+}
+
+fn lerp(a: Color, b: Color, t: f32) -> Color {
+    a * (1.0 - t) + b * t
+}
+
+impl Params {
+    // probability of sampling the transmission lobe, the rest is opaque
+    fn transmission_weight(&self) -> f32 {
+        (1.0 - self.metallic) * self.transmission
+    }
+
+    fn lobes(&self) -> Lobes {
+        let opaque = 1.0 - self.transmission_weight();
+        let diffuse_weight = if opaque > 0.0 {
+            (1.0 - self.metallic) * (1.0 - self.transmission) / opaque
+        } else {
+            0.0
+        };
+        let clearcoat_weight = 0.25 * self.clearcoat;
+        let total = diffuse_weight + 1.0 + clearcoat_weight;
+        Lobes {
+            diffuse_weight,
+            p_diffuse: diffuse_weight / total,
+            p_specular: 1.0 / total,
+            p_clearcoat: clearcoat_weight / total,
+            specular: Ggx::anisotropic(self.roughness, self.anisotropic),
+            clearcoat: Ggx::new(CLEARCOAT_ROUGHNESS)
+        }
+    }
+
+    // BSDF times cosine of the opaque part, optionally leaving out smooth lobes
+    fn eval_opaque(&self, lobes: &Lobes, wo: &Vec3, wi: &Vec3, include_smooth: bool) -> Color {
+        let black = Color{r: 0.0, g: 0.0, b: 0.0};
+        if wo.z <= 0.0 || wi.z <= 0.0 {
+            return black;
+        }
+        let h = (*wo + *wi).unit_vector();
+        let cos_d = wi.dot(&h);
+
+        let fl = schlick_weight(wi.z);
+        let fv = schlick_weight(wo.z);
+        let fd90 = 0.5 + 2.0 * self.roughness * cos_d * cos_d;
+        let retro = (1.0 + (fd90 - 1.0) * fl) * (1.0 + (fd90 - 1.0) * fv);
+        let diffuse = self.base_color * (retro / consts::PI);
+        let sheen = Color{r: 1.0, g: 1.0, b: 1.0} * (self.sheen * schlick_weight(cos_d));
+        let mut f = (diffuse + sheen) * lobes.diffuse_weight;
+
+        if include_smooth || !lobes.specular.is_smooth() {
+            let g = lobes.specular.g2(wo, wi);
+            f = f + self.specular_fresnel(cos_d) * (lobes.specular.d(&h) * g / (4.0 * wo.z * wi.z));
+        }
+
+        if self.clearcoat > 0.0 && (include_smooth || !lobes.clearcoat.is_smooth()) {
+            let g = lobes.clearcoat.g2(wo, wi);
+            let cc = self.clearcoat_fresnel(cos_d) * lobes.clearcoat.d(&h) * g / (4.0 * wo.z * wi.z);
+            f = f + Color{r: cc, g: cc, b: cc};
+        }
+        f * wi.z
+    }
+
+    fn specular_fresnel(&self, cosine: f32) -> Color {
+        let white = Color{r: 1.0, g: 1.0, b: 1.0};
+        let f0 = lerp(white * (0.08 * self.specular), self.base_color, self.metallic);
+        lerp(f0, white, schlick_weight(cosine))
+    }
+
+    // with the strength of the layer
+    fn clearcoat_fresnel(&self, cosine: f32) -> f32 {
+        0.25 * self.clearcoat * (0.04 + 0.96 * schlick_weight(cosine))
+    }
+
+    // mixture pdf of the lobes `eval_opaque` includes
+    fn pdf_opaque(&self, lobes: &Lobes, wo: &Vec3, wi: &Vec3, include_smooth: bool) -> f32 {
+        if wo.z <= 0.0 || wi.z <= 0.0 {
+            return 0.0;
+        }
+        let h = (*wo + *wi).unit_vector();
+        let mut pdf = lobes.p_diffuse * wi.z / consts::PI;
+        // visible normal sampling, reflected: G1(wo) D(h) / (4 cos_o)
+        if include_smooth || !lobes.specular.is_smooth() {
+            pdf += lobes.p_specular * lobes.specular.g1(wo) * lobes.specular.d(&h) / (4.0 * wo.z);
+        }
+        if include_smooth || !lobes.clearcoat.is_smooth() {
+            pdf += lobes.p_clearcoat * lobes.clearcoat.g1(wo) * lobes.clearcoat.d(&h) / (4.0 * wo.z);
+        }
+        pdf
+    }
+}
+
+impl Material for Principled {
+    fn name(&self) -> &str {
+        "principled"
+    }
+
+    fn scatter(&self, r_in: &Ray, hit_record: &HitRecord) -> Option<ScatterRecord> {
+        let mut rng = rand::thread_rng();
+        let params = self.params(hit_record);
+        let frame = shading_frame(hit_record);
+        let wo = frame.to_local(&(r_in.direction.unit_vector() * -1.0));
+        if wo.z <= 0.0 {
+            return None;
+        }
+
+        if rng.gen::<f32>() < params.transmission_weight() {
+            return sample_transmission(&params, &frame, &wo, r_in, hit_record, self.ior);
+        }
+
+        let lobes = params.lobes();
+        let choice = rng.gen::<f32>();
+        /*
+            A smooth lobe is left out of `eval`, lights it reflects are found by
+            this sample alone, so it is weighted by its own BSDF over its own
+            pdf. Otherwise the weight is the BSDF without the smooth lobes over
+            the mixture pdf of the others, the same ones light sampling sees.
+        */
+        let (wi, smooth_weight) = if choice < lobes.p_diffuse {
+            (Vec3::random_cosine_direction(), None)
+        } else if choice < lobes.p_diffuse + lobes.p_specular || lobes.p_clearcoat <= 0.0 {
+            let m = lobes.specular.sample_visible_normal(&wo, rng.gen(), rng.gen());
+            let wi = (wo * -1.0).reflect(&m);
+            let weight = lobes.specular.is_smooth().then(|| {
+                params.specular_fresnel(wo.dot(&m)) * (lobes.specular.g2(&wo, &wi) / lobes.specular.g1(&wo) / lobes.p_specular)
+            });
+            (wi, weight)
+        } else {
+            let m = lobes.clearcoat.sample_visible_normal(&wo, rng.gen(), rng.gen());
+            let wi = (wo * -1.0).reflect(&m);
+            let weight = lobes.clearcoat.is_smooth().then(|| {
+                let cc = params.clearcoat_fresnel(wo.dot(&m)) * lobes.clearcoat.g2(&wo, &wi) / lobes.clearcoat.g1(&wo) / lobes.p_clearcoat;
+                Color{r: cc, g: cc, b: cc}
+            });
+            (wi, weight)
+        };
+        if wi.z <= 0.0 {
+            return None;
+        }
+
+        let attenuation = match smooth_weight {
+            Some(weight) => weight,
+            None => {
+                let pdf = params.pdf_opaque(&lobes, &wo, &wi, false);
+                if pdf <= 0.0 {
+                    return None;
+                }
+                params.eval_opaque(&lobes, &wo, &wi, false) / pdf
+            }
+        };
+        Some(ScatterRecord{
+            attenuation,
+            specular: smooth_weight.is_some(),
+            scattered: Ray{
+                origin: hit_record.p,
+                direction: frame.to_world(&wi),
+                wavelength: r_in.wavelength,
+                time: r_in.time
+            }
+        })
+    }
+
+    fn eval(&self, r_in: &Ray, hit_record: &HitRecord, direction: &Vec3) -> Option<Color> {
+        let params = self.params(hit_record);
+        let opaque = 1.0 - params.transmission_weight();
+        if opaque <= 0.0 {
+            return None;
+        }
+        let frame = shading_frame(hit_record);
+        let wo = frame.to_local(&(r_in.direction.unit_vector() * -1.0));
+        let wi = frame.to_local(&direction.unit_vector());
+        let lobes = params.lobes();
+        Some(params.eval_opaque(&lobes, &wo, &wi, false) * opaque)
+    }
+}
+
+// rough glass tinted by the base color
+fn sample_transmission(params: &Params, frame: &Frame, wo: &Vec3, r_in: &Ray, hit_record: &HitRecord, ior: f32) -> Option<ScatterRecord> {
+    let mut rng = rand::thread_rng();
+    let etai_over_etat = if hit_record.front_face { 1.0 / ior } else { ior };
+    let ggx = Ggx::anisotropic(params.roughness, params.anisotropic);
+    let m = ggx.sample_visible_normal(wo, rng.gen(), rng.gen());
+    let reflect_prob = fresnel_dielectric(wo.dot(&m), etai_over_etat);
+
+    let (wi, tint) = if rng.gen::<f32>() < reflect_prob {
+        let wi = (*wo * -1.0).reflect(&m);
+        if wi.z <= 0.0 {
+            return None;
+        }
+        (wi, Color{r: 1.0, g: 1.0, b: 1.0})
+    } else {
+        let wi = (*wo * -1.0).refract(&m, etai_over_etat);
+        if wi.z >= 0.0 {
+            return None;
+        }
+        (wi, params.base_color)
+    };
+
+    Some(ScatterRecord{
+        attenuation: tint * (ggx.g2(wo, &wi) / ggx.g1(wo)),
+        specular: true,
+        scattered: Ray{
+            origin: hit_record.p,
+            direction: frame.to_world(&wi),
+            wavelength: r_in.wavelength,
+            time: r_in.time
+        }
+    })
+}
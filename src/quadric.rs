@@ -0,0 +1,363 @@
+/*
+    Analytic surfaces of revolution around the y axis, with their base at the
+    origin. Move them into the scene with an `Instance`. Partial sweeps keep
+    the points with an angle phi around the axis up to `phi_max` degrees,
+    measured from +x towards +z. The u coordinate follows phi and v the height,
+    or the angle around the tube for the torus.
+*/
+
+use crate::aabb::Aabb;
+use crate::hittable::{Hittable, HitRecord};
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::vec3::Vec3;
+use std::f32::consts::PI;
+
+fn phi_of(p: &Vec3) -> f32 {
+    let phi = p.z.atan2(p.x);
+    if phi < 0.0 { phi + 2.0 * PI } else { phi }
+}
+
+fn in_sweep(p: &Vec3, phi_max: f32) -> bool {
+    phi_max >= 360.0 || phi_of(p) <= phi_max.to_radians()
+}
+
+// both roots of a t^2 + b t + c in increasing order, avoiding cancellation
+pub fn solve_quadratic(a: f32, b: f32, c: f32) -> Option<(f32, f32)> {
+    if a.abs() < 1e-12 {
+        if b.abs() < 1e-12 {
+            return None;
+        }
+        let t = -c / b;
+        return Some((t, t));
+    }
+    let discriminant = b * b - 4.0 * a * c;
+    if discriminant < 0.0 {
+        return None;
+    }
+    let q = -0.5 * (b + b.signum() * discriminant.sqrt());
+    let (t0, t1) = if q == 0.0 { (0.0, 0.0) } else { (q / a, c / q) };
+    Some((t0.min(t1), t0.max(t1)))
+}
+
+// the nearer root in (t_min, t_max) whose point is part of the surface
+fn nearest_root(r: &Ray, (a, b, c): (f32, f32, f32), t_min: f32, t_max: f32, part_of: impl Fn(&Vec3) -> bool) -> Option<f32> {
+    let (t0, t1) = solve_quadratic(a, b, c)?;
+    [t0, t1].iter().copied().find(|&t| t > t_min && t < t_max && part_of(&r.point_at_parameter(t)))
+}
+
+#[allow(clippy::borrowed_box)]
+fn record<'a>(r: &Ray, t: f32, outward_normal: Vec3, (u, v): (f32, f32), material: &'a Box<dyn Material>) -> HitRecord<'a> {
+    let normal = outward_normal.unit_vector();
+    let mut hit_record = HitRecord{
+        p: r.point_at_parameter(t),
+        normal,
+        t,
+        u,
+        v,
+        tangent: None,
+        front_face: true,
+        color: None,
+        instance: 0,
+        material
+    };
+    hit_record.set_face_normal(r, normal);
+    hit_record
+}
+
+fn closest<'a>(a: Option<HitRecord<'a>>, b: Option<HitRecord<'a>>) -> Option<HitRecord<'a>> {
+    match (a, b) {
+        (Some(a), Some(b)) => if b.t < a.t { Some(b) } else { Some(a) },
+        (a, None) => a,
+        (None, b) => b
+    }
+}
+
+// flat cap at height `y` facing up or down, cut to the sweep
+#[allow(clippy::too_many_arguments, clippy::borrowed_box)]
+fn hit_cap<'a>(r: &Ray, t_min: f32, t_max: f32, y: f32, radius: f32, phi_max: f32, up: bool, material: &'a Box<dyn Material>) -> Option<HitRecord<'a>> {
+    if r.direction.y.abs() < 1e-12 {
+        return None;
+    }
+    let t = (y - r.origin.y) / r.direction.y;
+    if t <= t_min || t >= t_max {
+        return None;
+    }
+    let p = r.point_at_parameter(t);
+    let distance = (p.x * p.x + p.z * p.z).sqrt();
+    if distance > radius || !in_sweep(&p, phi_max) {
+        return None;
+    }
+    let normal = Vec3{x: 0.0, y: if up { 1.0 } else { -1.0 }, z: 0.0};
+    Some(record(r, t, normal, sweep_uv(&p, phi_max, distance / radius), material))
+}
+
+fn sweep_uv(p: &Vec3, phi_max: f32, v: f32) -> (f32, f32) {
+    (phi_of(p) / phi_max.to_radians().min(2.0 * PI), v)
+}
+
+// x^2 + z^2 = radius^2 between y_min and y_max
+pub struct Cylinder {
+    pub radius: f32,
+    pub y_min: f32,
+    pub y_max: f32,
+    pub phi_max: f32,
+    pub capped: bool,
+    pub material: Box<dyn Material>
+}
+
+impl Hittable for Cylinder {
+    fn hit(&self, r: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        let (o, d) = (r.origin, r.direction);
+        let coefficients = (
+            d.x * d.x + d.z * d.z,
+            2.0 * (o.x * d.x + o.z * d.z),
+            o.x * o.x + o.z * o.z - self.radius * self.radius
+        );
+        let side = nearest_root(r, coefficients, t_min, t_max, |p| {
+            p.y >= self.y_min && p.y <= self.y_max && in_sweep(p, self.phi_max)
+        }).map(|t| {
+            let p = r.point_at_parameter(t);
+            let uv = sweep_uv(&p, self.phi_max, (p.y - self.y_min) / (self.y_max - self.y_min));
+            record(r, t, Vec3{x: p.x, y: 0.0, z: p.z}, uv, &self.material)
+        });
+        if !self.capped {
+            return side;
+        }
+        let t_side = side.as_ref().map_or(t_max, |h| h.t);
+        let bottom = hit_cap(r, t_min, t_side, self.y_min, self.radius, self.phi_max, false, &self.material);
+        let top = hit_cap(r, t_min, t_side, self.y_max, self.radius, self.phi_max, true, &self.material);
+        closest(closest(side, bottom), top)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let r = self.radius;
+        Some(Aabb::new(Vec3{x: -r, y: self.y_min, z: -r}, Vec3{x: r, y: self.y_max, z: r}))
+    }
+}
+
+// cone with its base of `radius` at y = 0 and the apex at y = height
+pub struct Cone {
+    pub radius: f32,
+    pub height: f32,
+    pub phi_max: f32,
+    pub capped: bool,
+    pub material: Box<dyn Material>
+}
+
+impl Hittable for Cone {
+    fn hit(&self, r: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        // x^2 + z^2 = k (height - y)^2
+        let k = (self.radius / self.height) * (self.radius / self.height);
+        let (o, d) = (r.origin, r.direction);
+        let e = self.height - o.y;
+        let coefficients = (
+            d.x * d.x + d.z * d.z - k * d.y * d.y,
+            2.0 * (o.x * d.x + o.z * d.z + k * e * d.y),
+            o.x * o.x + o.z * o.z - k * e * e
+        );
+        let side = nearest_root(r, coefficients, t_min, t_max, |p| {
+            p.y >= 0.0 && p.y <= self.height && in_sweep(p, self.phi_max)
+        }).map(|t| {
+            let p = r.point_at_parameter(t);
+            let normal = Vec3{x: p.x, y: k * (self.height - p.y), z: p.z};
+            record(r, t, normal, sweep_uv(&p, self.phi_max, p.y / self.height), &self.material)
+        });
+        if !self.capped {
+            return side;
+        }
+        let t_side = side.as_ref().map_or(t_max, |h| h.t);
+        closest(side, hit_cap(r, t_min, t_side, 0.0, self.radius, self.phi_max, false, &self.material))
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let r = self.radius;
+        Some(Aabb::new(Vec3{x: -r, y: 0.0, z: -r}, Vec3{x: r, y: self.height, z: r}))
+    }
+}
+
+// x^2 + z^2 = radius^2 y / y_max, the radius is reached at y_max which has to be above 0
+pub struct Paraboloid {
+    pub radius: f32,
+    pub y_min: f32,
+    pub y_max: f32,
+    pub phi_max: f32,
+    pub material: Box<dyn Material>
+}
+
+impl Hittable for Paraboloid {
+    fn hit(&self, r: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        if self.y_max <= 0.0 {
+            return None;
+        }
+        let k = self.radius * self.radius / self.y_max;
+        let (o, d) = (r.origin, r.direction);
+        let coefficients = (
+            d.x * d.x + d.z * d.z,
+            2.0 * (o.x * d.x + o.z * d.z) - k * d.y,
+            o.x * o.x + o.z * o.z - k * o.y
+        );
+        nearest_root(r, coefficients, t_min, t_max, |p| {
+            p.y >= self.y_min && p.y <= self.y_max && in_sweep(p, self.phi_max)
+        }).map(|t| {
+            let p = r.point_at_parameter(t);
+            let uv = sweep_uv(&p, self.phi_max, (p.y - self.y_min) / (self.y_max - self.y_min));
+            record(r, t, Vec3{x: p.x, y: -0.5 * k, z: p.z}, uv, &self.material)
+        })
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let r = self.radius;
+        Some(Aabb::new(Vec3{x: -r, y: self.y_min, z: -r}, Vec3{x: r, y: self.y_max, z: r}))
+    }
+}
+
+// hyperboloid of one sheet (x^2 + z^2) / radius^2 - y^2 / c^2 = 1, `radius` is the waist
+pub struct Hyperboloid {
+    pub radius: f32,
+    pub c: f32,
+    pub y_min: f32,
+    pub y_max: f32,
+    pub phi_max: f32,
+    pub material: Box<dyn Material>
+}
+
+impl Hittable for Hyperboloid {
+    fn hit(&self, r: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        let a2 = self.radius * self.radius;
+        let c2 = self.c * self.c;
+        let (o, d) = (r.origin, r.direction);
+        let coefficients = (
+            (d.x * d.x + d.z * d.z) / a2 - d.y * d.y / c2,
+            2.0 * (o.x * d.x + o.z * d.z) / a2 - 2.0 * o.y * d.y / c2,
+            (o.x * o.x + o.z * o.z) / a2 - o.y * o.y / c2 - 1.0
+        );
+        nearest_root(r, coefficients, t_min, t_max, |p| {
+            p.y >= self.y_min && p.y <= self.y_max && in_sweep(p, self.phi_max)
+        }).map(|t| {
+            let p = r.point_at_parameter(t);
+            let uv = sweep_uv(&p, self.phi_max, (p.y - self.y_min) / (self.y_max - self.y_min));
+            record(r, t, Vec3{x: p.x / a2, y: -p.y / c2, z: p.z / a2}, uv, &self.material)
+        })
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let y = self.y_min.abs().max(self.y_max.abs());
+        let r = self.radius * (1.0 + y * y / (self.c * self.c)).sqrt();
+        Some(Aabb::new(Vec3{x: -r, y: self.y_min, z: -r}, Vec3{x: r, y: self.y_max, z: r}))
+    }
+}
+
+/*
+    Torus in the xz plane around the origin, the tube of `minor_radius` runs
+    at `major_radius` from the y axis.
+*/
+pub struct Torus {
+    pub major_radius: f32,
+    pub minor_radius: f32,
+    pub material: Box<dyn Material>
+}
+
+fn evaluate(coefficients: &[f64], t: f64) -> f64 {
+    coefficients.iter().fold(0.0, |value, c| value * t + c)
+}
+
+fn derivative(coefficients: &[f64]) -> Vec<f64> {
+    let degree = coefficients.len() - 1;
+    coefficients[..degree].iter().enumerate().map(|(i, c)| c * (degree - i) as f64).collect()
+}
+
+/*
+    Real roots in [lo, hi] of a polynomial, highest power first. Between the
+    roots of the derivative the polynomial is monotonic, so every sign change
+    there holds exactly one root that bisection finds.
+*/
+pub fn polynomial_roots(coefficients: &[f64], lo: f64, hi: f64) -> Vec<f64> {
+    if coefficients.len() < 2 {
+        return Vec::new();
+    }
+    if coefficients.len() == 2 {
+        let t = -coefficients[1] / coefficients[0];
+        return if coefficients[0] != 0.0 && t >= lo && t <= hi { vec![t] } else { Vec::new() };
+    }
+    let mut bounds = vec![lo];
+    bounds.extend(polynomial_roots(&derivative(coefficients), lo, hi));
+    bounds.push(hi);
+
+    let mut roots = Vec::new();
+    for pair in bounds.windows(2) {
+        let (mut a, mut b) = (pair[0], pair[1]);
+        let (fa, fb) = (evaluate(coefficients, a), evaluate(coefficients, b));
+        if fa == 0.0 {
+            roots.push(a);
+            continue;
+        }
+        if fa.signum() == fb.signum() {
+            continue;
+        }
+        for _ in 0..64 {
+            let mid = 0.5 * (a + b);
+            if evaluate(coefficients, mid).signum() == fa.signum() { a = mid } else { b = mid }
+        }
+        roots.push(0.5 * (a + b));
+    }
+    if evaluate(coefficients, hi) == 0.0 {
+        roots.push(hi);
+    }
+    roots.dedup();
+    roots
+}
+
+impl Hittable for Torus {
+    fn hit(&self, r: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        // solve along the unit direction starting where the ray enters the bounding sphere
+        let length = r.direction.length() as f64;
+        let d = r.direction.unit_vector();
+        let (dx, dy, dz) = (d.x as f64, d.y as f64, d.z as f64);
+        let bound = (self.major_radius + self.minor_radius) as f64;
+        let (ox, oy, oz) = (r.origin.x as f64, r.origin.y as f64, r.origin.z as f64);
+        let od = ox * dx + oy * dy + oz * dz;
+        let oo = ox * ox + oy * oy + oz * oz;
+        let discriminant = od * od - (oo - bound * bound);
+        if discriminant <= 0.0 {
+            return None;
+        }
+        let enter = (-od - discriminant.sqrt()).max(t_min as f64 * length);
+        let exit = (-od + discriminant.sqrt()).min(t_max as f64 * length);
+        if enter >= exit {
+            return None;
+        }
+
+        let (ox, oy, oz) = (ox + dx * enter, oy + dy * enter, oz + dz * enter);
+        let big = self.major_radius as f64 * self.major_radius as f64;
+        let k = ox * ox + oy * oy + oz * oz + big - self.minor_radius as f64 * self.minor_radius as f64;
+        let od = ox * dx + oy * dy + oz * dz;
+        let coefficients = [
+            1.0,
+            4.0 * od,
+            4.0 * od * od + 2.0 * k - 4.0 * big * (dx * dx + dz * dz),
+            4.0 * od * k - 8.0 * big * (ox * dx + oz * dz),
+            k * k - 4.0 * big * (ox * ox + oz * oz)
+        ];
+        let s = polynomial_roots(&coefficients, 0.0, exit - enter)
+            .into_iter()
+            .map(|s| ((s + enter) / length) as f32)
+            .find(|&t| t > t_min && t < t_max)?;
+
+        let p = r.point_at_parameter(s);
+        let ring = Vec3{x: p.x, y: 0.0, z: p.z};
+        let from_axis = ring.length();
+        // a spindle torus meets itself on the axis, where the normal runs along it
+        let tube_center = if from_axis > 0.0 { ring * (self.major_radius / from_axis) } else { ring };
+        // angle around the tube, starting on the outside of the ring
+        let theta = p.y.atan2(from_axis - self.major_radius);
+        let theta = if theta < 0.0 { theta + 2.0 * PI } else { theta };
+        Some(record(r, s, p - tube_center, (phi_of(&p) / (2.0 * PI), theta / (2.0 * PI)), &self.material))
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let outer = self.major_radius + self.minor_radius;
+        let r = self.minor_radius;
+        Some(Aabb::new(Vec3{x: -outer, y: -r, z: -outer}, Vec3{x: outer, y: r, z: outer}))
+    }
+}
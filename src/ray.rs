@@ -4,6 +4,10 @@ use crate::vec3::Vec3;
 pub struct Ray {
     pub origin: Vec3,
     pub direction: Vec3,
+    // hero wavelength in nanometers when rendering spectrally
+    pub wavelength: Option<f32>,
+    // instant within the shutter interval the ray samples, for motion blur
+    pub time: f32
 }
 
 impl Ray {
@@ -0,0 +1,11 @@
+use crate::hittable_list::HittableList;
+use crate::medium::Fog;
+use crate::sky::Sky;
+
+// everything rendered besides the camera
+pub struct Scene {
+    pub world: HittableList,
+    pub sky: Sky,
+    // scene wide medium the camera sits in, for depth cueing
+    pub fog: Option<Fog>
+}
@@ -0,0 +1,260 @@
+/*
+    Shapes given by signed distance functions, negative inside, and rendered
+    by sphere tracing: the ray can always advance by the distance to the
+    nearest surface without passing through one.
+    Hart. "Sphere Tracing: A Geometric Method for the Antialiased Ray Tracing
+    of Implicit Surfaces" (1996)
+*/
+
+use crate::aabb::Aabb;
+use crate::hittable::{Hittable, HitRecord};
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::sphere::get_sphere_uv;
+use crate::vec3::Vec3;
+
+pub trait Sdf {
+    fn distance(&self, p: &Vec3) -> f32;
+    // None for shapes without bounds, like repetitions
+    fn bounding_box(&self) -> Option<Aabb> {
+        None
+    }
+}
+
+fn splat(v: f32) -> Vec3 {
+    Vec3{x: v, y: v, z: v}
+}
+
+fn expand(b: &Aabb, margin: f32) -> Aabb {
+    Aabb::new(b.min - splat(margin), b.max + splat(margin))
+}
+
+// sphere around the origin
+pub struct SdfSphere {
+    pub radius: f32
+}
+
+impl Sdf for SdfSphere {
+    fn distance(&self, p: &Vec3) -> f32 {
+        p.length() - self.radius
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(Aabb::new(splat(-self.radius), splat(self.radius)))
+    }
+}
+
+// box around the origin reaching `half_extents` along each axis
+pub struct SdfBox {
+    pub half_extents: Vec3
+}
+
+impl Sdf for SdfBox {
+    fn distance(&self, p: &Vec3) -> f32 {
+        let q = Vec3{
+            x: p.x.abs() - self.half_extents.x,
+            y: p.y.abs() - self.half_extents.y,
+            z: p.z.abs() - self.half_extents.z
+        };
+        let outside = Vec3{x: q.x.max(0.0), y: q.y.max(0.0), z: q.z.max(0.0)};
+        outside.length() + q.x.max(q.y.max(q.z)).min(0.0)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(Aabb::new(self.half_extents * -1.0, self.half_extents))
+    }
+}
+
+// torus in the xz plane around the origin
+pub struct SdfTorus {
+    pub major_radius: f32,
+    pub minor_radius: f32
+}
+
+impl Sdf for SdfTorus {
+    fn distance(&self, p: &Vec3) -> f32 {
+        let ring = (p.x * p.x + p.z * p.z).sqrt() - self.major_radius;
+        (ring * ring + p.y * p.y).sqrt() - self.minor_radius
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let outer = self.major_radius + self.minor_radius;
+        let extent = Vec3{x: outer, y: self.minor_radius, z: outer};
+        Some(Aabb::new(extent * -1.0, extent))
+    }
+}
+
+pub struct Translate {
+    pub shape: Box<dyn Sdf>,
+    pub offset: Vec3
+}
+
+impl Sdf for Translate {
+    fn distance(&self, p: &Vec3) -> f32 {
+        self.shape.distance(&(*p - self.offset))
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let b = self.shape.bounding_box()?;
+        Some(Aabb::new(b.min + self.offset, b.max + self.offset))
+    }
+}
+
+// grows the shape by `radius`, rounding its edges
+pub struct Rounded {
+    pub shape: Box<dyn Sdf>,
+    pub radius: f32
+}
+
+impl Sdf for Rounded {
+    fn distance(&self, p: &Vec3) -> f32 {
+        self.shape.distance(p) - self.radius
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(expand(&self.shape.bounding_box()?, self.radius))
+    }
+}
+
+// union blending the shapes together within `k` of where they meet
+pub struct SmoothUnion {
+    pub a: Box<dyn Sdf>,
+    pub b: Box<dyn Sdf>,
+    pub k: f32
+}
+
+impl Sdf for SmoothUnion {
+    fn distance(&self, p: &Vec3) -> f32 {
+        let (d1, d2) = (self.a.distance(p), self.b.distance(p));
+        if self.k <= 0.0 {
+            return d1.min(d2);
+        }
+        // polynomial smooth minimum
+        let h = (0.5 + 0.5 * (d2 - d1) / self.k).clamp(0.0, 1.0);
+        d2 + (d1 - d2) * h - self.k * h * (1.0 - h)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let b = self.a.bounding_box()?.surrounding(&self.b.bounding_box()?);
+        Some(expand(&b, self.k))
+    }
+}
+
+/*
+    Twists the shape around the y axis by `rate` radians per unit of height.
+    The result is no exact distance any more, the object has to march with
+    a smaller step scale the stronger the twist.
+*/
+pub struct Twist {
+    pub shape: Box<dyn Sdf>,
+    pub rate: f32
+}
+
+impl Sdf for Twist {
+    fn distance(&self, p: &Vec3) -> f32 {
+        let (sin, cos) = (self.rate * p.y).sin_cos();
+        let q = Vec3{x: cos * p.x - sin * p.z, y: p.y, z: sin * p.x + cos * p.z};
+        self.shape.distance(&q)
+    }
+
+    // anything the shape covers at some angle around the axis
+    fn bounding_box(&self) -> Option<Aabb> {
+        let b = self.shape.bounding_box()?;
+        let radius = b.corners().iter().map(|c| (c.x * c.x + c.z * c.z).sqrt()).fold(0.0, f32::max);
+        Some(Aabb::new(Vec3{x: -radius, y: b.min.y, z: -radius}, Vec3{x: radius, y: b.max.y, z: radius}))
+    }
+}
+
+// copies of the shape every `period` along each axis, a period of zero leaves an axis alone
+pub struct Repeat {
+    pub shape: Box<dyn Sdf>,
+    pub period: Vec3
+}
+
+impl Sdf for Repeat {
+    fn distance(&self, p: &Vec3) -> f32 {
+        let wrap = |x: f32, period: f32| if period > 0.0 { x - period * (x / period).round() } else { x };
+        let q = Vec3{x: wrap(p.x, self.period.x), y: wrap(p.y, self.period.y), z: wrap(p.z, self.period.z)};
+        self.shape.distance(&q)
+    }
+}
+
+/*
+    A signed distance shape in the world. Marching stops at `epsilon` from
+    the surface, after `max_steps` or `max_distance` along unbounded shapes.
+*/
+pub struct SdfObject {
+    pub shape: Box<dyn Sdf>,
+    pub material: Box<dyn Material>,
+    pub epsilon: f32,
+    pub max_steps: usize,
+    pub max_distance: f32,
+    // below one for shapes whose distances overestimate, like twists
+    pub step_scale: f32
+}
+
+impl SdfObject {
+    pub fn new(shape: Box<dyn Sdf>, material: Box<dyn Material>) -> Self {
+        Self { shape, material, epsilon: 1e-4, max_steps: 256, max_distance: 100.0, step_scale: 1.0 }
+    }
+
+    // gradient by central differences on a tetrahedron
+    fn normal(&self, p: &Vec3) -> Vec3 {
+        let h = self.epsilon;
+        let offsets = [
+            Vec3{x: 1.0, y: -1.0, z: -1.0},
+            Vec3{x: -1.0, y: -1.0, z: 1.0},
+            Vec3{x: -1.0, y: 1.0, z: -1.0},
+            Vec3{x: 1.0, y: 1.0, z: 1.0}
+        ];
+        let gradient = offsets.iter().fold(splat(0.0), |sum, k| sum + *k * self.shape.distance(&(*p + *k * h)));
+        gradient.unit_vector()
+    }
+}
+
+impl Hittable for SdfObject {
+    fn hit(&self, r: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        let (t0, t1) = match self.shape.bounding_box() {
+            Some(bounds) => expand(&bounds, 2.0 * self.epsilon).interval(r, t_min, t_max)?,
+            None => (t_min, t_max)
+        };
+        // march along the unit direction so steps are world distances
+        let length = r.direction.length();
+        let direction = r.direction / length;
+        let (mut s, end) = (t0 * length, t1.min(t0 + self.max_distance / length) * length);
+        // rays leaving a surface start inside and march on the flipped distance
+        let inside = self.shape.distance(&(r.origin + direction * (t_min * length))) < 0.0;
+        for _ in 0..self.max_steps {
+            if s >= end {
+                return None;
+            }
+            let p = r.origin + direction * s;
+            let d = self.shape.distance(&p);
+            let d = if inside { -d } else { d };
+            if d < self.epsilon && s > t_min * length {
+                let normal = self.normal(&p);
+                let (u, v) = get_sphere_uv(&normal);
+                let mut hit_record = HitRecord{
+                    p,
+                    normal,
+                    t: s / length,
+                    u,
+                    v,
+                    tangent: None,
+                    front_face: true,
+                    color: None,
+                    instance: 0,
+                    material: &self.material
+                };
+                hit_record.set_face_normal(r, normal);
+                return Some(hit_record);
+            }
+            s += d.max(self.epsilon) * self.step_scale;
+        }
+        None
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        self.shape.bounding_box()
+    }
+}
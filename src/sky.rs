@@ -0,0 +1,185 @@
+// Preetham, Shirley, Smits. "A Practical Analytic Model for Daylight" (SIGGRAPH 1999)
+
+use crate::color::Color;
+use crate::vec3::Vec3;
+use rand::Rng;
+use std::f32::consts;
+
+// angular radius of the sun disk seen from the earth
+const SUN_ANGULAR_RADIUS: f32 = 0.00465;
+// radiance of the unattenuated sun, chosen so that direct sunlight is a few
+// times brighter than the whole sky dome like it is outdoors
+const SUN_RADIANCE: f32 = 40000.0;
+// converts the model's luminance (kcd/m^2) to the renderer's radiance scale
+const SKY_SCALE: f32 = 0.1;
+
+struct Perez {
+    a: f32,
+    b: f32,
+    c: f32,
+    d: f32,
+    e: f32
+}
+
+impl Perez {
+    fn eval(&self, cos_theta: f32, gamma: f32) -> f32 {
+        let cos_gamma = gamma.cos();
+        (1.0 + self.a * (self.b / cos_theta).exp())
+            * (1.0 + self.c * (self.d * gamma).exp() + self.e * cos_gamma * cos_gamma)
+    }
+}
+
+pub struct Sky {
+    pub sun_direction: Vec3,
+    pub turbidity: f32,
+    // Perez distributions for luminance and chromaticity
+    perez_y: Perez,
+    perez_x: Perez,
+    perez_yy: Perez,
+    // zenith values divided by F(0, theta_s)
+    zenith_y: f32,
+    zenith_x: f32,
+    zenith_yy: f32,
+    sun_radiance: Color,
+    sun_cos_max: f32
+}
+
+/*
+    sun elevation and azimuth are in degrees, the azimuth is measured from -z
+    (the direction the default camera looks at) towards +x
+*/
+impl Sky {
+    pub fn new(elevation: f32, azimuth: f32, turbidity: f32) -> Self {
+        let t = turbidity.max(1.7);
+        // the model is only valid while the sun is above the horizon
+        let elevation = elevation.clamp(0.5, 90.0).to_radians();
+        let azimuth = azimuth.to_radians();
+        let sun_direction = Vec3{
+            x: elevation.cos() * azimuth.sin(),
+            y: elevation.sin(),
+            z: -elevation.cos() * azimuth.cos()
+        };
+
+        let theta_s = consts::FRAC_PI_2 - elevation;
+        let perez_y = Perez{
+            a: 0.1787 * t - 1.4630,
+            b: -0.3554 * t + 0.4275,
+            c: -0.0227 * t + 5.3251,
+            d: 0.1206 * t - 2.5771,
+            e: -0.0670 * t + 0.3703
+        };
+        let perez_x = Perez{
+            a: -0.0193 * t - 0.2592,
+            b: -0.0665 * t + 0.0008,
+            c: -0.0004 * t + 0.2125,
+            d: -0.0641 * t - 0.8989,
+            e: -0.0033 * t + 0.0452
+        };
+        let perez_yy = Perez{
+            a: -0.0167 * t - 0.2608,
+            b: -0.0950 * t + 0.0092,
+            c: -0.0079 * t + 0.2102,
+            d: -0.0441 * t - 1.6537,
+            e: -0.0109 * t + 0.0529
+        };
+
+        let chi = (4.0 / 9.0 - t / 120.0) * (consts::PI - 2.0 * theta_s);
+        let luminance = (4.0453 * t - 4.9710) * chi.tan() - 0.2155 * t + 2.4192;
+
+        let th = theta_s;
+        let th2 = th * th;
+        let th3 = th2 * th;
+        let t2 = t * t;
+        let chroma_x = t2 * (0.00166 * th3 - 0.00375 * th2 + 0.00209 * th)
+            + t * (-0.02903 * th3 + 0.06377 * th2 - 0.03202 * th + 0.00394)
+            + (0.11693 * th3 - 0.21196 * th2 + 0.06052 * th + 0.25886);
+        let chroma_y = t2 * (0.00275 * th3 - 0.00610 * th2 + 0.00317 * th)
+            + t * (-0.04214 * th3 + 0.08970 * th2 - 0.04153 * th + 0.00516)
+            + (0.15346 * th3 - 0.26756 * th2 + 0.06670 * th + 0.26688);
+
+        let cos_theta_s = theta_s.cos();
+        let zenith_y = luminance / perez_y.eval(1.0, theta_s);
+        let zenith_x = chroma_x / perez_x.eval(1.0, theta_s);
+        let zenith_yy = chroma_y / perez_yy.eval(1.0, theta_s);
+
+        Self {
+            sun_direction,
+            turbidity: t,
+            perez_y,
+            perez_x,
+            perez_yy,
+            zenith_y,
+            zenith_x,
+            zenith_yy,
+            sun_radiance: sun_transmittance(cos_theta_s, t) * SUN_RADIANCE,
+            sun_cos_max: SUN_ANGULAR_RADIUS.cos()
+        }
+    }
+
+    // the sun in `direction` instead of at angles, e.g. opposite an imported directional light
+    pub fn towards(direction: &Vec3, turbidity: f32) -> Self {
+        let d = direction.unit_vector();
+        Self::new(d.y.clamp(-1.0, 1.0).asin().to_degrees(), d.x.atan2(-d.z).to_degrees(), turbidity)
+    }
+
+    // radiance of the sky dome alone, without the sun disk
+    pub fn radiance(&self, direction: &Vec3) -> Color {
+        let d = direction.unit_vector();
+        // below the horizon the sky is mirrored, the ground is part of the scene
+        let cos_theta = d.y.abs().max(0.01);
+        let cos_gamma = d.dot(&self.sun_direction).clamp(-1.0, 1.0);
+        let gamma = cos_gamma.acos();
+
+        let luminance = self.zenith_y * self.perez_y.eval(cos_theta, gamma);
+        let x = self.zenith_x * self.perez_x.eval(cos_theta, gamma);
+        let y = self.zenith_yy * self.perez_yy.eval(cos_theta, gamma);
+
+        let big_x = x / y * luminance;
+        let big_z = (1.0 - x - y) / y * luminance;
+        Color::from_xyz(big_x, luminance, big_z) * SKY_SCALE
+    }
+
+    // radiance seen along `direction` including the sun disk
+    pub fn radiance_with_sun(&self, direction: &Vec3) -> Color {
+        let sky = self.radiance(direction);
+        if direction.unit_vector().dot(&self.sun_direction) >= self.sun_cos_max {
+            return sky + self.sun_radiance;
+        }
+        sky
+    }
+
+    pub fn sun_radiance(&self) -> Color {
+        self.sun_radiance
+    }
+
+    // solid angle of the sun disk, the inverse of the pdf of `sample_sun`
+    pub fn sun_solid_angle(&self) -> f32 {
+        2.0 * consts::PI * (1.0 - self.sun_cos_max)
+    }
+
+    // uniformly samples a direction towards the sun disk
+    pub fn sample_sun(&self) -> Vec3 {
+        let mut rng = rand::thread_rng();
+        let cos_theta = 1.0 - rng.gen::<f32>() * (1.0 - self.sun_cos_max);
+        let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+        let phi = 2.0 * consts::PI * rng.gen::<f32>();
+        let (s, t) = self.sun_direction.orthonormal_basis();
+        s * (sin_theta * phi.cos()) + t * (sin_theta * phi.sin()) + self.sun_direction * cos_theta
+    }
+}
+
+/*
+    Rayleigh and aerosol extinction of sunlight along the optical air mass,
+    evaluated at one representative wavelength (in micrometers) per channel
+*/
+fn sun_transmittance(cos_theta_s: f32, turbidity: f32) -> Color {
+    let theta_deg = cos_theta_s.acos().to_degrees();
+    let air_mass = 1.0 / (cos_theta_s + 0.15 * (93.885 - theta_deg).powf(-1.253));
+    let beta = 0.04608 * turbidity - 0.04586;
+    let transmittance = |lambda: f32| {
+        let rayleigh = 0.008735 * lambda.powf(-4.08);
+        let aerosol = beta * lambda.powf(-1.3);
+        (-(rayleigh + aerosol) * air_mass).exp()
+    };
+    Color{r: transmittance(0.68), g: transmittance(0.55), b: transmittance(0.44)}
+}
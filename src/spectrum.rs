@@ -0,0 +1,114 @@
+/*
+    Spectral rendering with hero wavelength sampling.
+    Wilkie et al. "Hero Wavelength Spectral Sampling" (EGSR 2014)
+
+    A spectral path carries three wavelengths, the hero one stored in the ray
+    and two more spread evenly over the visible range. The three channels of
+    a `Color` then hold the radiance at these wavelengths instead of RGB.
+*/
+
+use crate::color::Color;
+use rand::Rng;
+
+pub const LAMBDA_MIN: f32 = 380.0;
+pub const LAMBDA_MAX: f32 = 780.0;
+
+// integral of the CIE y matching function over [LAMBDA_MIN, LAMBDA_MAX]
+const CIE_Y_INTEGRAL: f32 = 106.91973;
+// linear sRGB of a constant spectrum, divided out so it renders white
+const WHITE_RGB: Color = Color{r: 1.2005363, g: 0.9496664, b: 0.9078287};
+
+pub fn sample_hero_wavelength() -> f32 {
+    let mut rng = rand::thread_rng();
+    LAMBDA_MIN + rng.gen::<f32>() * (LAMBDA_MAX - LAMBDA_MIN)
+}
+
+// the hero wavelength and its two rotations
+pub fn wavelengths(hero: f32) -> [f32; 3] {
+    let range = LAMBDA_MAX - LAMBDA_MIN;
+    let rotate = |i: f32| LAMBDA_MIN + (hero - LAMBDA_MIN + i * range / 3.0) % range;
+    [hero, rotate(1.0), rotate(2.0)]
+}
+
+// Smits. "An RGB to Spectrum Conversion for Reflectances" (1999)
+// ten bins between 380nm and 720nm
+const SMITS_WHITE: [f32; 10] = [1.0000, 1.0000, 0.9999, 0.9993, 0.9992, 0.9998, 1.0000, 1.0000, 1.0000, 1.0000];
+const SMITS_CYAN: [f32; 10] = [0.9710, 0.9426, 1.0007, 1.0007, 1.0007, 1.0007, 0.1564, 0.0000, 0.0000, 0.0000];
+const SMITS_MAGENTA: [f32; 10] = [1.0000, 1.0000, 0.9685, 0.2229, 0.0000, 0.0458, 0.8369, 1.0000, 1.0000, 0.9959];
+const SMITS_YELLOW: [f32; 10] = [0.0001, 0.0000, 0.1088, 0.6651, 1.0000, 1.0000, 0.9996, 0.9586, 0.9685, 0.9840];
+const SMITS_RED: [f32; 10] = [0.1012, 0.0515, 0.0000, 0.0000, 0.0000, 0.0000, 0.8325, 1.0149, 1.0149, 1.0149];
+const SMITS_GREEN: [f32; 10] = [0.0000, 0.0000, 0.0273, 0.7937, 1.0000, 0.9418, 0.1719, 0.0000, 0.0000, 0.0025];
+const SMITS_BLUE: [f32; 10] = [1.0000, 1.0000, 0.8916, 0.3323, 0.0000, 0.0000, 0.0003, 0.0369, 0.0483, 0.0496];
+
+// value of the smooth spectrum upsampled from an RGB color at one wavelength
+pub fn rgb_to_spectrum(color: &Color, lambda: f32) -> f32 {
+    let bin = (((lambda - 380.0) / 34.0) as i32).clamp(0, 9) as usize;
+    let (r, g, b) = (color.r, color.g, color.b);
+    if r <= g && r <= b {
+        let base = r * SMITS_WHITE[bin];
+        if g <= b {
+            base + (g - r) * SMITS_CYAN[bin] + (b - g) * SMITS_BLUE[bin]
+        } else {
+            base + (b - r) * SMITS_CYAN[bin] + (g - b) * SMITS_GREEN[bin]
+        }
+    } else if g <= r && g <= b {
+        let base = g * SMITS_WHITE[bin];
+        if r <= b {
+            base + (r - g) * SMITS_MAGENTA[bin] + (b - r) * SMITS_BLUE[bin]
+        } else {
+            base + (b - g) * SMITS_MAGENTA[bin] + (r - b) * SMITS_RED[bin]
+        }
+    } else {
+        let base = b * SMITS_WHITE[bin];
+        if r <= g {
+            base + (r - b) * SMITS_YELLOW[bin] + (g - r) * SMITS_GREEN[bin]
+        } else {
+            base + (g - b) * SMITS_YELLOW[bin] + (r - g) * SMITS_RED[bin]
+        }
+    }
+}
+
+// an RGB color sampled at the three wavelengths of a spectral path
+pub fn sample_rgb(color: &Color, hero: f32) -> Color {
+    let [l0, l1, l2] = wavelengths(hero);
+    Color{
+        r: rgb_to_spectrum(color, l0),
+        g: rgb_to_spectrum(color, l1),
+        b: rgb_to_spectrum(color, l2)
+    }
+}
+
+// Wyman, Sloan, Shirley. "Simple Analytic Approximations to the CIE XYZ
+// Color Matching Functions" (JCGT 2013)
+fn gaussian(lambda: f32, mu: f32, sigma1: f32, sigma2: f32) -> f32 {
+    let sigma = if lambda < mu { sigma1 } else { sigma2 };
+    let t = (lambda - mu) / sigma;
+    (-0.5 * t * t).exp()
+}
+
+pub fn cie_xyz(lambda: f32) -> (f32, f32, f32) {
+    let x = 1.056 * gaussian(lambda, 599.8, 37.9, 31.0)
+        + 0.362 * gaussian(lambda, 442.0, 16.0, 26.7)
+        - 0.065 * gaussian(lambda, 501.1, 20.4, 26.2);
+    let y = 0.821 * gaussian(lambda, 568.8, 46.9, 40.5)
+        + 0.286 * gaussian(lambda, 530.9, 16.3, 31.1);
+    let z = 1.217 * gaussian(lambda, 437.0, 11.8, 36.0)
+        + 0.681 * gaussian(lambda, 459.0, 26.0, 13.8);
+    (x, y, z)
+}
+
+// Monte Carlo estimate of the linear sRGB color of a spectral path sample
+pub fn to_rgb(radiance: &Color, hero: f32) -> Color {
+    let values = [radiance.r, radiance.g, radiance.b];
+    let pdf = 1.0 / (LAMBDA_MAX - LAMBDA_MIN);
+    let (mut x, mut y, mut z) = (0.0, 0.0, 0.0);
+    for (lambda, value) in wavelengths(hero).iter().zip(values.iter()) {
+        let (cx, cy, cz) = cie_xyz(*lambda);
+        x += value * cx;
+        y += value * cy;
+        z += value * cz;
+    }
+    let scale = 1.0 / (3.0 * pdf * CIE_Y_INTEGRAL);
+    let rgb = Color::from_xyz(x * scale, y * scale, z * scale);
+    Color{r: rgb.r / WHITE_RGB.r, g: rgb.g / WHITE_RGB.g, b: rgb.b / WHITE_RGB.b}
+}
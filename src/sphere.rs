@@ -1,3 +1,4 @@
+use crate::aabb::Aabb;
 use crate::material::Material;
 use crate::ray::Ray;
 use crate::hittable::{Hittable,HitRecord};
@@ -12,41 +13,127 @@ pub struct Sphere {
 
 
 
+/*
+    p: a given point on the sphere of radius one, centered at the origin.
+    u: returned value [0,1] of angle around the Y axis from X=-1.
+    v: returned value [0,1] of angle from Y=-1 to Y=+1.
+*/
+pub fn get_sphere_uv(p: &Vec3) -> (f32, f32) {
+    let theta = (-p.y).clamp(-1.0, 1.0).acos();
+    let phi = (-p.z).atan2(p.x) + std::f32::consts::PI;
+    (phi / (2.0 * std::f32::consts::PI), theta / std::f32::consts::PI)
+}
+
+// eastwards, none at the poles
+fn sphere_tangent(p: &Vec3) -> Option<Vec3> {
+    let tangent = Vec3{x: p.z, y: 0.0, z: -p.x};
+    if tangent.squared_length() > 1e-12 { Some(tangent.unit_vector()) } else { None }
+}
+
 impl Hittable for Sphere {
     fn hit(&self, r: &Ray, t_min: f32, t_max:f32) -> Option<HitRecord> {
-        let oc = r.origin - self.center;
-        let a = r.direction.squared_length();
-        let half_b = oc.dot(&r.direction);
-        let c = oc.squared_length() - self.radius * self.radius;
-        let discriminant = half_b* half_b - a * c;
-
-        if discriminant <= 0.0 { return None;}
-        let root = discriminant.sqrt();
-        let mut temp = (-half_b - root) / a;
-        if temp < t_max && temp > t_min {
-            let mut ret = HitRecord{
-                t: temp,
-                p: r.point_at_parameter(temp),
-                normal: (r.point_at_parameter(temp) - self.center) / self.radius,
-                front_face: true,
-                material: &self.material
-            };
-            ret.set_face_normal(r, (ret.p - self.center)/self.radius);
-            return Some(ret);
-        }
+        hit_sphere(self.center, self.radius, &self.material, r, t_min, t_max)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(sphere_box(self.center, self.radius))
+    }
+}
+
+fn sphere_box(center: Vec3, radius: f32) -> Aabb {
+    let r = radius.abs();
+    let extent = Vec3{x: r, y: r, z: r};
+    Aabb::new(center - extent, center + extent)
+}
+
+#[allow(clippy::borrowed_box)]
+fn hit_sphere<'a>(center: Vec3, radius: f32, material: &'a Box<dyn Material>, r: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'a>> {
+    let oc = r.origin - center;
+    let a = r.direction.squared_length();
+    let half_b = oc.dot(&r.direction);
+    let c = oc.squared_length() - radius * radius;
+    let discriminant = half_b* half_b - a * c;
+
+    if discriminant <= 0.0 { return None;}
+    let root = discriminant.sqrt();
+    let mut temp = (-half_b - root) / a;
+    if temp < t_max && temp > t_min {
+        let mut ret = HitRecord{
+            t: temp,
+            p: r.point_at_parameter(temp),
+            normal: (r.point_at_parameter(temp) - center) / radius,
+            u: 0.0,
+            v: 0.0,
+            tangent: None,
+            front_face: true,
+            color: None,
+            instance: 0,
+            material
+        };
+        ret.set_face_normal(r, (ret.p - center)/radius);
+        let (u, v) = get_sphere_uv(&((ret.p - center) / radius));
+        ret.u = u;
+        ret.v = v;
+        ret.tangent = sphere_tangent(&((ret.p - center) / radius));
+        return Some(ret);
+    }
 
-        temp = (-half_b + root) / a;
-        if temp < t_max && temp > t_min {
-            let mut ret = HitRecord{
-                t: temp,
-                p: r.point_at_parameter(temp),
-                normal: (r.point_at_parameter(temp) - self.center) / self.radius,
-                front_face: true,
-                material: &self.material
-            };
-            ret.set_face_normal(r, (ret.p - self.center)/self.radius);
-            return Some(ret);
+    temp = (-half_b + root) / a;
+    if temp < t_max && temp > t_min {
+        let mut ret = HitRecord{
+            t: temp,
+            p: r.point_at_parameter(temp),
+            normal: (r.point_at_parameter(temp) - center) / radius,
+            u: 0.0,
+            v: 0.0,
+            tangent: None,
+            front_face: true,
+            color: None,
+            instance: 0,
+            material
+        };
+        ret.set_face_normal(r, (ret.p - center)/radius);
+        let (u, v) = get_sphere_uv(&((ret.p - center) / radius));
+        ret.u = u;
+        ret.v = v;
+        ret.tangent = sphere_tangent(&((ret.p - center) / radius));
+        return Some(ret);
+    }
+    None
+}
+
+/*
+    A sphere moving in a straight line, at center0 at time0 and at center1 at
+    time1. Rays sample the sphere at their own time.
+*/
+pub struct MovingSphere {
+    pub center0: Vec3,
+    pub center1: Vec3,
+    pub time0: f32,
+    pub time1: f32,
+    pub radius: f32,
+    pub material: Box<dyn Material>
+}
+
+impl MovingSphere {
+    pub fn center(&self, time: f32) -> Vec3 {
+        if self.time1 <= self.time0 {
+            return self.center0;
         }
-        return None;
+        // outside of the motion the sphere rests at its ends
+        let s = ((time - self.time0) / (self.time1 - self.time0)).clamp(0.0, 1.0);
+        self.center0 + (self.center1 - self.center0) * s
     }
-}
\ No newline at end of file
+}
+
+impl Hittable for MovingSphere {
+    fn hit(&self, r: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        hit_sphere(self.center(r.time), self.radius, &self.material, r, t_min, t_max)
+    }
+
+    // the sphere sweeps between its two centers while the shutter is open
+    fn bounding_box(&self) -> Option<Aabb> {
+        let start = sphere_box(self.center0, self.radius);
+        Some(start.surrounding(&sphere_box(self.center1, self.radius)))
+    }
+}
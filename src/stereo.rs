@@ -0,0 +1,139 @@
+/*
+    Stereo pairs for VR previews. Both eyes are rendered into one packed
+    image, `StereoCamera` maps the packed image to the camera of each eye so
+    the renderer doesn't need to know about stereo at all.
+*/
+
+use crate::camera::{frame, Camera, PerspectiveCamera, Shutter};
+use crate::ray::Ray;
+use crate::vec3::Vec3;
+use std::f32::consts;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Eye {
+    Left,
+    Right
+}
+
+impl Eye {
+    // the side of the eye, along the camera's right
+    fn side(&self) -> f32 {
+        match self {
+            Eye::Left => -1.0,
+            Eye::Right => 1.0
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum StereoLayout {
+    // left eye in the left half
+    SideBySide,
+    // left eye in the upper half
+    TopBottom
+}
+
+/*
+    Omni-directional stereo, an equirectangular panorama per eye. Every ray
+    starts on the circle the eyes turn on as the head looks around, tangent
+    to it.
+    Peleg et al. "Omnistereo: Panoramic Stereo Imaging" (2001)
+*/
+pub struct OdsCamera {
+    origin: Vec3,
+    u: Vec3,
+    v: Vec3,
+    w: Vec3,
+    // signed distance of the eye from the center, half the interpupillary distance
+    offset: f32,
+    shutter: Shutter
+}
+
+impl OdsCamera {
+    pub fn new(lookfrom: Vec3, lookat: Vec3, vup: Vec3, ipd: f32, eye: Eye) -> Self {
+        let (u, v, w) = frame(lookfrom, lookat, vup);
+        Self { origin: lookfrom, u, v, w, offset: eye.side() * ipd / 2.0, shutter: Shutter{ open: 0.0, close: 0.0 } }
+    }
+
+    pub fn with_shutter(mut self, open: f32, close: f32) -> Self {
+        self.shutter = Shutter{ open, close };
+        self
+    }
+}
+
+impl Camera for OdsCamera {
+    fn get_ray(&self, s: f32, t: f32) -> Ray {
+        let longitude = (s - 0.5) * 2.0 * consts::PI;
+        let latitude = (t - 0.5) * consts::PI;
+        let (sin_lat, cos_lat) = latitude.sin_cos();
+        let (sin_lon, cos_lon) = longitude.sin_cos();
+        // the head's right while looking towards this longitude
+        let right = self.u * cos_lon + self.w * sin_lon;
+        Ray{
+            origin: self.origin + right * self.offset,
+            direction: self.u * (cos_lat * sin_lon) + self.v * sin_lat - self.w * (cos_lat * cos_lon),
+            wavelength: None,
+            time: self.shutter.sample()
+        }
+    }
+}
+
+pub struct StereoCamera {
+    pub left: Box<dyn Camera>,
+    pub right: Box<dyn Camera>,
+    pub layout: StereoLayout
+}
+
+impl StereoCamera {
+    /*
+        Parallel perspective cameras `ipd` apart with their images shifted to
+        line up at `convergence`, objects at that distance appear at the
+        screen. Unlike cameras turned towards each other this keeps vertical
+        parallax out. `aspect_ratio` is the one of the packed image.
+    */
+    #[allow(clippy::too_many_arguments)]
+    pub fn perspective(lookfrom: Vec3, lookat: Vec3, vup: Vec3, vfov: f32, aspect_ratio: f32, ipd: f32, convergence: f32, layout: StereoLayout) -> Self {
+        let eye_aspect = match layout {
+            StereoLayout::SideBySide => aspect_ratio / 2.0,
+            StereoLayout::TopBottom => aspect_ratio * 2.0
+        };
+        let forward = (lookat - lookfrom).unit_vector();
+        let right = forward.cross(&vup).unit_vector();
+        let image_width = 2.0 * (vfov.to_radians() / 2.0).tan() * eye_aspect * convergence;
+        let eye = |eye: Eye| -> Box<dyn Camera> {
+            let offset = eye.side() * ipd / 2.0;
+            let position = lookfrom + right * offset;
+            Box::new(PerspectiveCamera::new(position, position + forward, vup, vfov, eye_aspect, 0.0, convergence)
+                .with_lens_shift(-offset / image_width))
+        };
+        Self { left: eye(Eye::Left), right: eye(Eye::Right), layout }
+    }
+
+    pub fn ods(lookfrom: Vec3, lookat: Vec3, vup: Vec3, ipd: f32, layout: StereoLayout) -> Self {
+        Self {
+            left: Box::new(OdsCamera::new(lookfrom, lookat, vup, ipd, Eye::Left)),
+            right: Box::new(OdsCamera::new(lookfrom, lookat, vup, ipd, Eye::Right)),
+            layout
+        }
+    }
+}
+
+impl Camera for StereoCamera {
+    fn get_ray(&self, s: f32, t: f32) -> Ray {
+        match self.layout {
+            StereoLayout::SideBySide if s < 0.5 => self.left.get_ray(2.0 * s, t),
+            StereoLayout::SideBySide => self.right.get_ray(2.0 * s - 1.0, t),
+            StereoLayout::TopBottom if t >= 0.5 => self.left.get_ray(s, 2.0 * t - 1.0),
+            StereoLayout::TopBottom => self.right.get_ray(s, 2.0 * t)
+        }
+    }
+
+    fn sample_ray(&self, s: f32, t: f32) -> Option<Ray> {
+        match self.layout {
+            StereoLayout::SideBySide if s < 0.5 => self.left.sample_ray(2.0 * s, t),
+            StereoLayout::SideBySide => self.right.sample_ray(2.0 * s - 1.0, t),
+            StereoLayout::TopBottom if t >= 0.5 => self.left.sample_ray(s, 2.0 * t - 1.0),
+            StereoLayout::TopBottom => self.right.sample_ray(s, 2.0 * t)
+        }
+    }
+}
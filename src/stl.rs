@@ -0,0 +1,75 @@
+/*
+    Loads triangle meshes from ASCII and binary STL files, the format parts
+    for 3D printing come in. STL stores every triangle with its own corners,
+    corners at the same position are merged again so the mesh is indexed.
+    The facet normals are ignored, the triangles are flat either way.
+*/
+
+use crate::material::Material;
+use crate::triangle::{Mesh, MeshOptions};
+use crate::vec3::Vec3;
+use std::collections::HashMap;
+
+// header, triangle count and 50 bytes per triangle
+const HEADER_SIZE: usize = 84;
+const TRIANGLE_SIZE: usize = 50;
+
+fn binary_corners(bytes: &[u8]) -> Option<Vec<Vec3>> {
+    let count = bytes.get(80..HEADER_SIZE)?;
+    let count = u32::from_le_bytes([count[0], count[1], count[2], count[3]]) as usize;
+    // ASCII files start with "solid" too, only the size tells them apart
+    if Some(bytes.len()) != count.checked_mul(TRIANGLE_SIZE).map(|size| size + HEADER_SIZE) {
+        return None;
+    }
+    let float = |at: usize| f32::from_le_bytes([bytes[at], bytes[at + 1], bytes[at + 2], bytes[at + 3]]);
+    let corners = (0..count).flat_map(|i| {
+        // the corners follow the facet normal
+        let start = HEADER_SIZE + i * TRIANGLE_SIZE + 12;
+        (0..3).map(move |k| start + 12 * k)
+    }).map(|at| Vec3{x: float(at), y: float(at + 4), z: float(at + 8)});
+    Some(corners.collect())
+}
+
+fn ascii_corners(bytes: &[u8]) -> Result<Vec<Vec3>, String> {
+    let text = std::str::from_utf8(bytes).map_err(|_| "STL file is neither binary nor ASCII".to_string())?;
+    if !text.trim_start().starts_with("solid") {
+        return Err("not an STL file".to_string());
+    }
+    let mut corners = Vec::new();
+    let mut tokens = text.split_whitespace();
+    while let Some(token) = tokens.next() {
+        if token != "vertex" {
+            continue;
+        }
+        let mut coordinate = || -> Result<f32, String> {
+            let value = tokens.next().ok_or_else(|| "STL vertex with less than 3 coordinates".to_string())?;
+            value.parse().map_err(|_| format!("invalid STL coordinate '{}'", value))
+        };
+        corners.push(Vec3{x: coordinate()?, y: coordinate()?, z: coordinate()?});
+    }
+    if corners.len() % 3 != 0 {
+        return Err("STL facet without 3 vertices".to_string());
+    }
+    Ok(corners)
+}
+
+pub fn load_stl(bytes: &[u8], options: &MeshOptions, material: Box<dyn Material>) -> Result<Mesh, String> {
+    let corners = match binary_corners(bytes) {
+        Some(corners) => corners,
+        None => ascii_corners(bytes)?
+    };
+
+    let mut positions = Vec::new();
+    let mut indices: HashMap<[u32; 3], usize> = HashMap::new();
+    let corner_indices: Vec<usize> = corners.iter().map(|p| {
+        let key = [p.x.to_bits(), p.y.to_bits(), p.z.to_bits()];
+        *indices.entry(key).or_insert_with(|| {
+            positions.push(*p);
+            positions.len() - 1
+        })
+    }).collect();
+    let triangles = corner_indices.chunks_exact(3).map(|t| [t[0], t[1], t[2]]).collect();
+
+    options.apply(&mut positions);
+    Mesh::new(positions, triangles, material)
+}
@@ -0,0 +1,76 @@
+use crate::color::Color;
+use crate::vec3::Vec3;
+
+pub trait Texture {
+    fn value(&self, u: f32, v: f32, p: &Vec3) -> Color;
+}
+
+pub struct SolidColor {
+    pub color_value: Color
+}
+
+impl SolidColor {
+    pub fn new(r: f32, g: f32, b: f32) -> Self {
+        Self { color_value: Color{r, g, b} }
+    }
+
+    // for scalar parameters, which read the red channel
+    pub fn gray(value: f32) -> Self {
+        Self::new(value, value, value)
+    }
+}
+
+impl Texture for SolidColor {
+    fn value(&self, _u: f32, _v: f32, _p: &Vec3) -> Color {
+        self.color_value
+    }
+}
+
+pub struct CheckerTexture {
+    pub odd: Box<dyn Texture>,
+    pub even: Box<dyn Texture>,
+    pub scale: f32
+}
+
+impl Texture for CheckerTexture {
+    fn value(&self, u: f32, v: f32, p: &Vec3) -> Color {
+        let sines = (self.scale * p.x).sin() * (self.scale * p.y).sin() * (self.scale * p.z).sin();
+        if sines < 0.0 {
+            return self.odd.value(u, v, p);
+        }
+        self.even.value(u, v, p)
+    }
+}
+
+/*
+    Terrain colors for heightfields, which put the height in u and the slope
+    in v: `low` in the valleys, `high` above the snow line and `steep` on
+    slopes too steep for either, blended over `blend`.
+*/
+pub struct TerrainTexture {
+    pub low: Box<dyn Texture>,
+    pub high: Box<dyn Texture>,
+    pub steep: Box<dyn Texture>,
+    pub snow_line: f32,
+    pub max_slope: f32,
+    pub blend: f32
+}
+
+impl TerrainTexture {
+    fn smoothstep(&self, edge: f32, x: f32) -> f32 {
+        if self.blend <= 0.0 {
+            return if x < edge { 0.0 } else { 1.0 };
+        }
+        let t = ((x - edge) / self.blend + 0.5).clamp(0.0, 1.0);
+        t * t * (3.0 - 2.0 * t)
+    }
+}
+
+impl Texture for TerrainTexture {
+    fn value(&self, u: f32, v: f32, p: &Vec3) -> Color {
+        let height = self.smoothstep(self.snow_line, u);
+        let flat = self.low.value(u, v, p) * (1.0 - height) + self.high.value(u, v, p) * height;
+        let steep = self.smoothstep(self.max_slope, v);
+        flat * (1.0 - steep) + self.steep.value(u, v, p) * steep
+    }
+}
@@ -0,0 +1,246 @@
+use crate::vec3::Vec3;
+use std::ops::Mul;
+
+// row major 4x4 matrix acting on column vectors
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Mat4 {
+    pub m: [[f32; 4]; 4]
+}
+
+impl Mat4 {
+    pub fn identity() -> Mat4 {
+        let mut m = [[0.0; 4]; 4];
+        for (i, row) in m.iter_mut().enumerate() {
+            row[i] = 1.0;
+        }
+        Mat4{ m }
+    }
+
+    // the 16 values column after column, as glTF and pbrt store matrices
+    pub fn from_column_major(values: &[f32; 16]) -> Mat4 {
+        let mut m = [[0.0; 4]; 4];
+        for (i, row) in m.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value = values[j * 4 + i];
+            }
+        }
+        Mat4{ m }
+    }
+
+    pub fn transpose(&self) -> Mat4 {
+        let mut m = [[0.0; 4]; 4];
+        for (i, row) in m.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value = self.m[j][i];
+            }
+        }
+        Mat4{ m }
+    }
+
+    // Gauss-Jordan elimination with partial pivoting, None for singular matrices
+    pub fn inverse(&self) -> Option<Mat4> {
+        let mut a = self.m;
+        let mut inv = Mat4::identity().m;
+        for col in 0..4 {
+            let pivot = (col..4).max_by(|&i, &j| a[i][col].abs().total_cmp(&a[j][col].abs()))?;
+            if a[pivot][col].abs() < 1e-12 {
+                return None;
+            }
+            a.swap(col, pivot);
+            inv.swap(col, pivot);
+
+            let scale = 1.0 / a[col][col];
+            for j in 0..4 {
+                a[col][j] *= scale;
+                inv[col][j] *= scale;
+            }
+            for row in 0..4 {
+                if row == col {
+                    continue;
+                }
+                let factor = a[row][col];
+                for j in 0..4 {
+                    a[row][j] -= factor * a[col][j];
+                    inv[row][j] -= factor * inv[col][j];
+                }
+            }
+        }
+        Some(Mat4{ m: inv })
+    }
+
+    pub fn transform_point(&self, p: &Vec3) -> Vec3 {
+        let m = &self.m;
+        let x = m[0][0] * p.x + m[0][1] * p.y + m[0][2] * p.z + m[0][3];
+        let y = m[1][0] * p.x + m[1][1] * p.y + m[1][2] * p.z + m[1][3];
+        let z = m[2][0] * p.x + m[2][1] * p.y + m[2][2] * p.z + m[2][3];
+        let w = m[3][0] * p.x + m[3][1] * p.y + m[3][2] * p.z + m[3][3];
+        if w == 1.0 { Vec3{x, y, z} } else { Vec3{x: x / w, y: y / w, z: z / w} }
+    }
+
+    // directions ignore the translation
+    pub fn transform_vector(&self, v: &Vec3) -> Vec3 {
+        let m = &self.m;
+        Vec3{
+            x: m[0][0] * v.x + m[0][1] * v.y + m[0][2] * v.z,
+            y: m[1][0] * v.x + m[1][1] * v.y + m[1][2] * v.z,
+            z: m[2][0] * v.x + m[2][1] * v.y + m[2][2] * v.z
+        }
+    }
+}
+
+impl Mul for Mat4 {
+    type Output = Mat4;
+
+    fn mul(self, rhs: Mat4) -> Mat4 {
+        let mut m = [[0.0; 4]; 4];
+        for (i, row) in m.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value = (0..4).map(|k| self.m[i][k] * rhs.m[k][j]).sum();
+            }
+        }
+        Mat4{ m }
+    }
+}
+
+/*
+    An affine object to world transform together with its inverse. `a * b`
+    applies `b` first, like the matrices it holds.
+*/
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Transform {
+    pub matrix: Mat4,
+    pub inverse: Mat4
+}
+
+impl Transform {
+    pub fn identity() -> Transform {
+        Transform{ matrix: Mat4::identity(), inverse: Mat4::identity() }
+    }
+
+    pub fn from_matrix(matrix: Mat4) -> Option<Transform> {
+        Some(Transform{ matrix, inverse: matrix.inverse()? })
+    }
+
+    pub fn translate(offset: Vec3) -> Transform {
+        let mut matrix = Mat4::identity();
+        let mut inverse = Mat4::identity();
+        for (i, d) in [offset.x, offset.y, offset.z].iter().enumerate() {
+            matrix.m[i][3] = *d;
+            inverse.m[i][3] = -d;
+        }
+        Transform{ matrix, inverse }
+    }
+
+    // non uniform scaling along the axes, no factor may be zero
+    pub fn scale(factors: Vec3) -> Transform {
+        let mut matrix = Mat4::identity();
+        let mut inverse = Mat4::identity();
+        for (i, s) in [factors.x, factors.y, factors.z].iter().enumerate() {
+            matrix.m[i][i] = *s;
+            inverse.m[i][i] = 1.0 / s;
+        }
+        Transform{ matrix, inverse }
+    }
+
+    // counterclockwise rotation in degrees looking down the axis towards the origin
+    pub fn rotate(axis: Vec3, degrees: f32) -> Transform {
+        let a = axis.unit_vector();
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        let c = 1.0 - cos;
+        let mut matrix = Mat4::identity();
+        matrix.m[0] = [a.x * a.x * c + cos, a.x * a.y * c - a.z * sin, a.x * a.z * c + a.y * sin, 0.0];
+        matrix.m[1] = [a.y * a.x * c + a.z * sin, a.y * a.y * c + cos, a.y * a.z * c - a.x * sin, 0.0];
+        matrix.m[2] = [a.z * a.x * c - a.y * sin, a.z * a.y * c + a.x * sin, a.z * a.z * c + cos, 0.0];
+        // rotations are orthogonal
+        Transform{ matrix, inverse: matrix.transpose() }
+    }
+
+    // rotation by the unit quaternion x i + y j + z k + w
+    pub fn rotate_quaternion(x: f32, y: f32, z: f32, w: f32) -> Transform {
+        let mut matrix = Mat4::identity();
+        matrix.m[0] = [1.0 - 2.0 * (y * y + z * z), 2.0 * (x * y - z * w), 2.0 * (x * z + y * w), 0.0];
+        matrix.m[1] = [2.0 * (x * y + z * w), 1.0 - 2.0 * (x * x + z * z), 2.0 * (y * z - x * w), 0.0];
+        matrix.m[2] = [2.0 * (x * z - y * w), 2.0 * (y * z + x * w), 1.0 - 2.0 * (x * x + y * y), 0.0];
+        Transform{ matrix, inverse: matrix.transpose() }
+    }
+
+    pub fn invert(&self) -> Transform {
+        Transform{ matrix: self.inverse, inverse: self.matrix }
+    }
+
+    pub fn point(&self, p: &Vec3) -> Vec3 {
+        self.matrix.transform_point(p)
+    }
+
+    pub fn vector(&self, v: &Vec3) -> Vec3 {
+        self.matrix.transform_vector(v)
+    }
+
+    // normals transform with the inverse transpose to stay perpendicular to the surface
+    pub fn normal(&self, n: &Vec3) -> Vec3 {
+        self.inverse.transpose().transform_vector(n)
+    }
+}
+
+impl Mul for Transform {
+    type Output = Transform;
+
+    fn mul(self, rhs: Transform) -> Transform {
+        Transform{ matrix: self.matrix * rhs.matrix, inverse: rhs.inverse * self.inverse }
+    }
+}
+
+// scale, then rotation about an axis through the origin, then translation
+#[derive(Debug, Copy, Clone)]
+pub struct Pose {
+    pub translation: Vec3,
+    pub axis: Vec3,
+    pub degrees: f32,
+    pub scale: Vec3
+}
+
+impl Pose {
+    pub fn new(translation: Vec3) -> Pose {
+        Pose{
+            translation,
+            axis: Vec3{x: 0.0, y: 1.0, z: 0.0},
+            degrees: 0.0,
+            scale: Vec3{x: 1.0, y: 1.0, z: 1.0}
+        }
+    }
+
+    pub fn transform(&self) -> Transform {
+        Transform::translate(self.translation) * Transform::rotate(self.axis, self.degrees) * Transform::scale(self.scale)
+    }
+}
+
+/*
+    Transform moving from the `start` pose at time0 to the `end` pose at
+    time1. The components are interpolated rather than the matrices, so a
+    spinning object keeps its shape mid way.
+*/
+#[derive(Debug, Copy, Clone)]
+pub struct AnimatedTransform {
+    pub start: Pose,
+    pub end: Pose,
+    pub time0: f32,
+    pub time1: f32
+}
+
+impl AnimatedTransform {
+    pub fn at(&self, time: f32) -> Transform {
+        if self.time1 <= self.time0 {
+            return self.start.transform();
+        }
+        let s = ((time - self.time0) / (self.time1 - self.time0)).clamp(0.0, 1.0);
+        let lerp = |a: Vec3, b: Vec3| a + (b - a) * s;
+        let axis = lerp(self.start.axis.unit_vector(), self.end.axis.unit_vector());
+        Pose{
+            translation: lerp(self.start.translation, self.end.translation),
+            // opposite axes have no axis in between, keep the start one
+            axis: if axis.squared_length() > 1e-8 { axis } else { self.start.axis },
+            degrees: self.start.degrees + (self.end.degrees - self.start.degrees) * s,
+            scale: lerp(self.start.scale, self.end.scale)
+        }.transform()
+    }
+}
@@ -0,0 +1,264 @@
+use crate::aabb::Aabb;
+use crate::color::Color;
+use crate::hittable::{Hittable, HitRecord};
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::vec3::Vec3;
+
+/*
+    Ray parameter and barycentric coordinates of the second and third corner
+    where the ray crosses the triangle, from either side.
+    Möller, Trumbore. "Fast, Minimum Storage Ray/Triangle Intersection" (1997)
+*/
+pub fn intersect_triangle(r: &Ray, p0: &Vec3, p1: &Vec3, p2: &Vec3, t_min: f32, t_max: f32) -> Option<(f32, f32, f32)> {
+    let edge1 = *p1 - *p0;
+    let edge2 = *p2 - *p0;
+    let pvec = r.direction.cross(&edge2);
+    let det = edge1.dot(&pvec);
+    if det.abs() < 1e-12 {
+        return None;
+    }
+    let inv_det = 1.0 / det;
+    let tvec = r.origin - *p0;
+    let b1 = tvec.dot(&pvec) * inv_det;
+    if !(0.0..=1.0).contains(&b1) {
+        return None;
+    }
+    let qvec = tvec.cross(&edge1);
+    let b2 = r.direction.dot(&qvec) * inv_det;
+    if b2 < 0.0 || b1 + b2 > 1.0 {
+        return None;
+    }
+    let t = edge2.dot(&qvec) * inv_det;
+    if t <= t_min || t >= t_max {
+        return None;
+    }
+    Some((t, b1, b2))
+}
+
+// triangles per leaf of the bounding volume hierarchy
+const LEAF_SIZE: usize = 4;
+
+enum BvhNode {
+    Leaf { bounds: Aabb, first: usize, count: usize },
+    Inner { bounds: Aabb, left: usize, right: usize }
+}
+
+impl BvhNode {
+    fn bounds(&self) -> &Aabb {
+        match self {
+            BvhNode::Leaf{ bounds, .. } => bounds,
+            BvhNode::Inner{ bounds, .. } => bounds
+        }
+    }
+}
+
+// unit conversion and placement of imported meshes
+#[derive(Debug, Copy, Clone)]
+pub struct MeshOptions {
+    // e.g. 0.001 for a part modeled in millimeters
+    pub scale: f32,
+    // moves the center of the bounding box to the origin before scaling
+    pub recenter: bool
+}
+
+impl Default for MeshOptions {
+    fn default() -> Self {
+        Self { scale: 1.0, recenter: false }
+    }
+}
+
+impl MeshOptions {
+    pub fn apply(&self, positions: &mut [Vec3]) {
+        let center = match Aabb::from_points(positions) {
+            Some(bounds) if self.recenter => (bounds.min + bounds.max) / 2.0,
+            _ => Vec3{x: 0.0, y: 0.0, z: 0.0}
+        };
+        for p in positions.iter_mut() {
+            *p = (*p - center) * self.scale;
+        }
+    }
+}
+
+/*
+    Indexed triangle mesh with optional per vertex normals, texture
+    coordinates and colors. Without normals the triangles are flat and without
+    texture coordinates u and v are the barycentrics of the hit.
+
+    The triangles are sorted into a bounding volume hierarchy, split at the
+    median along the longest axis, so rays only test the few triangles near
+    their path.
+*/
+pub struct Mesh {
+    positions: Vec<Vec3>,
+    normals: Option<Vec<Vec3>>,
+    uvs: Option<Vec<(f32, f32)>>,
+    colors: Option<Vec<Color>>,
+    triangles: Vec<[usize; 3]>,
+    nodes: Vec<BvhNode>,
+    pub material: Box<dyn Material>
+}
+
+impl Mesh {
+    pub fn new(positions: Vec<Vec3>, triangles: Vec<[usize; 3]>, material: Box<dyn Material>) -> Result<Self, String> {
+        if let Some(index) = triangles.iter().flatten().find(|i| **i >= positions.len()) {
+            return Err(format!("triangle index {} out of range for {} vertices", index, positions.len()));
+        }
+        let mut mesh = Self { positions, normals: None, uvs: None, colors: None, triangles, nodes: Vec::new(), material };
+        if !mesh.triangles.is_empty() {
+            let count = mesh.triangles.len();
+            mesh.build(0, count);
+        }
+        Ok(mesh)
+    }
+
+    pub fn with_normals(mut self, normals: Vec<Vec3>) -> Result<Self, String> {
+        if normals.len() != self.positions.len() {
+            return Err(format!("{} normals for {} vertices", normals.len(), self.positions.len()));
+        }
+        self.normals = Some(normals);
+        Ok(self)
+    }
+
+    pub fn with_uvs(mut self, uvs: Vec<(f32, f32)>) -> Result<Self, String> {
+        if uvs.len() != self.positions.len() {
+            return Err(format!("{} texture coordinates for {} vertices", uvs.len(), self.positions.len()));
+        }
+        self.uvs = Some(uvs);
+        Ok(self)
+    }
+
+    // shown by materials reading the hit color, like `VertexLambertian`
+    pub fn with_colors(mut self, colors: Vec<Color>) -> Result<Self, String> {
+        if colors.len() != self.positions.len() {
+            return Err(format!("{} colors for {} vertices", colors.len(), self.positions.len()));
+        }
+        self.colors = Some(colors);
+        Ok(self)
+    }
+
+    pub fn positions(&self) -> &[Vec3] {
+        &self.positions
+    }
+
+    pub fn triangles(&self) -> &[[usize; 3]] {
+        &self.triangles
+    }
+
+    // dp/du of the triangle, the first edge where the texture coordinates don't say
+    fn tangent(&self, a: usize, b: usize, c: usize) -> Vec3 {
+        let (pa, pb, pc) = (self.positions[a], self.positions[b], self.positions[c]);
+        if let Some(uv) = &self.uvs {
+            let (du1, dv1) = (uv[b].0 - uv[a].0, uv[b].1 - uv[a].1);
+            let (du2, dv2) = (uv[c].0 - uv[a].0, uv[c].1 - uv[a].1);
+            let determinant = du1 * dv2 - dv1 * du2;
+            if determinant.abs() > 1e-12 {
+                return ((pb - pa) * dv2 - (pc - pa) * dv1) / determinant;
+            }
+        }
+        pb - pa
+    }
+
+    fn triangle_box(&self, triangle: &[usize; 3]) -> Aabb {
+        let corners = [self.positions[triangle[0]], self.positions[triangle[1]], self.positions[triangle[2]]];
+        Aabb::from_points(&corners).unwrap().pad(1e-5)
+    }
+
+    fn centroid(&self, triangle: &[usize; 3]) -> Vec3 {
+        (self.positions[triangle[0]] + self.positions[triangle[1]] + self.positions[triangle[2]]) / 3.0
+    }
+
+    // builds the node for triangles[first..first + count] and returns its index
+    fn build(&mut self, first: usize, count: usize) -> usize {
+        let bounds = self.triangles[first..first + count].iter()
+            .map(|t| self.triangle_box(t))
+            .reduce(|a, b| a.surrounding(&b))
+            .unwrap();
+        let index = self.nodes.len();
+        if count <= LEAF_SIZE {
+            self.nodes.push(BvhNode::Leaf{ bounds, first, count });
+            return index;
+        }
+
+        let extent = bounds.max - bounds.min;
+        let axis = if extent.x > extent.y && extent.x > extent.z { 0 } else if extent.y > extent.z { 1 } else { 2 };
+        let key = |c: Vec3| match axis { 0 => c.x, 1 => c.y, _ => c.z };
+        let mut slice: Vec<[usize; 3]> = self.triangles[first..first + count].to_vec();
+        slice.sort_by(|a, b| key(self.centroid(a)).total_cmp(&key(self.centroid(b))));
+        self.triangles[first..first + count].copy_from_slice(&slice);
+
+        // placeholder until both children exist
+        self.nodes.push(BvhNode::Leaf{ bounds, first, count });
+        let half = count / 2;
+        let left = self.build(first, half);
+        let right = self.build(first + half, count - half);
+        self.nodes[index] = BvhNode::Inner{ bounds, left, right };
+        index
+    }
+}
+
+impl Hittable for Mesh {
+    fn hit(&self, r: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        if self.nodes.is_empty() {
+            return None;
+        }
+        let mut closest: Option<(f32, usize, f32, f32)> = None;
+        let mut stack = vec![0];
+        while let Some(index) = stack.pop() {
+            let limit = closest.map_or(t_max, |c| c.0);
+            let node = &self.nodes[index];
+            if !node.bounds().hit(r, t_min, limit) {
+                continue;
+            }
+            match node {
+                BvhNode::Leaf{ first, count, .. } => {
+                    for k in *first..first + count {
+                        let [a, b, c] = self.triangles[k];
+                        let limit = closest.map_or(t_max, |c| c.0);
+                        if let Some((t, b1, b2)) = intersect_triangle(r, &self.positions[a], &self.positions[b], &self.positions[c], t_min, limit) {
+                            closest = Some((t, k, b1, b2));
+                        }
+                    }
+                },
+                BvhNode::Inner{ left, right, .. } => {
+                    stack.push(*right);
+                    stack.push(*left);
+                }
+            }
+        }
+
+        let (t, k, b1, b2) = closest?;
+        let [a, b, c] = self.triangles[k];
+        let b0 = 1.0 - b1 - b2;
+        let geometric = (self.positions[b] - self.positions[a]).cross(&(self.positions[c] - self.positions[a])).unit_vector();
+        let normal = match &self.normals {
+            Some(n) => (n[a] * b0 + n[b] * b1 + n[c] * b2).unit_vector(),
+            None => geometric
+        };
+        let (u, v) = match &self.uvs {
+            Some(uv) => (uv[a].0 * b0 + uv[b].0 * b1 + uv[c].0 * b2, uv[a].1 * b0 + uv[b].1 * b1 + uv[c].1 * b2),
+            None => (b1, b2)
+        };
+        let color = self.colors.as_ref().map(|colors| colors[a] * b0 + colors[b] * b1 + colors[c] * b2);
+        let tangent = self.tangent(a, b, c);
+        // the triangle decides the side, the interpolated normal the shading
+        let front_face = r.direction.dot(&geometric) < 0.0;
+        let normal = if normal.dot(&geometric) < 0.0 { normal * -1.0 } else { normal };
+        Some(HitRecord{
+            p: r.point_at_parameter(t),
+            normal: if front_face { normal } else { normal * -1.0 },
+            t,
+            u,
+            v,
+            tangent: Some(tangent),
+            front_face,
+            color,
+            instance: 0,
+            material: &self.material
+        })
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        self.nodes.first().map(|node| *node.bounds())
+    }
+}
@@ -85,6 +85,20 @@ impl Vec3 {
         }
     }
 
+    // cosine weighted direction around +z
+    pub fn random_cosine_direction() -> Vec3 {
+        let mut rng = rand::thread_rng();
+        let r1 = rng.gen::<f32>();
+        let r2 = rng.gen::<f32>();
+        let phi = 2.0 * consts::PI * r1;
+        let r = r2.sqrt();
+        Vec3{
+            x: r * phi.cos(),
+            y: r * phi.sin(),
+            z: (1.0 - r2).sqrt()
+        }
+    }
+
     /*
     vec3 random_in_unit_disk() {
         while (true) {
@@ -141,6 +155,14 @@ impl Vec3 {
         }
     }
 
+    // Two unit vectors completing an orthonormal basis with this unit vector
+    pub fn orthonormal_basis(&self) -> (Vec3, Vec3) {
+        let a = if self.x.abs() > 0.9 { Vec3{x: 0.0, y: 1.0, z: 0.0} } else { Vec3{x: 1.0, y: 0.0, z: 0.0} };
+        let t = self.cross(&a).unit_vector();
+        let s = t.cross(self);
+        return (s, t);
+    }
+
     pub fn to_str(&self) -> String {
     
         format!("(value x: {}, value y: {}, value z: {})", self.x, self.y, self.z)
@@ -0,0 +1,373 @@
+/*
+    The scene together with the camera settings, the handle JavaScript keeps
+    to render and to interact with the image.
+*/
+
+use crate::camera::{Camera, Projection};
+use crate::color::Color;
+use crate::film::{Exposure, Film, NEUTRAL_KELVIN};
+use crate::hittable::Hittable;
+use crate::integrator::ray_color;
+use crate::orbit::OrbitController;
+use crate::ray::Ray;
+use crate::scene::Scene;
+use crate::sky::Sky;
+use crate::spectrum;
+use crate::vec3::Vec3;
+use crate::write_color;
+use rand::Rng;
+use wasm_bindgen::prelude::*;
+
+// while the camera moves, one path this deep per block of pixels this wide
+const PREVIEW_DEPTH: i32 = 4;
+const PREVIEW_BLOCK: u32 = 4;
+
+// what is seen through a pixel
+#[wasm_bindgen]
+pub struct Pick {
+    object_id: u32,
+    material: String,
+    position: Vec3,
+    normal: Vec3,
+    distance: f32
+}
+
+#[wasm_bindgen]
+impl Pick {
+    // id of the object in the scene's `HittableList`
+    #[wasm_bindgen(getter)]
+    pub fn object_id(&self) -> u32 {
+        self.object_id
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn material(&self) -> String {
+        self.material.clone()
+    }
+
+    // x, y and z of the hit point in world space
+    #[wasm_bindgen(getter)]
+    pub fn position(&self) -> Vec<f32> {
+        vec![self.position.x, self.position.y, self.position.z]
+    }
+
+    // unit normal facing the camera
+    #[wasm_bindgen(getter)]
+    pub fn normal(&self) -> Vec<f32> {
+        vec![self.normal.x, self.normal.y, self.normal.z]
+    }
+
+    // from the camera to the hit point
+    #[wasm_bindgen(getter)]
+    pub fn distance(&self) -> f32 {
+        self.distance
+    }
+}
+
+#[wasm_bindgen]
+pub struct Viewer {
+    width: u32,
+    height: u32,
+    scene: Scene,
+    orbit: OrbitController,
+    projection: Projection,
+    vfov: f32,
+    exposure: Exposure,
+    // in scene units, once it is known the f-stop sets the aperture of perspective projections
+    sensor_height: Option<f32>,
+    film: Film,
+    // distance of the sharp plane along the view direction
+    focus_dist: f32,
+    samples_per_pixel: u32,
+    max_depth: i32,
+    // trace wavelengths instead of RGB, needed to see dispersion
+    spectral: bool,
+    camera: Box<dyn Camera>,
+    // sum of the passes rendered since the camera last changed, rows from the top
+    accumulation: Vec<Color>,
+    passes: u32,
+    // the camera is being dragged, passes are quick previews
+    moving: bool
+}
+
+impl Viewer {
+    // focused on `lookat` with an aperture of 2, exposed by the sunny 16 rule
+    pub fn from_scene(scene: Scene, width: u32, height: u32, lookfrom: Vec3, lookat: Vec3) -> Self {
+        let exposure = Exposure::default();
+        let orbit = OrbitController::new(lookfrom, lookat);
+        let vfov = 20.0;
+        let focus_dist = (lookfrom - lookat).length();
+        let projection = Projection::Perspective{ vfov, aperture: 2.0, focus_dist };
+        Self {
+            width,
+            height,
+            scene,
+            orbit,
+            projection,
+            vfov,
+            exposure,
+            sensor_height: None,
+            film: Film::new(exposure, NEUTRAL_KELVIN),
+            focus_dist,
+            samples_per_pixel: 50,
+            max_depth: 50,
+            spectral: false,
+            camera: projection.camera(orbit.lookfrom(), lookat, orbit.vup(), width as f32 / height as f32),
+            accumulation: vec![Color{ r: 0.0, g: 0.0, b: 0.0 }; (width * height) as usize],
+            passes: 0,
+            moving: false
+        }
+    }
+
+    // perspective projections follow the field of view and focus the viewer changes, others are used as they are
+    pub fn with_projection(mut self, projection: Projection) -> Self {
+        self.set_projection(projection);
+        self
+    }
+
+    pub fn set_projection(&mut self, projection: Projection) {
+        if let Projection::Perspective{ vfov, focus_dist, .. } = projection {
+            self.vfov = vfov;
+            self.focus_dist = focus_dist;
+        }
+        self.projection = projection;
+        self.rebuild_camera();
+    }
+
+    pub fn with_vfov(mut self, vfov: f32) -> Self {
+        self.vfov = vfov;
+        self.rebuild_camera();
+        self
+    }
+
+    // `white_balance` in kelvin, e.g. 3200.0 under tungsten light
+    pub fn with_film(mut self, exposure: Exposure, white_balance: f32) -> Self {
+        self.exposure = exposure;
+        self.film = Film::new(exposure, white_balance);
+        self.rebuild_camera();
+        self
+    }
+
+    // `height` in scene units, 0.024 for a full frame camera in a scene in meters
+    pub fn with_sensor(mut self, height: f32) -> Self {
+        self.sensor_height = Some(height);
+        self.rebuild_camera();
+        self
+    }
+
+    fn aspect_ratio(&self) -> f32 {
+        self.width as f32 / self.height as f32
+    }
+
+    // the f-stop can set the aperture, so exposure changes rebuild the camera too
+    fn projection(&self) -> Projection {
+        match self.projection {
+            Projection::Perspective{ aperture, .. } => {
+                let aperture = self.sensor_height.map_or(aperture, |height| self.exposure.aperture(self.vfov, height));
+                Projection::Perspective{ vfov: self.vfov, aperture, focus_dist: self.focus_dist }
+            },
+            other => other
+        }
+    }
+
+    // after any change of the camera, the passes so far show the old image
+    fn rebuild_camera(&mut self) {
+        self.camera = self.projection().camera(self.orbit.lookfrom(), self.orbit.lookat, self.orbit.vup(), self.aspect_ratio());
+        self.reset_accumulation();
+    }
+
+    fn reset_accumulation(&mut self) {
+        for color in self.accumulation.iter_mut() {
+            *color = Color{ r: 0.0, g: 0.0, b: 0.0 };
+        }
+        self.passes = 0;
+    }
+
+    fn moved(&mut self) {
+        self.moving = true;
+        self.rebuild_camera();
+    }
+
+    // the ray through the center of pixel (x, y) counted from the top left, without depth of field
+    fn primary_ray(&self, x: u32, y: u32) -> Ray {
+        let projection = match self.projection() {
+            Projection::Perspective{ vfov, focus_dist, .. } => Projection::Perspective{ vfov, aperture: 0.0, focus_dist },
+            other => other
+        };
+        let pinhole = projection.camera(self.orbit.lookfrom(), self.orbit.lookat, self.orbit.vup(), self.aspect_ratio());
+        let s = (x as f32 + 0.5) / self.width as f32;
+        let t = 1.0 - (y as f32 + 0.5) / self.height as f32;
+        pinhole.get_ray(s, t)
+    }
+
+    // sum of `samples` paths through pixel (i, j) counted from the bottom left
+    fn pixel(&self, i: u32, j: u32, samples: u32, max_depth: i32) -> Color {
+        let mut rng = rand::thread_rng();
+        let mut col = Color{ r: 0.0, g: 0.0, b: 0.0 };
+        for _k in 0..samples {
+            let u = ((i as f32) + rng.gen_range(0.0, 1.0)) / (self.width as f32);
+            let v = ((j as f32) + rng.gen_range(0.0, 1.0)) / (self.height as f32);
+            // rays the lens blocks add black, the corners darken
+            let mut r = match self.camera.sample_ray(u, v) {
+                Some(r) => r,
+                None => continue
+            };
+            if self.spectral {
+                let hero = spectrum::sample_hero_wavelength();
+                r.wavelength = Some(hero);
+                col = spectrum::to_rgb(&ray_color(&r, &self.scene, max_depth), hero) + col;
+                continue;
+            }
+            col = ray_color(&r, &self.scene, max_depth) + col;
+        }
+        col
+    }
+
+    // RGBA rows from the top, for an ImageData of the canvas
+    fn image(&self, colors: &[Color], samples: u32) -> Vec<u8> {
+        let mut data = Vec::with_capacity(colors.len() * 4);
+        for color in colors {
+            write_color(&mut data, color, samples as f32, &self.film);
+        }
+        data
+    }
+
+    // one path per block of pixels, not kept
+    fn preview(&self) -> Vec<u8> {
+        let mut colors = vec![Color{ r: 0.0, g: 0.0, b: 0.0 }; (self.width * self.height) as usize];
+        for y in (0..self.height).step_by(PREVIEW_BLOCK as usize) {
+            for x in (0..self.width).step_by(PREVIEW_BLOCK as usize) {
+                let color = self.pixel(x, self.height - y - 1, 1, PREVIEW_DEPTH);
+                for by in y..(y + PREVIEW_BLOCK).min(self.height) {
+                    for bx in x..(x + PREVIEW_BLOCK).min(self.width) {
+                        colors[(by * self.width + bx) as usize] = color;
+                    }
+                }
+            }
+        }
+        self.image(&colors, 1)
+    }
+}
+
+#[wasm_bindgen]
+impl Viewer {
+    #[wasm_bindgen(constructor)]
+    pub fn new(width: u32, height: u32) -> Viewer {
+        Self::from_scene(crate::demo_scene(), width, height, Vec3{x: 3.0, y: 3.0, z: 2.0}, Vec3{x: 0.0, y: 0.0, z: -1.0})
+    }
+
+    pub fn set_spectral(&mut self, spectral: bool) {
+        self.spectral = spectral;
+        self.reset_accumulation();
+    }
+
+    // sun elevation and azimuth in degrees, as for `Sky::new`
+    pub fn set_sky(&mut self, elevation: f32, azimuth: f32, turbidity: f32) {
+        self.scene.sky = Sky::new(elevation, azimuth, turbidity);
+        self.reset_accumulation();
+    }
+
+    // the whole image at once, RGBA rows from the top
+    pub fn render(&self) -> Vec<u8> {
+        let colors: Vec<Color> = (0..self.width * self.height)
+            .map(|k| self.pixel(k % self.width, self.height - k / self.width - 1, self.samples_per_pixel, self.max_depth))
+            .collect();
+        self.image(&colors, self.samples_per_pixel)
+    }
+
+    /*
+        Progressive rendering, called once per animation frame: adds one path
+        per pixel to the ones since the camera last changed and returns their
+        average. While the camera moves it returns a quick preview instead.
+    */
+    pub fn render_pass(&mut self) -> Vec<u8> {
+        if self.moving {
+            return self.preview();
+        }
+        for k in 0..self.width * self.height {
+            let color = self.pixel(k % self.width, self.height - k / self.width - 1, 1, self.max_depth);
+            self.accumulation[k as usize] = self.accumulation[k as usize] + color;
+        }
+        self.passes += 1;
+        self.image(&self.accumulation, self.passes)
+    }
+
+    // passes accumulated in the current image
+    pub fn passes(&self) -> u32 {
+        self.passes
+    }
+
+    // degrees around `lookat`, sideways and up
+    pub fn orbit(&mut self, yaw: f32, pitch: f32) {
+        self.orbit.orbit(yaw, pitch);
+        self.moved();
+    }
+
+    // drags the scene `dx` pixels right and `dy` down with the cursor, as seen at `lookat`
+    pub fn pan(&mut self, dx: f32, dy: f32) {
+        let pixel = 2.0 * (self.vfov.to_radians() / 2.0).tan() / self.height as f32;
+        self.orbit.pan(-dx * pixel, dy * pixel);
+        self.moved();
+    }
+
+    // towards `lookat` for factors below 1, the focus scales along so `lookat` stays sharp when it was
+    pub fn dolly(&mut self, factor: f32) {
+        let distance = self.orbit.distance;
+        self.orbit.dolly(factor);
+        self.focus_dist *= self.orbit.distance / distance;
+        self.moved();
+    }
+
+    // degrees counterclockwise around the view direction
+    pub fn roll(&mut self, degrees: f32) {
+        self.orbit.roll(degrees);
+        self.moved();
+    }
+
+    // the drag ended, passes refine the image again
+    pub fn stop_moving(&mut self) {
+        self.moving = false;
+        self.reset_accumulation();
+    }
+
+    pub fn focus_distance(&self) -> f32 {
+        self.focus_dist
+    }
+
+    pub fn set_focus_distance(&mut self, focus_dist: f32) {
+        self.focus_dist = focus_dist;
+        self.rebuild_camera();
+    }
+
+    /*
+        Click to focus: puts the sharp plane through whatever is seen at pixel
+        (x, y) and returns its distance, or None and keeps the focus when the
+        pixel shows the sky.
+    */
+    pub fn focus_at(&mut self, x: u32, y: u32) -> Option<f32> {
+        let r = self.primary_ray(x, y);
+        let hit = self.scene.world.hit(&r, 0.001, f32::INFINITY)?;
+        // the thin lens focuses on a plane, not a sphere around the camera
+        let lookfrom = self.orbit.lookfrom();
+        let forward = (self.orbit.lookat - lookfrom).unit_vector();
+        let distance = (hit.p - lookfrom).dot(&forward);
+        if distance <= 0.0 {
+            return None;
+        }
+        self.set_focus_distance(distance);
+        Some(distance)
+    }
+
+    // the object seen at pixel (x, y) counted from the top left, None for the sky
+    pub fn pick(&self, x: u32, y: u32) -> Option<Pick> {
+        let r = self.primary_ray(x, y);
+        let (object_id, hit) = self.scene.world.hit_object(&r, 0.001, f32::INFINITY)?;
+        Some(Pick{
+            object_id,
+            material: hit.material.name().to_string(),
+            position: hit.p,
+            normal: hit.normal.unit_vector(),
+            distance: hit.t * r.direction.length()
+        })
+    }
+}
@@ -0,0 +1,65 @@
+use hell::camera::{Camera, FisheyeMapping, Projection};
+use hell::vec3::Vec3;
+
+const LOOKFROM: Vec3 = Vec3{x: 0.0, y: 0.0, z: 0.0};
+const LOOKAT: Vec3 = Vec3{x: 0.0, y: 0.0, z: -1.0};
+const VUP: Vec3 = Vec3{x: 0.0, y: 1.0, z: 0.0};
+
+fn close(a: &Vec3, b: &Vec3) -> bool {
+    (*a - *b).length() < 1e-4
+}
+
+fn direction(camera: &dyn Camera, s: f32, t: f32) -> Vec3 {
+    camera.get_ray(s, t).direction.unit_vector()
+}
+
+// angle from the view direction in degrees
+fn off_axis(camera: &dyn Camera, s: f32, t: f32) -> f32 {
+    direction(camera, s, t).dot(&(LOOKAT - LOOKFROM)).clamp(-1.0, 1.0).acos().to_degrees()
+}
+
+#[test]
+fn test_orthographic_rays_are_parallel() {
+    let camera = Projection::Orthographic{ height: 4.0 }.camera(LOOKFROM, LOOKAT, VUP, 2.0);
+    let corner = camera.get_ray(0.0, 0.0);
+    assert!(close(&corner.origin, &Vec3{x: -4.0, y: -2.0, z: 0.0}));
+    assert!(close(&camera.get_ray(1.0, 1.0).origin, &Vec3{x: 4.0, y: 2.0, z: 0.0}));
+    assert!(close(&direction(camera.as_ref(), 0.0, 0.0), &direction(camera.as_ref(), 0.7, 0.2)));
+    assert!(close(&corner.direction.unit_vector(), &Vec3{x: 0.0, y: 0.0, z: -1.0}));
+}
+
+#[test]
+fn test_fisheye_mappings() {
+    let equidistant = Projection::Fisheye{ fov: 180.0, mapping: FisheyeMapping::Equidistant }.camera(LOOKFROM, LOOKAT, VUP, 1.0);
+    let equisolid = Projection::Fisheye{ fov: 180.0, mapping: FisheyeMapping::Equisolid }.camera(LOOKFROM, LOOKAT, VUP, 1.0);
+    for camera in [&equidistant, &equisolid].iter() {
+        assert!(off_axis(camera.as_ref(), 0.5, 0.5) < 1e-2);
+        // the field of view spans the image height
+        assert!((off_axis(camera.as_ref(), 0.5, 1.0) - 90.0).abs() < 1e-2);
+        assert!(close(&direction(camera.as_ref(), 1.0, 0.5), &Vec3{x: 1.0, y: 0.0, z: 0.0}));
+    }
+    // half way out the equidistant lens sees half the angle, the equisolid one a bit less
+    assert!((off_axis(equidistant.as_ref(), 0.5, 0.75) - 45.0).abs() < 1e-2);
+    let expected = (2.0 * (0.5 * (45.0f32).to_radians().sin()).asin()).to_degrees();
+    assert!((off_axis(equisolid.as_ref(), 0.5, 0.75) - expected).abs() < 1e-2);
+    // the corners look beyond the field of view
+    assert!(off_axis(equidistant.as_ref(), 1.0, 1.0) > 90.0);
+}
+
+#[test]
+fn test_equirectangular_covers_the_sphere() {
+    let camera = Projection::Equirectangular.camera(LOOKFROM, LOOKAT, VUP, 2.0);
+    assert!(close(&direction(camera.as_ref(), 0.5, 0.5), &Vec3{x: 0.0, y: 0.0, z: -1.0}));
+    assert!(close(&direction(camera.as_ref(), 0.75, 0.5), &Vec3{x: 1.0, y: 0.0, z: 0.0}));
+    assert!(close(&direction(camera.as_ref(), 0.0, 0.5), &Vec3{x: 0.0, y: 0.0, z: 1.0}));
+    assert!(close(&direction(camera.as_ref(), 1.0, 0.5), &Vec3{x: 0.0, y: 0.0, z: 1.0}));
+    assert!(close(&direction(camera.as_ref(), 0.3, 1.0), &Vec3{x: 0.0, y: 1.0, z: 0.0}));
+}
+
+#[test]
+fn test_perspective_projection() {
+    let camera = Projection::Perspective{ vfov: 90.0, aperture: 0.0, focus_dist: 1.0 }.camera(LOOKFROM, LOOKAT, VUP, 1.5);
+    assert!(close(&direction(camera.as_ref(), 0.5, 0.5), &Vec3{x: 0.0, y: 0.0, z: -1.0}));
+    assert!((off_axis(camera.as_ref(), 0.5, 1.0) - 45.0).abs() < 1e-2);
+    assert!(close(&camera.get_ray(1.0, 0.5).direction, &Vec3{x: 1.5, y: 0.0, z: -1.0}));
+}
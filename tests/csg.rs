@@ -0,0 +1,71 @@
+use hell::color::Color;
+use hell::csg::Csg;
+use hell::hittable::Hittable;
+use hell::material::Lambertian;
+use hell::ray::Ray;
+use hell::sphere::Sphere;
+use hell::vec3::Vec3;
+
+fn sphere(x: f32, radius: f32) -> Box<Sphere> {
+    Box::new(Sphere{
+        center: Vec3{x, y: 0.0, z: 0.0},
+        radius,
+        material: Box::new(Lambertian{ albedo: Color{r: 0.5, g: 0.5, b: 0.5} })
+    })
+}
+
+fn along_x(origin: f32, direction: f32) -> Ray {
+    Ray{ origin: Vec3{x: origin, y: 0.0, z: 0.0}, direction: Vec3{x: direction, y: 0.0, z: 0.0}, wavelength: None, time: 0.0 }
+}
+
+// every surface the solid has along the ray, with its outward facing flag
+fn surfaces(solid: &dyn Hittable, r: &Ray) -> Vec<(f32, bool)> {
+    let mut result = Vec::new();
+    let mut t = 0.001;
+    while let Some(hit) = solid.hit(r, t, f32::INFINITY) {
+        result.push((hit.t, hit.front_face));
+        t = hit.t + 0.001;
+    }
+    result
+}
+
+fn assert_surfaces(found: &[(f32, bool)], expected: &[(f32, bool)]) {
+    assert_eq!(found.len(), expected.len(), "{:?}", found);
+    for ((t, front), (t_expected, front_expected)) in found.iter().zip(expected.iter()) {
+        assert!((t - t_expected).abs() < 1e-4, "{:?}", found);
+        assert_eq!(front, front_expected);
+    }
+}
+
+#[test]
+fn test_union_skips_inner_surfaces() {
+    let solid = Csg::union(sphere(0.0, 1.0), sphere(1.5, 1.0));
+    assert_surfaces(&surfaces(&solid, &along_x(-5.0, 1.0)), &[(4.0, true), (7.5, false)]);
+}
+
+#[test]
+fn test_intersection_lens() {
+    let lens = Csg::intersection(sphere(-0.8, 1.0), sphere(0.8, 1.0));
+    assert_surfaces(&surfaces(&lens, &along_x(-5.0, 1.0)), &[(4.8, true), (5.2, false)]);
+    let bounds = lens.bounding_box().unwrap();
+    assert!(bounds.max.x > 0.19 && bounds.min.x < -0.19);
+}
+
+#[test]
+fn test_difference_hollow_ball() {
+    let hollow = Csg::difference(sphere(0.0, 1.0), sphere(0.0, 0.5));
+    // in through the shell, out into the hollow, back into the shell and out
+    assert_surfaces(&surfaces(&hollow, &along_x(-5.0, 1.0)), &[(4.0, true), (4.5, false), (5.5, true), (6.0, false)]);
+    // the inner surface faces the hollow
+    let hit = hollow.hit(&along_x(0.0, 1.0), 0.001, f32::INFINITY).unwrap();
+    assert!((hit.t - 0.5).abs() < 1e-4);
+    assert!(hit.front_face && hit.normal.x < -0.99);
+}
+
+#[test]
+fn test_nested_operations() {
+    // a ball with a bite taken out, intersected with a slab like ball
+    let bitten = Csg::difference(sphere(0.0, 1.0), sphere(1.0, 0.5));
+    let solid = Csg::intersection(Box::new(bitten), sphere(-0.5, 1.0));
+    assert_surfaces(&surfaces(&solid, &along_x(5.0, -1.0)), &[(4.5, true), (6.0, false)]);
+}
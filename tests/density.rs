@@ -0,0 +1,33 @@
+use hell::density::{DensityField, NoiseDensity, VoxelGrid};
+use hell::vec3::Vec3;
+
+#[test]
+fn test_voxel_grid_size() {
+    let min = Vec3{x: 0.0, y: 0.0, z: 0.0};
+    let max = Vec3{x: 1.0, y: 1.0, z: 1.0};
+    assert!(VoxelGrid::new(2, 2, 2, vec![0.0; 7], min, max).is_err());
+    assert!(VoxelGrid::from_raw(&[0u8; 31], 2, 2, 2, min, max).is_err());
+    assert!(VoxelGrid::from_raw(&[0u8; 32], 2, 2, 2, min, max).is_ok());
+}
+
+#[test]
+fn test_voxel_grid_interpolation() {
+    let min = Vec3{x: 0.0, y: 0.0, z: 0.0};
+    let max = Vec3{x: 2.0, y: 1.0, z: 1.0};
+    let grid = VoxelGrid::new(2, 1, 1, vec![1.0, 3.0], min, max).unwrap();
+    assert_eq!(grid.max_density(), 3.0);
+    // halfway between the two voxel centers
+    assert!((grid.density(&Vec3{x: 1.0, y: 0.5, z: 0.5}) - 2.0).abs() < 1e-5);
+    assert!((grid.density(&Vec3{x: 0.2, y: 0.5, z: 0.5}) - 1.0).abs() < 1e-5);
+    assert_eq!(grid.density(&Vec3{x: 3.0, y: 0.5, z: 0.5}), 0.0);
+}
+
+#[test]
+fn test_noise_density_below_majorant() {
+    let smoke = NoiseDensity{ max_density: 4.0, scale: 3.0, octaves: 4, threshold: 0.3 };
+    for i in 0..1000 {
+        let p = Vec3{x: i as f32 * 0.137, y: i as f32 * 0.071, z: i as f32 * -0.053};
+        let d = smoke.density(&p);
+        assert!(d >= 0.0 && d <= smoke.max_density());
+    }
+}
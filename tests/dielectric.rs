@@ -0,0 +1,110 @@
+use hell::color::Color;
+use hell::hittable::{HitRecord, Hittable};
+use hell::hittable_list::HittableList;
+use hell::integrator::ray_color;
+use hell::material::{Dielectric, Material};
+use hell::ray::Ray;
+use hell::scene::Scene;
+use hell::sky::Sky;
+use hell::sphere::Sphere;
+use hell::vec3::Vec3;
+
+fn tinted(absorption: f32, density: f32, priority: i32) -> Dielectric {
+    let mut material = Dielectric::new(1.5);
+    material.absorption = Color{r: absorption, g: absorption, b: absorption};
+    material.density = density;
+    material.priority = priority;
+    material
+}
+
+#[test]
+fn test_clear_glass_absorbs_nothing() {
+    let interior = Dielectric::new(1.5).interior().unwrap();
+    assert_eq!(interior.ref_idx, 1.5);
+    assert_eq!(interior.priority, 0);
+    for a in [interior.absorption.r, interior.absorption.g, interior.absorption.b].iter() {
+        assert_eq!(*a, 0.0);
+    }
+}
+
+#[test]
+fn test_absorption_color_is_left_after_one_over_density() {
+    for &density in [0.5, 1.0, 4.0].iter() {
+        let interior = tinted(0.5, density, 0).interior().unwrap();
+        let left = (-interior.absorption.r / density).exp();
+        assert!((left - 0.5).abs() < 1e-5, "{} left at density {}", left, density);
+    }
+    assert_eq!(tinted(0.5, 1.0, 3).interior().unwrap().priority, 3);
+}
+
+#[test]
+fn test_refraction_uses_the_ratio_of_the_media() {
+    let material: Box<dyn Material> = Box::new(Dielectric::new(1.5));
+    let record = HitRecord{
+        p: Vec3{x: 0.0, y: 0.0, z: 0.0},
+        normal: Vec3{x: 0.0, y: 0.0, z: 1.0},
+        t: 1.0, u: 0.0, v: 0.0, tangent: None, front_face: true, color: None, instance: 0,
+        material: &material
+    };
+    let r = Ray{ origin: Vec3{x: -0.6, y: 0.0, z: 0.8}, direction: Vec3{x: 0.6, y: 0.0, z: -0.8}, wavelength: None, time: 0.0 };
+    // index matched media go straight on, water inside glass bends less than air
+    for &ratio in [1.0, 1.0 / 1.5, 1.33 / 1.5].iter() {
+        for _ in 0..100 {
+            let scatt = material.scatter_interface(&r, &record, ratio).unwrap();
+            let d = scatt.scattered.direction.unit_vector();
+            if d.z < 0.0 {
+                assert!((d.x - 0.6 * ratio).abs() < 1e-5, "sine {} at ratio {}", d.x, ratio);
+            }
+        }
+    }
+}
+
+// index matched to air, so paths go straight and only absorption tells the media apart
+fn matched(z: f32, radius: f32, absorption: f32, priority: i32) -> Box<dyn Hittable> {
+    let mut material = tinted(absorption, 1.0, priority);
+    material.ref_idx = 1.0;
+    Box::new(Sphere{ center: Vec3{x: 0.0, y: 0.0, z}, radius, material: Box::new(material) })
+}
+
+// red radiance along -z through the origin over the sky's behind the scene
+fn transmitted(hitables: Vec<Box<dyn Hittable>>) -> f32 {
+    let scene = Scene{ world: HittableList::new(hitables), sky: Sky::new(35.0, 30.0, 3.0), fog: None };
+    let r = Ray{ origin: Vec3{x: 0.0, y: 0.0, z: 5.0}, direction: Vec3{x: 0.0, y: 0.0, z: -1.0}, wavelength: None, time: 0.0 };
+    ray_color(&r, &scene, 50).r / scene.sky.radiance_with_sun(&r.direction).r
+}
+
+fn close(a: f32, b: f32) -> bool {
+    (a - b).abs() < 1e-3
+}
+
+#[test]
+fn test_beer_absorption_over_the_path() {
+    // half the light is left after every unit, the diameter is 2
+    assert!(close(transmitted(vec![matched(0.0, 1.0, 0.5, 0)]), 0.25));
+    assert!(close(transmitted(vec![matched(0.0, 1.5, 0.5, 0)]), 0.125));
+}
+
+#[test]
+fn test_bubble_in_glass() {
+    // the air bubble outranks the glass around it, 2 of the 4 units are in glass
+    let glass = matched(0.0, 2.0, 0.5, 1);
+    let bubble = matched(0.0, 1.0, 1.0, 2);
+    assert!(close(transmitted(vec![glass, bubble]), 0.25));
+
+    // ranked below the glass, the bubble is ignored
+    let glass = matched(0.0, 2.0, 0.5, 1);
+    let bubble = matched(0.0, 1.0, 1.0, 0);
+    assert!(close(transmitted(vec![glass, bubble]), 0.0625));
+}
+
+#[test]
+fn test_liquid_overlapping_its_glass() {
+    // the glass spans z from 2 to -2, the liquid from -1 to -4 and is hidden where they overlap
+    let glass = || matched(0.0, 2.0, 0.5, 2);
+    let liquid = |priority| matched(-2.5, 1.5, 0.25, priority);
+    let expected = 0.5f32.powi(4) * 0.25f32.powi(2);
+    assert!(close(transmitted(vec![glass(), liquid(1)]), expected));
+    // the other way round the liquid fills the overlap
+    let expected = 0.5f32.powi(3) * 0.25f32.powi(3);
+    assert!(close(transmitted(vec![glass(), liquid(3)]), expected));
+}
@@ -0,0 +1,62 @@
+use hell::color::Color;
+use hell::film::{black_body_color, Exposure, Film, NEUTRAL_KELVIN};
+
+fn close(a: f32, b: f32) -> bool {
+    (a - b).abs() < 1e-3
+}
+
+#[test]
+fn test_exposure_stops() {
+    let sunny_16 = Exposure::default();
+    assert!(close(sunny_16.scale(), 1.0));
+    assert!((sunny_16.ev100() - 14.64).abs() < 0.01);
+
+    let iso_200 = Exposure::new(200.0, 0.01, 16.0).unwrap();
+    assert!(close(iso_200.scale(), 2.0));
+    assert!(close(iso_200.ev100(), sunny_16.ev100() - 1.0));
+    assert_eq!((iso_200.iso(), iso_200.shutter_speed(), iso_200.f_number()), (200.0, 0.01, 16.0));
+    // one stop wider and twice as fast cancel out
+    let reciprocal = Exposure::new(100.0, 0.005, 16.0 / 2f32.sqrt()).unwrap();
+    assert!(close(reciprocal.scale(), 1.0));
+
+    assert!(Exposure::new(100.0, 0.0, 2.8).is_err());
+}
+
+#[test]
+fn test_f_stop_sets_the_aperture() {
+    // 90 degrees on a full frame sensor is a 12 mm lens, 6 mm wide open at f/2
+    let exposure = Exposure::new(100.0, 0.01, 2.0).unwrap();
+    assert!(close(exposure.aperture(90.0, 0.024), 0.006));
+}
+
+#[test]
+fn test_white_balance() {
+    let white = Color{r: 1.0, g: 1.0, b: 1.0};
+    let neutral = Film::default().develop(&white);
+    assert!(close(neutral.r, 1.0) && close(neutral.g, 1.0) && close(neutral.b, 1.0));
+
+    // tungsten light comes out white when the white balance is set for it
+    let tungsten = black_body_color(3200.0);
+    assert!(tungsten.r > tungsten.b);
+    let developed = Film::new(Exposure::default(), 3200.0).develop(&tungsten);
+    let expected = black_body_color(NEUTRAL_KELVIN);
+    assert!(close(developed.r, expected.r) && close(developed.g, expected.g) && close(developed.b, expected.b));
+
+    // and daylight then looks blue
+    let daylight = Film::new(Exposure::default(), 3200.0).develop(&white);
+    assert!(daylight.b > daylight.r);
+}
+
+#[test]
+fn test_white_balance_below_the_srgb_gamut() {
+    // candle light's blue is negative in sRGB, the adaptation must not flip or blow it up
+    assert!(black_body_color(1500.0).b < 0.0);
+    let white = Color{r: 1.0, g: 1.0, b: 1.0};
+    for &kelvin in [1000.0, 1500.0, 1900.0, 1930.0, 2000.0].iter() {
+        let developed = Film::new(Exposure::default(), kelvin).develop(&white);
+        for channel in [developed.r, developed.g, developed.b].iter() {
+            assert!(channel.is_finite() && *channel >= 0.0, "{:?} at {} K", developed, kelvin);
+        }
+        assert!(developed.b > developed.g && developed.g >= developed.r);
+    }
+}
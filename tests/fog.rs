@@ -0,0 +1,45 @@
+use hell::color::Color;
+use hell::medium::{Fog, Isotropic};
+use hell::vec3::Vec3;
+
+fn phase() -> Box<Isotropic> {
+    Box::new(Isotropic{ albedo: Color{r: 0.9, g: 0.9, b: 0.9} })
+}
+
+#[test]
+fn test_homogeneous_layer() {
+    let fog = Fog::homogeneous(0.5, 2.0, phase());
+    let origin = Vec3{x: 0.0, y: 0.0, z: 0.0};
+    let level = Vec3{x: 1.0, y: 0.0, z: 0.0};
+    assert!((fog.transmittance(&origin, &level, 3.0) - (-1.5f32).exp()).abs() < 1e-5);
+    // straight up the ray leaves the layer after 2 units
+    let up = Vec3{x: 0.0, y: 1.0, z: 0.0};
+    assert!((fog.optical_depth(&origin, &up, f32::INFINITY) - 1.0).abs() < 1e-5);
+    let above = Vec3{x: 0.0, y: 5.0, z: 0.0};
+    assert_eq!(fog.optical_depth(&above, &level, 10.0), 0.0);
+}
+
+#[test]
+fn test_exponential_escapes_upwards() {
+    let fog = Fog::exponential(0.2, 0.0, 0.5, phase());
+    let origin = Vec3{x: 0.0, y: 0.0, z: 0.0};
+    let up = Vec3{x: 0.0, y: 0.6, z: 0.8};
+    let depth = fog.optical_depth(&origin, &up, f32::INFINITY);
+    assert!((depth - 0.2 / (0.5 * 0.6)).abs() < 1e-5);
+    let down = Vec3{x: 0.0, y: -0.6, z: 0.8};
+    assert!(fog.optical_depth(&origin, &down, 1.0) > fog.optical_depth(&origin, &up, 1.0));
+}
+
+#[test]
+fn test_sampled_distance_matches_optical_depth() {
+    let fog = Fog::exponential(0.3, 1.0, 0.7, phase());
+    let origin = Vec3{x: 0.0, y: 0.5, z: 0.0};
+    let direction = Vec3{x: 0.0, y: 0.28, z: 0.96};
+    for i in 0..10 {
+        let xi = (i as f32 + 0.5) / 10.0;
+        if let Some(t) = fog.sample_distance(&origin, &direction, f32::INFINITY, xi) {
+            let depth = fog.optical_depth(&origin, &direction, t);
+            assert!((depth + (1.0 - xi).ln()).abs() < 1e-3);
+        }
+    }
+}
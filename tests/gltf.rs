@@ -0,0 +1,172 @@
+use hell::color::Color;
+use hell::gltf::{import_gltf, LightKind};
+use hell::hittable::Hittable;
+use hell::material::Lambertian;
+use hell::ray::Ray;
+use hell::triangle::{intersect_triangle, Mesh};
+use hell::vec3::Vec3;
+use rand::Rng;
+
+// a 2 x 2 quad in the xy plane facing +z, with 16 bit indices
+fn quad_buffer() -> Vec<u8> {
+    let positions: [f32; 12] = [-1.0, -1.0, 0.0, 1.0, -1.0, 0.0, 1.0, 1.0, 0.0, -1.0, 1.0, 0.0];
+    let indices: [u16; 6] = [0, 1, 2, 0, 2, 3];
+    let mut bytes: Vec<u8> = positions.iter().flat_map(|p| p.to_le_bytes()).collect();
+    bytes.extend(indices.iter().flat_map(|i| i.to_le_bytes()));
+    bytes
+}
+
+fn document(buffer: &str) -> String {
+    format!(r#"{{
+        "asset": {{"version": "2.0"}},
+        "extensionsUsed": ["KHR_lights_punctual", "KHR_texture_transform"],
+        "extensions": {{"KHR_lights_punctual": {{"lights": [{{"type": "directional", "intensity": 3}}]}}}},
+        "scene": 0,
+        "scenes": [{{"nodes": [0, 2, 3]}}],
+        "nodes": [
+            {{"mesh": 0, "translation": [0, 0, -5], "children": [1]}},
+            {{"mesh": 0, "translation": [4, 0, 0], "scale": [2, 2, 2]}},
+            {{"camera": 0, "translation": [0, 1, 3]}},
+            {{"rotation": [-0.5, 0, 0, 0.8660254], "extensions": {{"KHR_lights_punctual": {{"light": 0}}}}}}
+        ],
+        "cameras": [{{"type": "perspective", "perspective": {{"yfov": 0.5, "znear": 0.1}}}}],
+        "materials": [{{"name": "red", "pbrMetallicRoughness": {{"baseColorFactor": [1, 0, 0, 1], "metallicFactor": 0, "baseColorTexture": {{"index": 0}}}}}}],
+        "meshes": [{{"primitives": [{{"attributes": {{"POSITION": 0}}, "indices": 1, "material": 0}}]}}],
+        "accessors": [
+            {{"bufferView": 0, "componentType": 5126, "count": 4, "type": "VEC3"}},
+            {{"bufferView": 1, "componentType": 5123, "count": 6, "type": "SCALAR"}}
+        ],
+        "bufferViews": [
+            {{"buffer": 0, "byteOffset": 0, "byteLength": 48}},
+            {{"buffer": 0, "byteOffset": 48, "byteLength": 12}}
+        ],
+        "buffers": [{{"byteLength": 60{}}}]
+    }}"#, buffer)
+}
+
+fn glb(json: &str, bin: &[u8]) -> Vec<u8> {
+    let mut json = json.as_bytes().to_vec();
+    while json.len() % 4 != 0 {
+        json.push(b' ');
+    }
+    let mut bytes = b"glTF".to_vec();
+    bytes.extend(2u32.to_le_bytes());
+    bytes.extend(((12 + 8 + json.len() + 8 + bin.len()) as u32).to_le_bytes());
+    bytes.extend((json.len() as u32).to_le_bytes());
+    bytes.extend(b"JSON");
+    bytes.extend(json);
+    bytes.extend((bin.len() as u32).to_le_bytes());
+    bytes.extend(b"BIN\0");
+    bytes.extend(bin);
+    bytes
+}
+
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut text = String::new();
+    for chunk in bytes.chunks(3) {
+        let n = chunk.iter().enumerate().fold(0u32, |n, (i, b)| n | (*b as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            text.push(if i <= chunk.len() { ALPHABET[(n >> (18 - 6 * i) & 63) as usize] as char } else { '=' });
+        }
+    }
+    text
+}
+
+fn ray(origin: Vec3, direction: Vec3) -> Ray {
+    Ray{ origin, direction, wavelength: None, time: 0.0 }
+}
+
+fn close(a: &Vec3, b: &Vec3) -> bool {
+    (*a - *b).length() < 1e-4
+}
+
+#[test]
+fn test_glb_nodes_camera_and_light() {
+    let scene = import_gltf(&glb(&document(""), &quad_buffer()), 1.5).unwrap();
+    assert_eq!(scene.objects.len(), 2);
+
+    let origin = Vec3{x: 0.0, y: 0.0, z: 0.0};
+    let hit = scene.objects[0].hit(&ray(origin, Vec3{x: 0.0, y: 0.0, z: -1.0}), 0.001, f32::INFINITY).unwrap();
+    assert!((hit.t - 5.0).abs() < 1e-4);
+    assert!(close(&hit.normal, &Vec3{x: 0.0, y: 0.0, z: 1.0}));
+    assert_eq!(hit.material.name(), "red");
+    // the child is placed relative to its parent and twice as large
+    let child = &scene.objects[1];
+    assert!(child.hit(&ray(Vec3{x: 5.9, y: 1.9, z: 0.0}, Vec3{x: 0.0, y: 0.0, z: -1.0}), 0.001, f32::INFINITY).is_some());
+    assert!(child.hit(&ray(Vec3{x: 6.1, y: 0.0, z: 0.0}, Vec3{x: 0.0, y: 0.0, z: -1.0}), 0.001, f32::INFINITY).is_none());
+
+    // the camera looks down its -z axis from where its node is
+    assert_eq!(scene.cameras.len(), 1);
+    let r = scene.cameras[0].get_ray(0.5, 0.5);
+    assert!(close(&r.origin, &Vec3{x: 0.0, y: 1.0, z: 3.0}));
+    assert!(close(&r.direction.unit_vector(), &Vec3{x: 0.0, y: 0.0, z: -1.0}));
+
+    assert_eq!(scene.lights.len(), 1);
+    assert_eq!(scene.lights[0].kind, LightKind::Directional);
+    assert!((scene.lights[0].intensity - 3.0).abs() < 1e-6);
+    // tilted 60 degrees down, the sun is high up behind the default view
+    let sky = scene.sky(3.0).unwrap();
+    assert!(close(&sky.sun_direction, &Vec3{x: 0.0, y: (0.75f32).sqrt(), z: 0.5}));
+
+    assert!(scene.warnings.iter().any(|w| w.contains("KHR_texture_transform")));
+    assert!(scene.warnings.iter().any(|w| w.contains("'red'") && w.contains("textures")));
+}
+
+#[test]
+fn test_embedded_and_external_buffers() {
+    let uri = format!(r#", "uri": "data:application/octet-stream;base64,{}""#, base64(&quad_buffer()));
+    let scene = import_gltf(document(&uri).as_bytes(), 1.5).unwrap();
+    let hit = scene.objects[0].hit(&ray(Vec3{x: 0.5, y: -0.5, z: 0.0}, Vec3{x: 0.0, y: 0.0, z: -2.0}), 0.001, f32::INFINITY);
+    assert!((hit.unwrap().t - 2.5).abs() < 1e-4);
+
+    let external = import_gltf(document(r#", "uri": "quad.bin""#).as_bytes(), 1.5);
+    assert!(external.err().unwrap().contains("quad.bin"));
+    assert!(import_gltf(br#"{"asset": {"version": "1.0"}}"#, 1.0).is_err());
+}
+
+#[test]
+fn test_mesh_bvh_matches_brute_force() {
+    let mut rng = rand::thread_rng();
+    let mut random_point = || Vec3{x: rng.gen_range(-2.0, 2.0), y: rng.gen_range(-2.0, 2.0), z: rng.gen_range(-2.0, 2.0)};
+    let positions: Vec<Vec3> = (0..300).map(|_| random_point()).collect();
+    let triangles: Vec<[usize; 3]> = (0..100).map(|i| [3 * i, 3 * i + 1, 3 * i + 2]).collect();
+    let mesh = Mesh::new(positions.clone(), triangles.clone(), Box::new(Lambertian{ albedo: Color{r: 0.5, g: 0.5, b: 0.5} })).unwrap();
+
+    for _ in 0..200 {
+        let r = ray(random_point() * 2.0, random_point());
+        let expected = triangles.iter()
+            .filter_map(|t| intersect_triangle(&r, &positions[t[0]], &positions[t[1]], &positions[t[2]], 0.001, f32::INFINITY))
+            .map(|(t, _, _)| t)
+            .min_by(|a, b| a.total_cmp(b));
+        let hit = mesh.hit(&r, 0.001, f32::INFINITY).map(|h| h.t);
+        assert_eq!(hit.is_some(), expected.is_some());
+        if let (Some(hit), Some(expected)) = (hit, expected) {
+            assert!((hit - expected).abs() < 1e-4);
+        }
+    }
+    assert!(Mesh::new(positions, vec![[0, 1, 300]], Box::new(Lambertian{ albedo: Color{r: 0.5, g: 0.5, b: 0.5} })).is_err());
+}
+
+#[test]
+fn test_malformed_files_are_errors() {
+    // texture coordinates from the 3D positions
+    let uvs = document("").replace(r#"{"POSITION": 0}"#, r#"{"POSITION": 0, "TEXCOORD_0": 0}"#);
+    assert!(import_gltf(&glb(&uvs, &quad_buffer()), 1.5).err().unwrap().contains("texture coordinates"));
+
+    let huge = document("").replace(r#""count": 4"#, r#""count": 4000000000000"#);
+    assert!(import_gltf(&glb(&huge, &quad_buffer()), 1.5).is_err());
+    let zeros = huge.replace(r#""bufferView": 0, "#, "");
+    assert!(import_gltf(&glb(&zeros, &quad_buffer()), 1.5).is_err());
+
+    // a binary chunk claiming to be 4 GB
+    let mut bytes = glb(&document(""), &quad_buffer());
+    let bin = bytes.len() - quad_buffer().len() - 8;
+    bytes[bin..bin + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+    assert!(import_gltf(&bytes, 1.5).is_err());
+    // nesting that would overflow the stack
+    let deep = "[".repeat(100_000) + &"]".repeat(100_000);
+    assert!(import_gltf(deep.as_bytes(), 1.5).err().unwrap().contains("nested"));
+    let shallow = format!(r#"{{"asset": {{"version": "2.0"}}, "extras": {}{}}}"#, "[".repeat(100), "]".repeat(100));
+    assert!(import_gltf(shallow.as_bytes(), 1.5).is_ok());
+}
@@ -0,0 +1,74 @@
+use hell::color::Color;
+use hell::density::fbm;
+use hell::heightfield::Heightfield;
+use hell::hittable::Hittable;
+use hell::material::Lambertian;
+use hell::ray::Ray;
+use hell::vec3::Vec3;
+
+const N: usize = 17;
+const SIZE: Vec3 = Vec3{x: 4.0, y: 1.5, z: 4.0};
+
+fn gray() -> Box<Lambertian> {
+    Box::new(Lambertian{ albedo: Color{r: 0.5, g: 0.5, b: 0.5} })
+}
+
+fn terrain(x: f32, z: f32) -> f32 {
+    (0.5 + fbm(&Vec3{x: 3.0 * x, y: 0.0, z: 3.0 * z}, 4)).clamp(0.0, 1.0)
+}
+
+// the surface of the two triangles per cell the heightfield is made of
+fn surface(x: f32, z: f32) -> f32 {
+    let cell = SIZE.x / (N - 1) as f32;
+    let (gx, gz) = (x / cell, z / cell);
+    let (i, j) = ((gx.floor() as usize).min(N - 2), (gz.floor() as usize).min(N - 2));
+    let (fx, fz) = (gx - i as f32, gz - j as f32);
+    let h = |i: usize, j: usize| terrain(i as f32 / (N - 1) as f32, j as f32 / (N - 1) as f32) * SIZE.y;
+    if fx + fz <= 1.0 {
+        h(i, j) + fx * (h(i + 1, j) - h(i, j)) + fz * (h(i, j + 1) - h(i, j))
+    } else {
+        h(i + 1, j + 1) + (1.0 - fx) * (h(i, j + 1) - h(i + 1, j + 1)) + (1.0 - fz) * (h(i + 1, j) - h(i + 1, j + 1))
+    }
+}
+
+#[test]
+fn test_sample_count() {
+    assert!(Heightfield::new(4, 4, vec![0.0; 15], SIZE, gray()).is_err());
+    assert!(Heightfield::from_gray(&[0, 255, 128, 64], 2, 2, SIZE, gray()).is_ok());
+}
+
+#[test]
+fn test_tilted_plane() {
+    let field = Heightfield::from_fn(9, 9, |x, _| x, Vec3{x: 2.0, y: 2.0, z: 2.0}, gray()).unwrap();
+    let r = Ray{ origin: Vec3{x: 0.5, y: 5.0, z: 1.3}, direction: Vec3{x: 0.0, y: -1.0, z: 0.0}, wavelength: None, time: 0.0 };
+    let hit = field.hit(&r, 0.001, f32::INFINITY).unwrap();
+    assert!((hit.p.y - 0.5).abs() < 1e-4);
+    let expected = Vec3{x: -1.0, y: 1.0, z: 0.0}.unit_vector();
+    assert!((hit.normal - expected).length() < 1e-4);
+    assert!((hit.u - 0.25).abs() < 1e-4);
+    assert!((hit.v - (1.0 - expected.y)).abs() < 1e-4);
+}
+
+#[test]
+fn test_traversal_finds_the_first_hit() {
+    let field = Heightfield::from_fn(N, N, terrain, SIZE, gray()).unwrap();
+    for k in 0..200 {
+        let a = k as f32 * 2.399;
+        // from above the terrain, a ray from the side could pass under its edge
+        let origin = Vec3{x: 2.0 + 1.9 * a.cos(), y: 3.0, z: 2.0 + 1.9 * a.sin()};
+        let target = Vec3{x: 0.2 + (k % 13) as f32 * 0.28, y: 0.0, z: 0.2 + (k % 7) as f32 * 0.6};
+        let r = Ray{ origin, direction: target - origin, wavelength: None, time: 0.0 };
+        let hit = match field.hit(&r, 0.001, f32::INFINITY) {
+            Some(hit) => hit,
+            None => panic!("ray {} towards the ground missed", k)
+        };
+        assert!((hit.p.y - surface(hit.p.x, hit.p.z)).abs() < 1e-3);
+        assert!(hit.front_face && hit.normal.y > 0.0);
+        // nothing of the terrain lies in front of the hit
+        for step in 1..200 {
+            let p = r.point_at_parameter(hit.t * step as f32 / 200.0);
+            let inside = p.x >= 0.0 && p.x <= SIZE.x && p.z >= 0.0 && p.z <= SIZE.z;
+            assert!(!inside || p.y >= surface(p.x, p.z) - 1e-3);
+        }
+    }
+}
@@ -0,0 +1,128 @@
+use hell::camera::{Camera, PerspectiveCamera};
+use hell::lens::{Aperture, ApertureMask, LensSystem, RealisticCamera};
+use hell::vec3::Vec3;
+
+const LOOKFROM: Vec3 = Vec3{x: 0.0, y: 0.0, z: 0.0};
+const LOOKAT: Vec3 = Vec3{x: 0.0, y: 0.0, z: -1.0};
+const VUP: Vec3 = Vec3{x: 0.0, y: 1.0, z: 0.0};
+
+// double gauss 50mm from pbrt's lens files
+const DGAUSS_50MM: &str = "
+# radius thickness ior aperture
+29.475  3.76   1.67   25.2
+84.83   0.12   1      25.2
+19.275  4.025  1.67   23
+40.77   3.275  1.699  23
+12.75   5.705  1      18
+0       4.5    0      17.1  # stop
+-14.495 1.18   1.603  17
+40.77   6.065  1.658  20
+-20.385 0.19   1      20
+437.065 3.22   1.717  20
+-39.73  0      1      20
+";
+
+#[test]
+fn test_polygon_samples_stay_inside() {
+    let hexagon = Aperture::Polygon{ blades: 6, rotation: 15.0 };
+    for _ in 0..1000 {
+        let (x, y) = hexagon.sample();
+        assert!(hexagon.contains(x, y));
+    }
+    // corners reach the unit circle, the middles of the sides don't
+    let corner = 15f32.to_radians();
+    assert!(hexagon.contains(0.99 * corner.cos(), 0.99 * corner.sin()));
+    let side = 45f32.to_radians();
+    assert!(!hexagon.contains(0.95 * side.cos(), 0.95 * side.sin()));
+}
+
+#[test]
+fn test_mask_samples_its_open_pixels() {
+    // only the top right pixel of a 2x2 mask is open
+    let mask = ApertureMask::from_gray(&[0, 255, 0, 0], 2, 2).unwrap();
+    let aperture = Aperture::Mask(mask);
+    for _ in 0..200 {
+        let (x, y) = aperture.sample();
+        assert!((0.0..=1.0).contains(&x) && (0.0..=1.0).contains(&y));
+    }
+    assert!(aperture.contains(0.5, 0.5));
+    assert!(!aperture.contains(-0.5, 0.5));
+    assert!(ApertureMask::from_gray(&[0, 0, 0, 0], 2, 2).is_err());
+    assert!(ApertureMask::from_gray(&[255; 3], 2, 2).is_err());
+}
+
+#[test]
+fn test_cats_eye_clips_the_aperture() {
+    let circle = Aperture::Circle;
+    for _ in 0..500 {
+        let (x, y) = circle.sample_vignetted((0.8, 0.0));
+        assert!(x * x + y * y <= 1.0);
+        assert!((x - 0.8) * (x - 0.8) + y * y <= 1.0);
+    }
+}
+
+#[test]
+fn test_perspective_camera_focuses_with_any_aperture() {
+    let camera = PerspectiveCamera::new(LOOKFROM, LOOKAT, VUP, 40.0, 1.5, 0.5, 4.0)
+        .with_aperture(Aperture::Polygon{ blades: 5, rotation: 0.0 })
+        .with_cats_eye(0.7);
+    let on_focus_plane = |s: f32, t: f32| {
+        let r = camera.get_ray(s, t);
+        assert!(r.origin.length() <= 0.25 + 1e-4);
+        r.origin + r.direction * ((-4.0 - r.origin.z) / r.direction.z)
+    };
+    // every lens sample sees the same point of the focus plane
+    let first = on_focus_plane(0.9, 0.2);
+    for _ in 0..50 {
+        assert!((on_focus_plane(0.9, 0.2) - first).length() < 1e-3);
+    }
+}
+
+#[test]
+fn test_lens_system_focal_length_and_focus() {
+    let lens = LensSystem::parse(DGAUSS_50MM).unwrap();
+    assert_eq!(lens.elements.len(), 11);
+    let focal_length = lens.focal_length().unwrap();
+    assert!((focal_length - 50.0).abs() < 1.5, "focal length {}", focal_length);
+
+    // scene in meters, focused 2 m away
+    let camera = RealisticCamera::new(LOOKFROM, LOOKAT, VUP, lens, 35.0, 1.5, 2.0, 0.001).unwrap();
+    let mut spread = 0.0f32;
+    for _ in 0..50 {
+        let r = camera.get_ray(0.5, 0.5);
+        assert!(r.direction.z < 0.0);
+        let p = r.origin + r.direction * ((-2.0 - r.origin.z) / r.direction.z);
+        spread = spread.max((p.x * p.x + p.y * p.y).sqrt());
+    }
+    assert!(spread < 0.002, "blur of {} at the focus distance", spread);
+
+    // the image isn't upside down
+    let right = camera.get_ray(0.9, 0.5);
+    assert!(right.direction.x > 0.0);
+    let top = camera.get_ray(0.5, 0.9);
+    assert!(top.direction.y > 0.0);
+}
+
+#[test]
+fn test_lens_system_errors() {
+    assert!(LensSystem::parse("").is_err());
+    assert!(LensSystem::parse("29.475 3.76 1.67").err().unwrap().contains("line 1"));
+    let lens = LensSystem::parse(DGAUSS_50MM).unwrap();
+    // closer than four focal lengths can't be focused
+    assert!(RealisticCamera::new(LOOKFROM, LOOKAT, VUP, lens, 35.0, 1.5, 0.1, 0.001).is_err());
+}
+
+#[test]
+fn test_realistic_camera_vignettes_the_corners() {
+    let lens = LensSystem::parse(DGAUSS_50MM).unwrap();
+    let camera = RealisticCamera::new(LOOKFROM, LOOKAT, VUP, lens, 35.0, 1.5, 2.0, 0.001).unwrap()
+        .with_shutter(1.0, 2.0);
+    let passing = |s: f32, t: f32| (0..2000).filter_map(|_| camera.sample_ray(s, t)).count();
+    let center = passing(0.5, 0.5);
+    let corner = passing(0.0, 0.0);
+    assert!(corner < center, "{} of 2000 rays pass in the corner, {} in the center", corner, center);
+    for _ in 0..50 {
+        let time = camera.get_ray(0.0, 0.0).time;
+        assert!((1.0..=2.0).contains(&time));
+    }
+}
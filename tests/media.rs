@@ -0,0 +1,82 @@
+use hell::color::Color;
+use hell::hittable::{Hittable, HitRecord};
+use hell::hittable_list::HittableList;
+use hell::integrator::ray_color;
+use hell::material::{Dielectric, Lambertian, Material};
+use hell::medium::{ConstantMedium, Isotropic};
+use hell::ray::Ray;
+use hell::scene::Scene;
+use hell::sky::Sky;
+use hell::sphere::Sphere;
+use hell::vec3::Vec3;
+
+const SAMPLES: usize = 4000;
+
+fn sphere(radius: f32, material: Box<dyn Material>) -> Sphere {
+    Sphere{ center: Vec3{x: 0.0, y: 0.0, z: 0.0}, radius, material }
+}
+
+// a medium in a sphere of radius 1 around the origin, absorbing everything it scatters
+fn black_smoke(density: f32) -> Box<dyn Hittable> {
+    let boundary = sphere(1.0, Box::new(Lambertian{ albedo: Color{r: 0.5, g: 0.5, b: 0.5} }));
+    Box::new(ConstantMedium::new(Box::new(boundary), density, Box::new(Isotropic{ albedo: Color{r: 0.0, g: 0.0, b: 0.0} })))
+}
+
+fn scene(hitables: Vec<Box<dyn Hittable>>) -> Scene {
+    Scene{ world: HittableList::new(hitables), sky: Sky::new(35.0, 30.0, 3.0), fog: None }
+}
+
+// straight through the origin along -z
+fn view() -> Ray {
+    Ray{ origin: Vec3{x: 0.0, y: 0.0, z: 5.0}, direction: Vec3{x: 0.0, y: 0.0, z: -1.0}, wavelength: None, time: 0.0 }
+}
+
+// average red radiance along the view over the sky's red radiance behind the scene
+fn transmitted(scene: &Scene) -> f32 {
+    let sky = scene.sky.radiance_with_sun(&view().direction).r;
+    (0..SAMPLES).map(|_| ray_color(&view(), scene, 50).r).sum::<f32>() / SAMPLES as f32 / sky
+}
+
+#[test]
+fn test_isotropic_scatters_uniformly() {
+    let isotropic = Isotropic{ albedo: Color{r: 0.8, g: 0.8, b: 0.8} };
+    let material: Box<dyn Material> = Box::new(Lambertian{ albedo: Color{r: 0.5, g: 0.5, b: 0.5} });
+    let record = HitRecord{
+        p: Vec3{x: 0.0, y: 0.0, z: 0.0},
+        normal: Vec3{x: 0.0, y: 0.0, z: 1.0},
+        t: 1.0, u: 0.0, v: 0.0, tangent: None, front_face: true, color: None, instance: 0,
+        material: &material
+    };
+    let mut mean = Vec3{x: 0.0, y: 0.0, z: 0.0};
+    for _ in 0..SAMPLES {
+        let scatt = isotropic.scatter(&view(), &record).unwrap();
+        assert!((scatt.scattered.direction.length() - 1.0).abs() < 1e-4);
+        assert!((scatt.attenuation.r - 0.8).abs() < 1e-6);
+        mean = mean + scatt.scattered.direction / SAMPLES as f32;
+    }
+    assert!(mean.length() < 0.05);
+    // the phase function integrates to the albedo over the sphere
+    let f = isotropic.eval(&view(), &record, &Vec3{x: 1.0, y: 0.0, z: 0.0}).unwrap();
+    assert!((f.r * 4.0 * std::f32::consts::PI - 0.8).abs() < 1e-5);
+}
+
+#[test]
+fn test_empty_medium_is_invisible() {
+    assert!((transmitted(&scene(vec![black_smoke(0.0)])) - 1.0).abs() < 1e-4);
+}
+
+#[test]
+fn test_transmittance_through_a_medium() {
+    // the diameter of 2 at density 0.5 lets e^-1 through unscattered
+    let through = transmitted(&scene(vec![black_smoke(0.5)]));
+    assert!((through - (-1.0f32).exp()).abs() < 0.03, "transmitted {}", through);
+    assert!(transmitted(&scene(vec![black_smoke(50.0)])) < 1e-3);
+}
+
+#[test]
+fn test_medium_inside_glass_passes_its_boundary() {
+    // with the smoke's boundary taken for an interface, the black phase function would absorb every path
+    let glass = sphere(2.0, Box::new(Dielectric::new(1.5)));
+    let through = transmitted(&scene(vec![Box::new(glass), black_smoke(0.0)]));
+    assert!(through > 0.8, "transmitted {}", through);
+}
@@ -0,0 +1,148 @@
+use hell::color::Color;
+use hell::hittable::Hittable;
+use hell::material::VertexLambertian;
+use hell::ply::load_ply;
+use hell::ray::Ray;
+use hell::stl::load_stl;
+use hell::triangle::MeshOptions;
+use hell::vec3::Vec3;
+
+fn material() -> Box<VertexLambertian> {
+    Box::new(VertexLambertian{ albedo: Color{r: 0.5, g: 0.5, b: 0.5} })
+}
+
+fn down(x: f32, z: f32) -> Ray {
+    Ray{ origin: Vec3{x, y: 5.0, z}, direction: Vec3{x: 0.0, y: -1.0, z: 0.0}, wavelength: None, time: 0.0 }
+}
+
+// a unit square on the ground, one polygon, red at the origin corner
+const ASCII_PLY: &str = "ply
+format ascii 1.0
+comment made by hand
+element vertex 4
+property float x
+property float y
+property float z
+property uchar red
+property uchar green
+property uchar blue
+element face 1
+property list uchar int vertex_indices
+end_header
+0 0 0 255 0 0
+1 0 0 0 0 0
+1 0 1 0 0 0
+0 0 1 0 0 0
+4 0 1 2 3
+";
+
+#[test]
+fn test_ascii_ply_with_colors() {
+    let mesh = load_ply(ASCII_PLY.as_bytes(), &MeshOptions::default(), material()).unwrap();
+    assert_eq!(mesh.triangles().len(), 2);
+    let corner = mesh.hit(&down(0.0001, 0.0001), 0.001, f32::INFINITY).unwrap();
+    assert!((corner.t - 5.0).abs() < 1e-4);
+    assert!(corner.color.unwrap().r > 0.99);
+    // blended across the triangle, the red corner's weight there is 1 - x
+    let inner = mesh.hit(&down(0.75, 0.25), 0.001, f32::INFINITY).unwrap();
+    assert!((inner.color.unwrap().r - 0.25).abs() < 1e-4);
+    assert!(mesh.hit(&down(1.1, 0.5), 0.001, f32::INFINITY).is_none());
+}
+
+#[test]
+fn test_ply_colors_of_any_type() {
+    let ply = |ty: &str, red: &str| ASCII_PLY
+        .replace("uchar red", &format!("{} red", ty))
+        .replacen("0 0 0 255 0 0", &format!("0 0 0 {} 0 0", red), 1);
+    let red_at_corner = |text: String| {
+        let mesh = load_ply(text.as_bytes(), &MeshOptions::default(), material()).unwrap();
+        mesh.hit(&down(0.0001, 0.0001), 0.001, f32::INFINITY).unwrap().color.unwrap().r
+    };
+    assert!(red_at_corner(ply("ushort", "65535")) > 0.99);
+    assert!((red_at_corner(ply("ushort", "32768")) - 0.25).abs() < 1e-3);
+    assert!(red_at_corner(ply("char", "127")) > 0.99);
+    assert!(red_at_corner(ply("int", "-5")) < 1e-6);
+    assert!((red_at_corner(ply("float", "0.5")) - 0.25).abs() < 1e-3);
+
+    // every channel is normalized by its own type
+    let mixed = ASCII_PLY.replace("uchar red", "float red").replacen("0 0 0 255 0 0", "0 0 0 0.5 255 0", 1);
+    let mesh = load_ply(mixed.as_bytes(), &MeshOptions::default(), material()).unwrap();
+    let color = mesh.hit(&down(0.0001, 0.0001), 0.001, f32::INFINITY).unwrap().color.unwrap();
+    assert!((color.r - 0.25).abs() < 1e-3 && (color.g - 1.0).abs() < 1e-3, "{:?}", color);
+}
+
+#[test]
+fn test_ply_rejects_invalid_vertex_indices() {
+    for indices in ["4 0 1 -2 3", "4 0 1 2.5 3"].iter() {
+        let text = ASCII_PLY.replace("property list uchar int", "property list uchar float").replace("4 0 1 2 3", indices);
+        let error = load_ply(text.as_bytes(), &MeshOptions::default(), material()).err().unwrap();
+        assert!(error.contains("face 0"), "{}", error);
+    }
+}
+
+#[test]
+fn test_binary_ply_scaled_and_recentered() {
+    let mut bytes = b"ply\nformat binary_big_endian 1.0\nelement vertex 3\nproperty double x\nproperty double y\nproperty double z\n\
+element material 1\nproperty list uchar uchar name\nelement face 1\nproperty list uchar ushort vertex_index\nend_header\n".to_vec();
+    for p in [[0.0f64, 0.0, 0.0], [1000.0, 0.0, 0.0], [0.0, 0.0, 1000.0]].iter() {
+        for c in p.iter() {
+            bytes.extend(c.to_be_bytes());
+        }
+    }
+    // an element the loader doesn't know is skipped
+    bytes.extend([2u8, b'a', b'b'].iter());
+    bytes.push(3);
+    for i in [0u16, 1, 2].iter() {
+        bytes.extend(i.to_be_bytes());
+    }
+
+    let options = MeshOptions{ scale: 0.001, recenter: true };
+    let mesh = load_ply(&bytes, &options, material()).unwrap();
+    let expected = [Vec3{x: -0.5, y: 0.0, z: -0.5}, Vec3{x: 0.5, y: 0.0, z: -0.5}, Vec3{x: -0.5, y: 0.0, z: 0.5}];
+    for (p, e) in mesh.positions().iter().zip(expected.iter()) {
+        assert!((*p - *e).length() < 1e-6);
+    }
+    assert!(mesh.hit(&down(-0.4, -0.4), 0.001, f32::INFINITY).unwrap().color.is_none());
+    assert!(load_ply(&bytes[..bytes.len() - 1], &options, material()).is_err());
+}
+
+// a tetrahedron with corners on the axes
+fn tetrahedron() -> [[Vec3; 3]; 4] {
+    let o = Vec3{x: 0.0, y: 0.0, z: 0.0};
+    let x = Vec3{x: 1.0, y: 0.0, z: 0.0};
+    let y = Vec3{x: 0.0, y: 1.0, z: 0.0};
+    let z = Vec3{x: 0.0, y: 0.0, z: 1.0};
+    [[o, y, x], [o, x, z], [o, z, y], [x, y, z]]
+}
+
+#[test]
+fn test_binary_and_ascii_stl() {
+    let mut binary = b"solid but actually binary".to_vec();
+    binary.resize(80, 0);
+    binary.extend(4u32.to_le_bytes());
+    let mut ascii = String::from("solid tetrahedron\n");
+    for facet in tetrahedron().iter() {
+        binary.extend([0u8; 12].iter());
+        ascii.push_str("facet normal 0 0 0\nouter loop\n");
+        for p in facet.iter() {
+            for c in [p.x, p.y, p.z].iter() {
+                binary.extend(c.to_le_bytes());
+            }
+            ascii.push_str(&format!("vertex {} {} {}\n", p.x, p.y, p.z));
+        }
+        binary.extend([0u8; 2].iter());
+        ascii.push_str("endloop\nendfacet\n");
+    }
+    ascii.push_str("endsolid tetrahedron\n");
+
+    for bytes in [binary, ascii.into_bytes()].iter() {
+        let mesh = load_stl(bytes, &MeshOptions::default(), material()).unwrap();
+        // shared corners are merged
+        assert_eq!(mesh.positions().len(), 4);
+        assert_eq!(mesh.triangles().len(), 4);
+        let hit = mesh.hit(&down(0.2, 0.2), 0.001, f32::INFINITY).unwrap();
+        assert!((hit.t - 4.4).abs() < 1e-4);
+        assert!(hit.front_face);
+    }
+    assert!(load_stl(b"solid broken\nfacet normal 0 0 1\nouter loop\nvertex 0 0\n", &MeshOptions::default(), material()).is_err());
+}
@@ -0,0 +1,45 @@
+use hell::microfacet::{Ggx, fresnel_conductor, fresnel_dielectric};
+use hell::vec3::Vec3;
+
+#[test]
+fn test_fresnel_dielectric() {
+    let f = fresnel_dielectric(1.0, 1.0 / 1.5);
+    assert!((f - 0.04).abs() < 1e-4);
+    // total internal reflection leaving glass at a grazing angle
+    assert_eq!(fresnel_dielectric(0.1, 1.5), 1.0);
+}
+
+#[test]
+fn test_fresnel_conductor() {
+    // gold is much more reflective in red than in blue
+    let red = fresnel_conductor(1.0, 0.143, 3.983);
+    let blue = fresnel_conductor(1.0, 1.442, 1.603);
+    assert!(red > 0.9 && red <= 1.0);
+    assert!(blue < 0.5);
+    assert!((fresnel_conductor(0.0, 0.143, 3.983) - 1.0).abs() < 1e-3);
+}
+
+#[test]
+fn test_visible_normals_face_the_viewer() {
+    let ggx = Ggx::new(0.7);
+    let wo = Vec3{x: 0.6, y: 0.0, z: 0.8};
+    for i in 0..16 {
+        for j in 0..16 {
+            let m = ggx.sample_visible_normal(&wo, (i as f32 + 0.5) / 16.0, (j as f32 + 0.5) / 16.0);
+            assert!(m.z >= 0.0);
+            assert!(m.dot(&wo) >= -1e-4);
+            assert!((m.length() - 1.0).abs() < 1e-4);
+        }
+    }
+}
+
+#[test]
+fn test_masking_bounds() {
+    let ggx = Ggx::new(0.5);
+    let wo = Vec3{x: 0.0, y: 0.6, z: 0.8};
+    let wi = Vec3{x: 0.0, y: -0.6, z: 0.8};
+    let g1 = ggx.g1(&wo);
+    let g2 = ggx.g2(&wo, &wi);
+    assert!(g1 > 0.0 && g1 <= 1.0);
+    assert!(g2 <= g1);
+}
@@ -0,0 +1,30 @@
+use hell::color::Color;
+use hell::hittable::Hittable;
+use hell::material::Lambertian;
+use hell::ray::Ray;
+use hell::sphere::MovingSphere;
+use hell::vec3::Vec3;
+
+#[test]
+fn test_moving_sphere_follows_time() {
+    let sphere = MovingSphere{
+        center0: Vec3{x: 0.0, y: 0.0, z: -2.0},
+        center1: Vec3{x: 2.0, y: 0.0, z: -2.0},
+        time0: 0.0,
+        time1: 1.0,
+        radius: 0.5,
+        material: Box::new(Lambertian{ albedo: Color{r: 0.5, g: 0.5, b: 0.5} })
+    };
+    let r = |time: f32| Ray{
+        origin: Vec3{x: 0.0, y: 0.0, z: 0.0},
+        direction: Vec3{x: 0.0, y: 0.0, z: -1.0},
+        wavelength: None,
+        time
+    };
+    assert!((sphere.hit(&r(0.0), 0.001, f32::INFINITY).unwrap().t - 1.5).abs() < 1e-5);
+    assert!(sphere.hit(&r(0.5), 0.001, f32::INFINITY).is_none());
+    let center = sphere.center(0.25);
+    assert!((center.x - 0.5).abs() < 1e-6);
+    assert!((sphere.center(2.0).x - 2.0).abs() < 1e-6);
+    assert!(sphere.center(-1.0).x.abs() < 1e-6);
+}
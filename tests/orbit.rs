@@ -0,0 +1,41 @@
+use hell::orbit::OrbitController;
+use hell::vec3::Vec3;
+
+fn close(a: &Vec3, b: &Vec3) -> bool {
+    (*a - *b).length() < 1e-4
+}
+
+#[test]
+fn test_orbit_keeps_distance_to_lookat() {
+    let lookat = Vec3{x: 1.0, y: 0.0, z: -1.0};
+    let mut orbit = OrbitController::new(Vec3{x: 1.0, y: 0.0, z: 4.0}, lookat);
+    assert!(close(&orbit.lookfrom(), &Vec3{x: 1.0, y: 0.0, z: 4.0}));
+    assert!(close(&orbit.vup(), &Vec3{x: 0.0, y: 1.0, z: 0.0}));
+
+    orbit.orbit(90.0, 0.0);
+    assert!(close(&orbit.lookfrom(), &Vec3{x: 6.0, y: 0.0, z: -1.0}));
+    // the pitch stops short of the pole
+    orbit.orbit(0.0, 120.0);
+    assert_eq!(orbit.pitch, 89.0);
+    assert!(((orbit.lookfrom() - lookat).length() - 5.0).abs() < 1e-4);
+}
+
+#[test]
+fn test_pan_dolly_and_roll() {
+    let mut orbit = OrbitController::new(Vec3{x: 0.0, y: 0.0, z: 2.0}, Vec3{x: 0.0, y: 0.0, z: 0.0});
+    orbit.pan(0.5, 0.25);
+    assert!(close(&orbit.lookat, &Vec3{x: 1.0, y: 0.5, z: 0.0}));
+    assert!(close(&orbit.lookfrom(), &Vec3{x: 1.0, y: 0.5, z: 2.0}));
+
+    orbit.dolly(0.5);
+    assert!(close(&orbit.lookfrom(), &Vec3{x: 1.0, y: 0.5, z: 1.0}));
+    orbit.dolly(0.0);
+    assert!(orbit.distance > 0.0);
+
+    orbit.roll(90.0);
+    assert!(close(&orbit.vup(), &Vec3{x: -1.0, y: 0.0, z: 0.0}));
+    // panning right follows the rolled image
+    let lookat = orbit.lookat;
+    orbit.pan(1.0, 0.0);
+    assert!(orbit.lookat.y > lookat.y);
+}
@@ -0,0 +1,145 @@
+use hell::hittable::Hittable;
+use hell::hittable_list::HittableList;
+use hell::pbrt::import_pbrt;
+use hell::ray::Ray;
+use hell::vec3::Vec3;
+
+fn scene(before_look_at: &str) -> String {
+    format!(r#"# a glass ball right of the center and a wall behind it
+Film "image" "integer xresolution" [300] "integer yresolution" [200]
+{}
+LookAt 0 0 5  0 0 0  0 1 0
+Camera "perspective" "float fov" [40]
+Sampler "halton" "integer pixelsamples" 64
+Integrator "path"
+WorldBegin
+LightSource "distant" "point from" [1 1 0] "point to" [0 0 0]
+LightSource "point" "point from" [0 4 0]
+AttributeBegin
+  Translate 1 0 0
+  Material "glass" "float index" 1.5
+  Shape "sphere" "float radius" 0.5
+  Shape "cylinder"
+AttributeEnd
+Texture "checks" "spectrum" "checkerboard"
+Material "matte" "rgb Kd" [0.2 0.4 0.6]
+Shape "trianglemesh" "integer indices" [0 1 2 0 2 3]
+    "point P" [-3 -3 -1  3 -3 -1  3 3 -1  -3 3 -1]
+WorldEnd
+"#, before_look_at)
+}
+
+fn close(a: &Vec3, b: &Vec3) -> bool {
+    (*a - *b).length() < 1e-3
+}
+
+// the ball seen from the camera, `s` across the image
+fn ball_at(text: &str, s: f32) -> Option<Vec3> {
+    let scene = import_pbrt(text).unwrap();
+    let world = HittableList::new(scene.objects);
+    let hit = world.hit(&scene.camera.get_ray(s, 0.5), 0.001, f32::INFINITY)?;
+    if hit.t * scene.camera.get_ray(s, 0.5).direction.length() < 5.5 { Some(hit.p) } else { None }
+}
+
+#[test]
+fn test_camera_light_and_skipped() {
+    let scene = import_pbrt(&scene("")).unwrap();
+    assert!((scene.aspect_ratio - 1.5).abs() < 1e-6);
+    assert_eq!(scene.objects.len(), 2);
+
+    let r = scene.camera.get_ray(0.5, 0.5);
+    assert!(close(&r.origin, &Vec3{x: 0.0, y: 0.0, z: 5.0}));
+    assert!(close(&r.direction.unit_vector(), &Vec3{x: 0.0, y: 0.0, z: -1.0}));
+    // the fov spans the shorter, vertical side
+    let top = scene.camera.get_ray(0.5, 1.0).direction.unit_vector();
+    assert!((top.y.atan2(-top.z).to_degrees() - 20.0).abs() < 1e-3);
+
+    // the wall faces the camera although the scene is mirrored
+    let world = HittableList::new(scene.objects);
+    let wall = world.hit(&r, 0.001, f32::INFINITY).unwrap();
+    assert!((wall.t * r.direction.length() - 6.0).abs() < 1e-3);
+    assert!(wall.front_face);
+
+    let sun = scene.sun_direction.unwrap();
+    assert!(close(&sun, &Vec3{x: -1.0, y: 1.0, z: 0.0}.unit_vector()));
+    assert!(scene.skipped.iter().any(|s| s == "line 6: Sampler \"halton\""));
+    assert!(scene.skipped.iter().any(|s| s == "line 7: Integrator \"path\""));
+    assert!(scene.skipped.iter().any(|s| s == "line 10: LightSource \"point\""));
+    assert!(scene.skipped.iter().any(|s| s == "line 15: Shape \"cylinder\""));
+    assert!(scene.skipped.iter().any(|s| s == "line 17: Texture \"checks\""));
+    assert_eq!(scene.skipped.len(), 5);
+}
+
+#[test]
+fn test_image_matches_pbrt_handedness() {
+    // pbrt's LookAt shows +x on the left of the image
+    let left = ball_at(&scene(""), 0.317).unwrap();
+    assert!(ball_at(&scene(""), 0.683).is_none());
+    assert!((left.x + 1.0).abs() < 0.5);
+    // flipped by the common Scale -1 1 1, it is on the right
+    let right = ball_at(&scene("Scale -1 1 1"), 0.683).unwrap();
+    assert!(ball_at(&scene("Scale -1 1 1"), 0.317).is_none());
+    assert!((right.x - 1.0).abs() < 0.5);
+}
+
+#[test]
+fn test_errors() {
+    assert!(import_pbrt("AttributeEnd").err().unwrap().contains("line 1"));
+    assert!(import_pbrt("Translate 1 2").is_err());
+    assert!(import_pbrt("Shape \"sphere\" \"float radius [1]").is_err());
+    assert!(import_pbrt("WorldBegin\nShape \"trianglemesh\" \"point P\" [0 0 0 1 0 0 0 1 0 1 1 0]").err().unwrap().contains("line 2"));
+}
+
+#[test]
+fn test_orthographic_and_environment_cameras() {
+    let orthographic = scene("").replace("Camera \"perspective\" \"float fov\" [40]", "Camera \"orthographic\"");
+    let camera = import_pbrt(&orthographic).unwrap().camera;
+    // the screen window spans 2 along the shorter side, mirrored like the scene
+    assert!(close(&camera.get_ray(0.0, 0.0).origin, &Vec3{x: -1.5, y: -1.0, z: 5.0}));
+    assert!(close(&camera.get_ray(0.3, 0.9).direction.unit_vector(), &Vec3{x: 0.0, y: 0.0, z: -1.0}));
+
+    // pbrt's environment camera looks along its -x in the center of the image, world +x here,
+    // and its space is right handed, so the scene isn't mirrored
+    let environment = scene("").replace("Camera \"perspective\" \"float fov\" [40]", "Camera \"environment\"");
+    let scene = import_pbrt(&environment).unwrap();
+    assert!(scene.skipped.iter().all(|s| !s.contains("Camera")));
+    assert!(close(&scene.camera.get_ray(0.5, 0.5).direction.unit_vector(), &Vec3{x: 1.0, y: 0.0, z: 0.0}));
+    assert!(close(&scene.camera.get_ray(0.75, 0.5).direction.unit_vector(), &Vec3{x: 0.0, y: 0.0, z: 1.0}));
+}
+
+#[test]
+fn test_named_materials() {
+    let text = r#"WorldBegin
+MakeNamedMaterial "crystal" "string type" "glass"
+AttributeBegin
+  NamedMaterial "crystal"
+  Shape "sphere" "float radius" [1]
+AttributeEnd
+Translate 0 0 -5
+Shape "sphere" "float radius" [1]
+"#;
+    let world = HittableList::new(import_pbrt(text).unwrap().objects);
+    let down = Vec3{x: 0.0, y: 0.0, z: -1.0};
+    let crystal = world.hit(&Ray{ origin: Vec3{x: 0.0, y: 0.0, z: 3.0}, direction: down, wavelength: None, time: 0.0 }, 0.001, f32::INFINITY).unwrap();
+    assert!(crystal.material.interior().is_some());
+    assert_eq!(crystal.material.name(), "crystal");
+    // the attributes end with the material and its name, the default matte is no glass
+    let matte = world.hit(&Ray{ origin: Vec3{x: 0.0, y: 0.0, z: -3.0}, direction: down, wavelength: None, time: 0.0 }, 0.001, f32::INFINITY).unwrap();
+    assert!(matte.material.interior().is_none());
+    assert_eq!(matte.material.name(), "lambertian");
+
+    assert!(import_pbrt("NamedMaterial \"lead\"").err().unwrap().contains("line 1"));
+}
+
+#[test]
+fn test_names_with_spaces() {
+    // Blender's exporter keeps the spaces of material names
+    let text = r#"WorldBegin
+MakeNamedMaterial "Glass Floor" "string type" "glass"
+NamedMaterial "Glass Floor"
+Shape "sphere" "float radius" [1]
+"#;
+    let world = HittableList::new(import_pbrt(text).unwrap().objects);
+    let r = Ray{ origin: Vec3{x: 0.0, y: 0.0, z: 3.0}, direction: Vec3{x: 0.0, y: 0.0, z: -1.0}, wavelength: None, time: 0.0 };
+    assert_eq!(world.hit(&r, 0.001, f32::INFINITY).unwrap().material.name(), "Glass Floor");
+}
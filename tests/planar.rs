@@ -0,0 +1,71 @@
+use hell::color::Color;
+use hell::hittable::Hittable;
+use hell::material::Lambertian;
+use hell::planar::{Cuboid, Disk, Plane, Quad};
+use hell::ray::Ray;
+use hell::vec3::Vec3;
+
+fn gray() -> Box<Lambertian> {
+    Box::new(Lambertian{ albedo: Color{r: 0.5, g: 0.5, b: 0.5} })
+}
+
+fn ray(origin: Vec3, direction: Vec3) -> Ray {
+    Ray{ origin, direction, wavelength: None, time: 0.0 }
+}
+
+#[test]
+fn test_quad_uv_and_normal() {
+    let quad = Quad{
+        q: Vec3{x: -1.0, y: -1.0, z: -2.0},
+        u: Vec3{x: 2.0, y: 0.0, z: 0.0},
+        v: Vec3{x: 0.0, y: 4.0, z: 0.0},
+        material: gray()
+    };
+    let hit = quad.hit(&ray(Vec3{x: 0.5, y: 0.0, z: 0.0}, Vec3{x: 0.0, y: 0.0, z: -1.0}), 0.001, f32::INFINITY).unwrap();
+    assert!((hit.t - 2.0).abs() < 1e-5);
+    assert!((hit.u - 0.75).abs() < 1e-5 && (hit.v - 0.25).abs() < 1e-5);
+    assert!(hit.front_face && (hit.normal.z - 1.0).abs() < 1e-5);
+    assert!(quad.hit(&ray(Vec3{x: 1.5, y: 0.0, z: 0.0}, Vec3{x: 0.0, y: 0.0, z: -1.0}), 0.001, f32::INFINITY).is_none());
+
+    let bounds = quad.bounding_box().unwrap();
+    assert!(bounds.max.z > bounds.min.z);
+    assert!((bounds.max.y - 3.0).abs() < 1e-3);
+}
+
+#[test]
+fn test_disk_and_plane() {
+    let disk = Disk{ center: Vec3{x: 0.0, y: 0.0, z: 0.0}, normal: Vec3{x: 0.0, y: 1.0, z: 0.0}, radius: 1.0, material: gray() };
+    let hit = disk.hit(&ray(Vec3{x: 0.5, y: 1.0, z: 0.0}, Vec3{x: 0.0, y: -1.0, z: 0.0}), 0.001, f32::INFINITY).unwrap();
+    assert!((hit.v - 0.5).abs() < 1e-5);
+    assert!(disk.hit(&ray(Vec3{x: 1.1, y: 1.0, z: 0.0}, Vec3{x: 0.0, y: -1.0, z: 0.0}), 0.001, f32::INFINITY).is_none());
+    let bounds = disk.bounding_box().unwrap();
+    assert!((bounds.max.x - 1.0).abs() < 1e-5 && bounds.max.y < 1e-3);
+
+    let plane = Plane{ point: Vec3{x: 0.0, y: -1.0, z: 0.0}, normal: Vec3{x: 0.0, y: 1.0, z: 0.0}, material: gray() };
+    // seen from below the normal flips towards the ray
+    let hit = plane.hit(&ray(Vec3{x: 7.3, y: -3.0, z: 0.0}, Vec3{x: 0.0, y: 1.0, z: 0.0}), 0.001, f32::INFINITY).unwrap();
+    assert!(!hit.front_face && hit.normal.y < 0.0);
+    assert!((0.0..1.0).contains(&hit.u) && (0.0..1.0).contains(&hit.v));
+    assert!(plane.bounding_box().is_none());
+}
+
+#[test]
+fn test_cuboid_faces_point_outwards() {
+    let cuboid = Cuboid{ min: Vec3{x: -1.0, y: -1.0, z: -1.0}, max: Vec3{x: 1.0, y: 2.0, z: 3.0}, material: gray() };
+    let center = Vec3{x: 0.0, y: 0.5, z: 1.0};
+    let directions = [
+        Vec3{x: 1.0, y: 0.0, z: 0.0}, Vec3{x: -1.0, y: 0.0, z: 0.0},
+        Vec3{x: 0.0, y: 1.0, z: 0.0}, Vec3{x: 0.0, y: -1.0, z: 0.0},
+        Vec3{x: 0.0, y: 0.0, z: 1.0}, Vec3{x: 0.0, y: 0.0, z: -1.0}
+    ];
+    for d in directions.iter() {
+        // from outside towards the center the first hit is on the face along `d`
+        let hit = cuboid.hit(&ray(center + *d * 10.0, *d * -1.0), 0.001, f32::INFINITY).unwrap();
+        assert!(hit.front_face);
+        assert!((hit.normal.dot(d) - 1.0).abs() < 1e-5);
+        // from inside the same face is hit from behind
+        let hit = cuboid.hit(&ray(center, *d), 0.001, f32::INFINITY).unwrap();
+        assert!(!hit.front_face);
+    }
+    assert!(cuboid.bounding_box().unwrap().hit(&ray(Vec3{x: 0.0, y: 0.5, z: 10.0}, Vec3{x: 0.0, y: 0.0, z: -1.0}), 0.0, f32::INFINITY));
+}
@@ -0,0 +1,154 @@
+use hell::color::Color;
+use hell::hittable::{HitRecord, Hittable};
+use hell::material::{Material, RoughConductor};
+use hell::principled::Principled;
+use hell::ray::Ray;
+use hell::sphere::{get_sphere_uv, Sphere};
+use hell::texture::{CheckerTexture, SolidColor, Texture};
+use hell::vec3::Vec3;
+
+const SAMPLES: usize = 20000;
+
+// a hit at the origin on a surface facing +z
+#[allow(clippy::borrowed_box)]
+fn record(material: &Box<dyn Material>, tangent: Option<Vec3>) -> HitRecord<'_> {
+    HitRecord{
+        p: Vec3{x: 0.0, y: 0.0, z: 0.0},
+        normal: Vec3{x: 0.0, y: 0.0, z: 1.0},
+        t: 1.0, u: 0.0, v: 0.0, tangent, front_face: true, color: None, instance: 0,
+        material
+    }
+}
+
+// looking at the origin from `towards`
+fn view(towards: Vec3) -> Ray {
+    Ray{ origin: towards, direction: towards * -1.0, wavelength: None, time: 0.0 }
+}
+
+// the fraction of light from `towards` the surface scatters, in a white furnace
+#[allow(clippy::borrowed_box)]
+fn albedo(material: &Box<dyn Material>, towards: Vec3) -> Color {
+    let hit_record = record(material, None);
+    let mut sum = Color{r: 0.0, g: 0.0, b: 0.0};
+    for _ in 0..SAMPLES {
+        if let Some(scatt) = material.scatter(&view(towards), &hit_record) {
+            sum = sum + scatt.attenuation;
+        }
+    }
+    sum * (1.0 / SAMPLES as f32)
+}
+
+fn principled(metallic: f32, roughness: f32) -> Principled {
+    let mut material = Principled::new(Color{r: 1.0, g: 1.0, b: 1.0});
+    material.metallic = Box::new(SolidColor::gray(metallic));
+    material.roughness = Box::new(SolidColor::gray(roughness));
+    material
+}
+
+#[test]
+fn test_white_furnace_keeps_energy() {
+    let above = Vec3{x: 0.0, y: 0.0, z: 1.0};
+    let oblique = Vec3{x: 0.6, y: 0.0, z: 0.8};
+    let mut varnished = principled(0.0, 0.5);
+    varnished.clearcoat = Box::new(SolidColor::gray(1.0));
+    let metals: Vec<Box<dyn Material>> = vec![Box::new(principled(1.0, 0.3)), Box::new(principled(1.0, 0.0))];
+    let dielectrics: Vec<Box<dyn Material>> = vec![Box::new(principled(0.0, 0.5)), Box::new(varnished)];
+    for towards in [above, oblique].iter() {
+        // white metals lose only what masking hides
+        for material in &metals {
+            let a = albedo(material, *towards);
+            assert!(a.r > 0.9 && a.r < 1.01, "white metal scatters {}", a.r);
+        }
+        // Burley's diffuse gains a few percent, the specular lobes are added on top
+        for material in &dielectrics {
+            let a = albedo(material, *towards);
+            assert!(a.r > 0.9 && a.r < 1.1, "white plastic scatters {}", a.r);
+        }
+    }
+}
+
+#[test]
+fn test_metallic_matches_rough_conductor() {
+    let (eta, k) = (Color{r: 0.143, g: 0.374, b: 1.442}, Color{r: 3.983, g: 2.385, b: 1.603});
+    let f0 = |n: f32, k: f32| ((n - 1.0) * (n - 1.0) + k * k) / ((n + 1.0) * (n + 1.0) + k * k);
+    let mut gold = principled(1.0, 0.3);
+    gold.base_color = Box::new(SolidColor::new(f0(eta.r, k.r), f0(eta.g, k.g), f0(eta.b, k.b)));
+    let gold: Box<dyn Material> = Box::new(gold);
+    let conductor: Box<dyn Material> = Box::new(RoughConductor{ eta, k, roughness: 0.3 });
+
+    let r = view(Vec3{x: 0.0, y: 0.0, z: 1.0});
+    let wi = Vec3{x: 0.5, y: 0.0, z: 0.75f32.sqrt()};
+    let expected = conductor.eval(&r, &record(&conductor, None), &wi).unwrap();
+    let principled = gold.eval(&r, &record(&gold, None), &wi).unwrap();
+    for (p, c) in [(principled.r, expected.r), (principled.g, expected.g), (principled.b, expected.b)].iter() {
+        assert!((p / c - 1.0).abs() < 0.02, "{} instead of {}", p, c);
+    }
+}
+
+#[test]
+fn test_anisotropy_follows_the_tangent() {
+    let mut brushed = principled(1.0, 0.4);
+    brushed.anisotropic = Box::new(SolidColor::gray(1.0));
+    let brushed: Box<dyn Material> = Box::new(brushed);
+    let r = view(Vec3{x: 0.0, y: 0.0, z: 1.0});
+    let along_x = Vec3{x: 0.6, y: 0.0, z: 0.8};
+    let along_y = Vec3{x: 0.0, y: 0.6, z: 0.8};
+    let eval = |tangent: Option<Vec3>, wi: &Vec3| brushed.eval(&r, &record(&brushed, tangent), wi).unwrap().r;
+
+    // the highlight stretches along the tangent
+    let x = Some(Vec3{x: 1.0, y: 0.0, z: 0.0});
+    let y = Some(Vec3{x: 0.0, y: 1.0, z: 0.0});
+    assert!(eval(x, &along_x) > 2.0 * eval(x, &along_y));
+    assert!(eval(y, &along_y) > 2.0 * eval(y, &along_x));
+    // without a tangent it has no direction
+    assert!((eval(None, &along_x) - eval(None, &along_y)).abs() < 1e-4);
+}
+
+#[test]
+fn test_textured_parameters() {
+    let checker = CheckerTexture{
+        odd: Box::new(SolidColor::gray(0.0)),
+        even: Box::new(SolidColor::gray(1.0)),
+        scale: 1.0
+    };
+    let origin = Vec3{x: 0.0, y: 0.0, z: 0.0};
+    assert_eq!(checker.value(0.0, 0.0, &Vec3{x: 1.0, y: 1.0, z: 1.0}).r, 1.0);
+    assert_eq!(checker.value(0.0, 0.0, &Vec3{x: -1.0, y: 1.0, z: 1.0}).r, 0.0);
+    assert_eq!(SolidColor::gray(0.3).value(0.5, 0.5, &origin).g, 0.3);
+
+    // scalar parameters read the red channel, clamped to [0, 1]
+    let r = view(Vec3{x: 0.0, y: 0.0, z: 1.0});
+    let wi = Vec3{x: 0.6, y: 0.0, z: 0.8};
+    let mut over = principled(0.0, 0.5);
+    over.metallic = Box::new(SolidColor::new(2.0, 0.0, 0.0));
+    let over: Box<dyn Material> = Box::new(over);
+    let metal: Box<dyn Material> = Box::new(principled(1.0, 0.5));
+    let expected = metal.eval(&r, &record(&metal, None), &wi).unwrap();
+    assert!((over.eval(&r, &record(&over, None), &wi).unwrap().r - expected.r).abs() < 1e-6);
+}
+
+#[test]
+fn test_sphere_uv_poles_and_seam() {
+    // v runs from the south pole up, u around from -x
+    let (_, south) = get_sphere_uv(&Vec3{x: 0.0, y: -1.0, z: 0.0});
+    let (_, north) = get_sphere_uv(&Vec3{x: 0.0, y: 1.0, z: 0.0});
+    assert!(south.abs() < 1e-6 && (north - 1.0).abs() < 1e-6);
+    let (u, v) = get_sphere_uv(&Vec3{x: 1.0, y: 0.0, z: 0.0});
+    assert!((u - 0.5).abs() < 1e-6 && (v - 0.5).abs() < 1e-6);
+
+    // u jumps across the seam at -x and stays in [0, 1]
+    let (before, _) = get_sphere_uv(&Vec3{x: -1.0, y: 0.0, z: 0.001}.unit_vector());
+    let (after, _) = get_sphere_uv(&Vec3{x: -1.0, y: 0.0, z: -0.001}.unit_vector());
+    assert!((0.0..0.01).contains(&before));
+    assert!(after <= 1.0 && after > 0.99);
+
+    // the tangent points where u grows, the poles have none
+    let sphere = Sphere{ center: Vec3{x: 0.0, y: 0.0, z: 0.0}, radius: 1.0, material: Box::new(principled(0.0, 0.5)) };
+    let front = sphere.hit(&view(Vec3{x: 0.0, y: 0.0, z: 3.0}), 0.001, f32::INFINITY).unwrap();
+    let tangent = front.tangent.unwrap();
+    assert!((tangent.x - 1.0).abs() < 1e-4);
+    let (east, _) = get_sphere_uv(&(front.p + tangent * 0.01).unit_vector());
+    assert!(east > front.u);
+    let top = sphere.hit(&view(Vec3{x: 0.0, y: 3.0, z: 0.0}), 0.001, f32::INFINITY).unwrap();
+    assert!(top.tangent.is_none());
+}
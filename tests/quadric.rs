@@ -0,0 +1,144 @@
+use hell::color::Color;
+use hell::hittable::Hittable;
+use hell::material::Lambertian;
+use hell::quadric::{Cone, Cylinder, Hyperboloid, Paraboloid, Torus, polynomial_roots};
+use hell::ray::Ray;
+use hell::vec3::Vec3;
+use std::f32::consts::PI;
+
+fn gray() -> Box<Lambertian> {
+    Box::new(Lambertian{ albedo: Color{r: 0.5, g: 0.5, b: 0.5} })
+}
+
+/*
+    Shoots a ray at each reference point from just outside along its normal
+    and checks the hit lands on the point with the same normal and uv.
+*/
+fn check_surface(shape: &dyn Hittable, samples: &[(Vec3, Vec3, (f32, f32))]) {
+    let bounds = shape.bounding_box().unwrap();
+    for (p, n, (u, v)) in samples.iter() {
+        let n = n.unit_vector();
+        let r = Ray{ origin: *p + n * 0.01, direction: n * -1.0, wavelength: None, time: 0.0 };
+        let hit = shape.hit(&r, 1e-4, f32::INFINITY).expect("reference point missed");
+        assert!((hit.p - *p).length() < 1e-3, "hit {} instead of {}", hit.p, p);
+        assert!((hit.normal - n).length() < 1e-3, "normal {} instead of {}", hit.normal, n);
+        assert!(hit.front_face);
+        assert!((hit.u - u).abs() < 1e-3 && (hit.v - v).abs() < 1e-3);
+        assert!(p.x >= bounds.min.x - 1e-4 && p.y >= bounds.min.y - 1e-4 && p.z >= bounds.min.z - 1e-4);
+        assert!(p.x <= bounds.max.x + 1e-4 && p.y <= bounds.max.y + 1e-4 && p.z <= bounds.max.z + 1e-4);
+    }
+}
+
+// parameters in the middle of an n x n grid over [0, 1]^2
+fn grid(n: usize) -> Vec<(f32, f32)> {
+    (0..n * n).map(|i| (((i % n) as f32 + 0.5) / n as f32, ((i / n) as f32 + 0.5) / n as f32)).collect()
+}
+
+fn around(phi: f32, radius: f32, y: f32) -> Vec3 {
+    Vec3{x: radius * phi.cos(), y, z: radius * phi.sin()}
+}
+
+#[test]
+fn test_cylinder_reference_points() {
+    let cylinder = Cylinder{ radius: 0.8, y_min: -0.5, y_max: 1.5, phi_max: 270.0, capped: false, material: gray() };
+    let samples: Vec<_> = grid(6).into_iter().map(|(u, v)| {
+        let phi = u * 1.5 * PI;
+        (around(phi, 0.8, -0.5 + 2.0 * v), around(phi, 1.0, 0.0), (u, v))
+    }).collect();
+    check_surface(&cylinder, &samples);
+
+    // outside the sweep the ray passes through the gap
+    let gap = Ray{ origin: around(1.75 * PI, 2.0, 0.0), direction: around(1.75 * PI, -1.0, 0.0), wavelength: None, time: 0.0 };
+    let hit = cylinder.hit(&gap, 1e-4, f32::INFINITY).unwrap();
+    assert!(!hit.front_face);
+}
+
+#[test]
+fn test_capped_cylinder_and_cone() {
+    let cylinder = Cylinder{ radius: 1.0, y_min: 0.0, y_max: 2.0, phi_max: 360.0, capped: true, material: gray() };
+    let down = Ray{ origin: Vec3{x: 0.3, y: 5.0, z: 0.2}, direction: Vec3{x: 0.0, y: -1.0, z: 0.0}, wavelength: None, time: 0.0 };
+    let hit = cylinder.hit(&down, 1e-4, f32::INFINITY).unwrap();
+    assert!((hit.t - 3.0).abs() < 1e-4 && hit.normal.y > 0.99);
+
+    let cone = Cone{ radius: 1.0, height: 2.0, phi_max: 360.0, capped: true, material: gray() };
+    let up = Ray{ origin: Vec3{x: 0.3, y: -1.0, z: 0.0}, direction: Vec3{x: 0.0, y: 1.0, z: 0.0}, wavelength: None, time: 0.0 };
+    let hit = cone.hit(&up, 1e-4, f32::INFINITY).unwrap();
+    assert!((hit.t - 1.0).abs() < 1e-4 && hit.normal.y < -0.99);
+
+    // the slope of the side is radius / height, the normal tilts up by its angle
+    let samples: Vec<_> = grid(5).into_iter().map(|(u, v)| {
+        let phi = u * 2.0 * PI;
+        (around(phi, 1.0 - v, 2.0 * v), around(phi, 2.0, 1.0), (u, v))
+    }).collect();
+    check_surface(&cone, &samples);
+}
+
+#[test]
+fn test_paraboloid_and_hyperboloid_reference_points() {
+    let paraboloid = Paraboloid{ radius: 1.0, y_min: 0.2, y_max: 2.0, phi_max: 180.0, material: gray() };
+    let samples: Vec<_> = grid(5).into_iter().map(|(u, v)| {
+        let phi = u * PI;
+        let y = 0.2 + 1.8 * v;
+        let radius = (y / 2.0).sqrt();
+        // gradient of x^2 + z^2 - y / 2
+        (around(phi, radius, y), around(phi, 2.0 * radius, -0.5), (u, v))
+    }).collect();
+    check_surface(&paraboloid, &samples);
+
+    let hyperboloid = Hyperboloid{ radius: 0.5, c: 1.0, y_min: -1.0, y_max: 1.0, phi_max: 360.0, material: gray() };
+    let samples: Vec<_> = grid(5).into_iter().map(|(u, v)| {
+        let phi = u * 2.0 * PI;
+        let y = -1.0 + 2.0 * v;
+        let radius = 0.5 * (1.0 + y * y).sqrt();
+        (around(phi, radius, y), around(phi, radius / 0.25, -y), (u, v))
+    }).collect();
+    check_surface(&hyperboloid, &samples);
+}
+
+#[test]
+fn test_torus_reference_points() {
+    let torus = Torus{ major_radius: 1.0, minor_radius: 0.25, material: gray() };
+    let samples: Vec<_> = grid(8).into_iter().map(|(u, v)| {
+        let (phi, theta) = (u * 2.0 * PI, v * 2.0 * PI);
+        let normal = Vec3{x: theta.cos() * phi.cos(), y: theta.sin(), z: theta.cos() * phi.sin()};
+        (around(phi, 1.0, 0.0) + normal * 0.25, normal, (u, v))
+    }).collect();
+    check_surface(&torus, &samples);
+
+    // straight down through the hole misses, through the ring hits twice
+    let hole = Ray{ origin: Vec3{x: 0.0, y: 2.0, z: 0.0}, direction: Vec3{x: 0.0, y: -1.0, z: 0.0}, wavelength: None, time: 0.0 };
+    assert!(torus.hit(&hole, 1e-4, f32::INFINITY).is_none());
+    let across = Ray{ origin: Vec3{x: -3.0, y: 0.0, z: 0.0}, direction: Vec3{x: 2.0, y: 0.0, z: 0.0}, wavelength: None, time: 0.0 };
+    let hit = torus.hit(&across, 1e-4, f32::INFINITY).unwrap();
+    assert!((hit.t - 0.875).abs() < 1e-4);
+}
+
+#[test]
+fn test_spindle_torus_on_its_axis() {
+    // the tube is wider than the ring, the surface crosses the axis at y = sqrt(0.75)
+    let spindle = Torus{ major_radius: 0.5, minor_radius: 1.0, material: gray() };
+    let down = Ray{ origin: Vec3{x: 0.0, y: 3.0, z: 0.0}, direction: Vec3{x: 0.0, y: -1.0, z: 0.0}, wavelength: None, time: 0.0 };
+    let hit = spindle.hit(&down, 1e-4, f32::INFINITY).unwrap();
+    assert!((hit.p.y - 0.75f32.sqrt()).abs() < 1e-3);
+    assert!((hit.normal - Vec3{x: 0.0, y: 1.0, z: 0.0}).length() < 1e-4, "normal {}", hit.normal);
+}
+
+#[test]
+fn test_flat_paraboloid_is_empty() {
+    let r = Ray{ origin: Vec3{x: 0.0, y: 3.0, z: 0.0}, direction: Vec3{x: 0.0, y: -1.0, z: 0.0}, wavelength: None, time: 0.0 };
+    for &y_max in [0.0, -1.0].iter() {
+        let paraboloid = Paraboloid{ radius: 1.0, y_min: -2.0, y_max, phi_max: 360.0, material: gray() };
+        assert!(paraboloid.hit(&r, 1e-4, f32::INFINITY).is_none());
+    }
+}
+
+#[test]
+fn test_polynomial_roots() {
+    // (t - 1)(t - 2)(t - 3)(t - 4)
+    let roots = polynomial_roots(&[1.0, -10.0, 35.0, -50.0, 24.0], 0.0, 10.0);
+    assert_eq!(roots.len(), 4);
+    for (root, expected) in roots.iter().zip([1.0, 2.0, 3.0, 4.0].iter()) {
+        assert!((root - expected).abs() < 1e-9);
+    }
+    assert!(polynomial_roots(&[1.0, 0.0, 1.0], -5.0, 5.0).is_empty());
+}
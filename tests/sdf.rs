@@ -0,0 +1,81 @@
+use hell::color::Color;
+use hell::hittable::Hittable;
+use hell::material::Lambertian;
+use hell::ray::Ray;
+use hell::sdf::{Repeat, Rounded, Sdf, SdfBox, SdfObject, SdfSphere, SdfTorus, SmoothUnion, Translate, Twist};
+use hell::sphere::Sphere;
+use hell::vec3::Vec3;
+
+fn gray() -> Box<Lambertian> {
+    Box::new(Lambertian{ albedo: Color{r: 0.5, g: 0.5, b: 0.5} })
+}
+
+fn ray(origin: Vec3, direction: Vec3) -> Ray {
+    Ray{ origin, direction, wavelength: None, time: 0.0 }
+}
+
+#[test]
+fn test_sdf_sphere_matches_analytic_sphere() {
+    let center = Vec3{x: 0.3, y: -0.2, z: -3.0};
+    let sdf = SdfObject::new(Box::new(Translate{ shape: Box::new(SdfSphere{ radius: 1.0 }), offset: center }), gray());
+    let sphere = Sphere{ center, radius: 1.0, material: gray() };
+    for i in 0..20 {
+        let d = Vec3{x: -0.3 + 0.03 * i as f32, y: 0.1, z: -1.0};
+        let r = ray(Vec3{x: 0.0, y: 0.0, z: 0.0}, d * 2.0);
+        match (sdf.hit(&r, 0.001, f32::INFINITY), sphere.hit(&r, 0.001, f32::INFINITY)) {
+            (Some(a), Some(b)) => {
+                assert!((a.t - b.t).abs() < 1e-3);
+                assert!((a.normal - b.normal).length() < 1e-2);
+                assert!(a.front_face);
+            },
+            (a, b) => assert!(a.is_none() && b.is_none())
+        }
+    }
+}
+
+#[test]
+fn test_sdf_exits_from_inside() {
+    let sdf = SdfObject::new(Box::new(SdfSphere{ radius: 1.0 }), gray());
+    let hit = sdf.hit(&ray(Vec3{x: 0.0, y: 0.0, z: 0.0}, Vec3{x: 1.0, y: 0.0, z: 0.0}), 0.001, f32::INFINITY).unwrap();
+    assert!((hit.t - 1.0).abs() < 1e-3);
+    assert!(!hit.front_face && hit.normal.x < -0.99);
+}
+
+#[test]
+fn test_distances() {
+    let cube = SdfBox{ half_extents: Vec3{x: 1.0, y: 1.0, z: 1.0} };
+    assert!((cube.distance(&Vec3{x: 3.0, y: 0.0, z: 0.0}) - 2.0).abs() < 1e-6);
+    assert!((cube.distance(&Vec3{x: 0.5, y: 0.0, z: 0.0}) + 0.5).abs() < 1e-6);
+    let rounded = Rounded{ shape: Box::new(cube), radius: 0.25 };
+    assert!((rounded.distance(&Vec3{x: 2.0, y: 2.0, z: 1.0}) - (2f32.sqrt() - 0.25)).abs() < 1e-5);
+
+    let torus = SdfTorus{ major_radius: 1.0, minor_radius: 0.25 };
+    assert!((torus.distance(&Vec3{x: 0.0, y: 0.0, z: 0.0}) - 0.75).abs() < 1e-6);
+
+    // blending only lowers the distance where the shapes are close
+    let blob = SmoothUnion{
+        a: Box::new(SdfSphere{ radius: 1.0 }),
+        b: Box::new(Translate{ shape: Box::new(SdfSphere{ radius: 1.0 }), offset: Vec3{x: 2.5, y: 0.0, z: 0.0} }),
+        k: 0.5
+    };
+    assert!(blob.distance(&Vec3{x: 1.25, y: 0.0, z: 0.0}) < 0.25);
+    assert!((blob.distance(&Vec3{x: -2.0, y: 0.0, z: 0.0}) - 1.0).abs() < 1e-6);
+}
+
+#[test]
+fn test_twist_and_repetition() {
+    let row = Repeat{ shape: Box::new(SdfSphere{ radius: 0.25 }), period: Vec3{x: 1.0, y: 0.0, z: 0.0} };
+    assert!(row.bounding_box().is_none());
+    let sdf = SdfObject::new(Box::new(row), gray());
+    // straight down onto the copy at x = 7
+    let hit = sdf.hit(&ray(Vec3{x: 7.0, y: 5.0, z: 0.0}, Vec3{x: 0.0, y: -1.0, z: 0.0}), 0.001, f32::INFINITY).unwrap();
+    assert!((hit.t - 4.75).abs() < 1e-3);
+
+    let twisted = Twist{ shape: Box::new(SdfBox{ half_extents: Vec3{x: 1.0, y: 2.0, z: 0.2} }), rate: 0.5 };
+    let bounds = twisted.bounding_box().unwrap();
+    assert!(bounds.max.z > 1.0);
+    let mut sdf = SdfObject::new(Box::new(twisted), gray());
+    sdf.step_scale = 0.5;
+    let hit = sdf.hit(&ray(Vec3{x: 0.0, y: 0.0, z: 5.0}, Vec3{x: 0.0, y: 0.0, z: -1.0}), 0.001, f32::INFINITY).unwrap();
+    assert!((hit.t - 4.8).abs() < 1e-3);
+}
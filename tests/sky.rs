@@ -0,0 +1,51 @@
+use hell::color::Color;
+use hell::sky::Sky;
+use hell::vec3::Vec3;
+
+fn brightness(c: &Color) -> f32 {
+    c.r + c.g + c.b
+}
+
+#[test]
+fn test_radiance_is_finite_and_positive_above_the_horizon() {
+    for &(elevation, turbidity) in [(5.0, 2.0), (35.0, 3.0), (80.0, 10.0)].iter() {
+        let sky = Sky::new(elevation, 30.0, turbidity);
+        for i in 0..=8 {
+            for j in 0..16 {
+                let (altitude, azimuth) = ((i as f32 * 11.0).to_radians(), (j as f32 * 22.5).to_radians());
+                let d = Vec3{x: altitude.cos() * azimuth.sin(), y: altitude.sin(), z: -altitude.cos() * azimuth.cos()};
+                let c = sky.radiance(&d);
+                for channel in [c.r, c.g, c.b].iter() {
+                    assert!(channel.is_finite() && *channel > 0.0, "{:?} towards {:?}", c, d);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_brightest_near_the_sun() {
+    let sky = Sky::new(35.0, 30.0, 3.0);
+    let (s, _) = sky.sun_direction.orthonormal_basis();
+    let near = brightness(&sky.radiance(&(sky.sun_direction + s * 0.1)));
+    let side = brightness(&sky.radiance(&(sky.sun_direction + s * 1.0)));
+    let away = brightness(&sky.radiance(&Vec3{x: -sky.sun_direction.x, y: 0.5, z: -sky.sun_direction.z}));
+    assert!(near > side && side > away, "{} {} {}", near, side, away);
+    // and the sun disk outshines it all
+    assert!(brightness(&sky.radiance_with_sun(&sky.sun_direction)) > 100.0 * near);
+}
+
+#[test]
+fn test_turbidity_hazes_the_sky() {
+    let zenith = Vec3{x: 0.0, y: 1.0, z: 0.0};
+    let mut last: Option<(f32, f32)> = None;
+    for &turbidity in [2.0, 3.0, 4.0, 6.0, 8.0].iter() {
+        let sky = Sky::new(35.0, 30.0, turbidity);
+        let (sun, dome) = (brightness(&sky.sun_radiance()), brightness(&sky.radiance(&zenith)));
+        // haze scatters light out of the sun's beam into the sky
+        if let Some((last_sun, last_dome)) = last {
+            assert!(sun < last_sun && dome > last_dome, "turbidity {}", turbidity);
+        }
+        last = Some((sun, dome));
+    }
+}
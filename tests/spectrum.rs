@@ -0,0 +1,52 @@
+use hell::color::Color;
+use hell::material::Dielectric;
+use hell::spectrum::{self, LAMBDA_MAX, LAMBDA_MIN};
+
+#[test]
+fn test_wavelengths_stay_visible() {
+    for i in 0..100 {
+        let hero = LAMBDA_MIN + (LAMBDA_MAX - LAMBDA_MIN) * (i as f32) / 100.0;
+        let lambdas = spectrum::wavelengths(hero);
+        assert_eq!(lambdas[0], hero);
+        for lambda in lambdas.iter() {
+            assert!(*lambda >= LAMBDA_MIN && *lambda < LAMBDA_MAX);
+        }
+    }
+}
+
+#[test]
+fn test_white_round_trip() {
+    let white = Color{r: 1.0, g: 1.0, b: 1.0};
+    let n = 2000;
+    let mut sum = Color{r: 0.0, g: 0.0, b: 0.0};
+    for i in 0..n {
+        let hero = LAMBDA_MIN + (LAMBDA_MAX - LAMBDA_MIN) * (i as f32 + 0.5) / (n as f32);
+        sum = sum + spectrum::to_rgb(&spectrum::sample_rgb(&white, hero), hero);
+    }
+    let mean = sum / (n as f32);
+    assert!((mean.r - 1.0).abs() < 0.02);
+    assert!((mean.g - 1.0).abs() < 0.02);
+    assert!((mean.b - 1.0).abs() < 0.02);
+}
+
+#[test]
+fn test_red_stays_red() {
+    let red = Color{r: 1.0, g: 0.0, b: 0.0};
+    let n = 2000;
+    let mut sum = Color{r: 0.0, g: 0.0, b: 0.0};
+    for i in 0..n {
+        let hero = LAMBDA_MIN + (LAMBDA_MAX - LAMBDA_MIN) * (i as f32 + 0.5) / (n as f32);
+        sum = sum + spectrum::to_rgb(&spectrum::sample_rgb(&red, hero), hero);
+    }
+    let mean = sum / (n as f32);
+    assert!(mean.r > 0.8);
+    assert!(mean.g.abs() < 0.2 && mean.b.abs() < 0.2);
+}
+
+#[test]
+fn test_sellmeier_dispersion() {
+    let glass = Dielectric::bk7();
+    assert!((glass.ior(None) - 1.5168).abs() < 1e-3);
+    // blue light is bent more than red light
+    assert!(glass.ior(Some(450.0)) > glass.ior(Some(650.0)));
+}
@@ -0,0 +1,71 @@
+use hell::camera::{Camera, Projection};
+use hell::stereo::{Eye, OdsCamera, StereoCamera, StereoLayout};
+use hell::vec3::Vec3;
+
+const LOOKFROM: Vec3 = Vec3{x: 0.0, y: 0.0, z: 0.0};
+const LOOKAT: Vec3 = Vec3{x: 0.0, y: 0.0, z: -1.0};
+const VUP: Vec3 = Vec3{x: 0.0, y: 1.0, z: 0.0};
+const IPD: f32 = 0.064;
+
+fn close(a: &Vec3, b: &Vec3) -> bool {
+    (*a - *b).length() < 1e-4
+}
+
+// where a ray of `camera` crosses the plane z = -distance
+fn on_plane(camera: &dyn Camera, s: f32, t: f32, distance: f32) -> Vec3 {
+    let r = camera.get_ray(s, t);
+    r.origin + r.direction * ((-distance - r.origin.z) / r.direction.z)
+}
+
+#[test]
+fn test_perspective_pair_converges_without_vertical_parallax() {
+    let stereo = StereoCamera::perspective(LOOKFROM, LOOKAT, VUP, 60.0, 2.0, IPD, 3.0, StereoLayout::SideBySide);
+    assert!(close(&stereo.left.get_ray(0.5, 0.5).origin, &Vec3{x: -IPD / 2.0, y: 0.0, z: 0.0}));
+    assert!(close(&stereo.right.get_ray(0.5, 0.5).origin, &Vec3{x: IPD / 2.0, y: 0.0, z: 0.0}));
+    for (s, t) in [(0.5, 0.5), (0.1, 0.9), (0.8, 0.3)].iter() {
+        // no parallax at the convergence distance
+        assert!(close(&on_plane(stereo.left.as_ref(), *s, *t, 3.0), &on_plane(stereo.right.as_ref(), *s, *t, 3.0)));
+        // behind it the eyes' rays cross over and disagree only horizontally
+        let (left, right) = (on_plane(stereo.left.as_ref(), *s, *t, 10.0), on_plane(stereo.right.as_ref(), *s, *t, 10.0));
+        assert!((left.y - right.y).abs() < 1e-4);
+        assert!(left.x > right.x);
+    }
+}
+
+#[test]
+fn test_packed_layouts() {
+    let side_by_side = Projection::StereoPerspective{ vfov: 60.0, ipd: IPD, convergence: 3.0, layout: StereoLayout::SideBySide }
+        .camera(LOOKFROM, LOOKAT, VUP, 2.0);
+    assert!(side_by_side.get_ray(0.25, 0.5).origin.x < 0.0);
+    assert!(side_by_side.get_ray(0.75, 0.5).origin.x > 0.0);
+    // each eye's image is square, so its field of view is the same both ways
+    let edge = side_by_side.get_ray(0.5 - 1e-6, 0.5).direction;
+    let top = side_by_side.get_ray(0.25, 1.0).direction;
+    assert!(((edge.x / -edge.z) - (top.y / -top.z)).abs() < 0.03);
+
+    let top_bottom = StereoCamera::ods(LOOKFROM, LOOKAT, VUP, IPD, StereoLayout::TopBottom);
+    assert!(close(&top_bottom.get_ray(0.5, 0.75).origin, &Vec3{x: -IPD / 2.0, y: 0.0, z: 0.0}));
+    assert!(close(&top_bottom.get_ray(0.5, 0.25).origin, &Vec3{x: IPD / 2.0, y: 0.0, z: 0.0}));
+}
+
+#[test]
+fn test_ods_rays_are_tangent() {
+    let left = OdsCamera::new(LOOKFROM, LOOKAT, VUP, IPD, Eye::Left);
+    let ahead = left.get_ray(0.5, 0.5);
+    assert!(close(&ahead.direction.unit_vector(), &Vec3{x: 0.0, y: 0.0, z: -1.0}));
+    // looking along +x the left eye is towards -z
+    let aside = left.get_ray(0.75, 0.5);
+    assert!(close(&aside.origin, &Vec3{x: 0.0, y: 0.0, z: -IPD / 2.0}));
+    assert!(close(&aside.direction.unit_vector(), &Vec3{x: 1.0, y: 0.0, z: 0.0}));
+    for (s, t) in [(0.1, 0.5), (0.6, 0.8), (0.9, 0.2)].iter() {
+        let r = left.get_ray(*s, *t);
+        assert!((r.origin.length() - IPD / 2.0).abs() < 1e-5);
+        let horizontal = Vec3{x: r.direction.x, y: 0.0, z: r.direction.z};
+        assert!(r.origin.dot(&horizontal).abs() < 1e-5);
+    }
+    // rays sample their time while the shutter is open
+    assert_eq!(ahead.time, 0.0);
+    let moving = OdsCamera::new(LOOKFROM, LOOKAT, VUP, IPD, Eye::Left).with_shutter(0.5, 1.0);
+    let time = moving.get_ray(0.5, 0.5).time;
+    assert!((0.5..=1.0).contains(&time));
+}
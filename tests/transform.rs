@@ -0,0 +1,129 @@
+use hell::aabb::Aabb;
+use hell::color::Color;
+use hell::hittable::Hittable;
+use hell::hittable_list::HittableList;
+use hell::instance::{Instance, MovingInstance};
+use hell::integrator::ray_color;
+use hell::material::{Dielectric, Lambertian};
+use hell::planar::Cuboid;
+use hell::ray::Ray;
+use hell::scene::Scene;
+use hell::sky::Sky;
+use hell::sphere::Sphere;
+use hell::transform::{Transform, Pose, AnimatedTransform};
+use hell::vec3::Vec3;
+
+fn close(a: &Vec3, b: &Vec3) -> bool {
+    (*a - *b).length() < 1e-4
+}
+
+#[test]
+fn test_rotate_about_axis() {
+    let t = Transform::rotate(Vec3{x: 0.0, y: 1.0, z: 0.0}, 90.0);
+    assert!(close(&t.vector(&Vec3{x: 1.0, y: 0.0, z: 0.0}), &Vec3{x: 0.0, y: 0.0, z: -1.0}));
+    // a third of a turn about the diagonal cycles the axes
+    let t = Transform::rotate(Vec3{x: 1.0, y: 1.0, z: 1.0}, 120.0);
+    assert!(close(&t.vector(&Vec3{x: 1.0, y: 0.0, z: 0.0}), &Vec3{x: 0.0, y: 1.0, z: 0.0}));
+}
+
+#[test]
+fn test_inverse_of_composition() {
+    let t = Transform::translate(Vec3{x: 1.0, y: 2.0, z: 3.0})
+        * Transform::rotate(Vec3{x: 0.3, y: -1.0, z: 0.5}, 37.0)
+        * Transform::scale(Vec3{x: 2.0, y: 0.5, z: 3.0});
+    let inverse = t.matrix.inverse().unwrap();
+    for i in 0..4 {
+        for j in 0..4 {
+            assert!((inverse.m[i][j] - t.inverse.m[i][j]).abs() < 1e-4);
+        }
+    }
+    let p = Vec3{x: -0.7, y: 0.2, z: 1.9};
+    assert!(close(&t.invert().point(&t.point(&p)), &p));
+    assert!(Transform::scale(Vec3{x: 1.0, y: 0.0, z: 1.0}).matrix.inverse().is_none());
+}
+
+#[test]
+fn test_scaled_instance() {
+    let sphere = Instance::share(Sphere{
+        center: Vec3{x: 0.0, y: 0.0, z: 0.0},
+        radius: 1.0,
+        material: Box::new(Lambertian{ albedo: Color{r: 0.5, g: 0.5, b: 0.5} })
+    });
+    // an ellipsoid 4 wide and 1 high, centered at x = 5
+    let ellipsoid = Instance::new(sphere.clone(),
+        Transform::translate(Vec3{x: 5.0, y: 0.0, z: 0.0}) * Transform::scale(Vec3{x: 2.0, y: 0.5, z: 1.0}));
+
+    let r = Ray{ origin: Vec3{x: 0.0, y: 0.0, z: 0.0}, direction: Vec3{x: 1.0, y: 0.0, z: 0.0}, wavelength: None, time: 0.0 };
+    let hit = ellipsoid.hit(&r, 0.001, f32::INFINITY).unwrap();
+    assert!((hit.t - 3.0).abs() < 1e-4);
+    assert!(close(&hit.p, &Vec3{x: 3.0, y: 0.0, z: 0.0}));
+    assert!(close(&hit.normal, &Vec3{x: -1.0, y: 0.0, z: 0.0}));
+
+    // the normal on the slanted side follows the squashed surface, not the sphere
+    let down = Ray{ origin: Vec3{x: 6.0, y: 5.0, z: 0.0}, direction: Vec3{x: 0.0, y: -1.0, z: 0.0}, wavelength: None, time: 0.0 };
+    let hit = ellipsoid.hit(&down, 0.001, f32::INFINITY).unwrap();
+    let expected = Vec3{x: 0.5 / 2.0, y: (0.75f32).sqrt() / 0.5, z: 0.0}.unit_vector();
+    assert!(close(&hit.normal, &expected));
+    assert!(hit.front_face);
+    assert!(ellipsoid.hit(&Ray{ origin: Vec3{x: 5.0, y: 0.6, z: -5.0}, ..r }, 0.001, f32::INFINITY).is_none());
+}
+
+#[test]
+fn test_overlapping_instances_are_separate_media() {
+    // index matched to air and absorbing half the light over every unit
+    let mut material = Dielectric::new(1.0);
+    material.absorption = Color{r: 0.5, g: 0.5, b: 0.5};
+    material.density = 1.0;
+    let sphere = Instance::share(Sphere{ center: Vec3{x: 0.0, y: 0.0, z: 0.0}, radius: 1.0, material: Box::new(material) });
+    let copy = |z| Box::new(Instance::new(sphere.clone(), Transform::translate(Vec3{x: 0.0, y: 0.0, z}))) as Box<dyn Hittable>;
+    let scene = Scene{ world: HittableList::new(vec![copy(0.5), copy(-0.5)]), sky: Sky::new(35.0, 30.0, 3.0), fog: None };
+
+    // the copies span z from 1.5 to -1.5, each crossing counts on its own
+    let r = Ray{ origin: Vec3{x: 0.0, y: 0.0, z: 5.0}, direction: Vec3{x: 0.0, y: 0.0, z: -1.0}, wavelength: None, time: 0.0 };
+    let through = ray_color(&r, &scene, 50).r / scene.sky.radiance_with_sun(&r.direction).r;
+    assert!((through - 0.125).abs() < 1e-3, "transmitted {}", through);
+}
+
+#[test]
+fn test_animated_transform() {
+    let mut end = Pose::new(Vec3{x: 4.0, y: 0.0, z: 0.0});
+    end.degrees = 180.0;
+    let motion = AnimatedTransform{ start: Pose::new(Vec3{x: 0.0, y: 0.0, z: 0.0}), end, time0: 0.0, time1: 1.0 };
+    let p = Vec3{x: 1.0, y: 0.0, z: 0.0};
+    assert!(close(&motion.at(0.0).point(&p), &p));
+    // half way the object has moved 2 and turned a quarter, not collapsed as a matrix blend would
+    assert!(close(&motion.at(0.5).point(&p), &Vec3{x: 2.0, y: 0.0, z: -1.0}));
+    assert!(close(&motion.at(2.0).point(&p), &Vec3{x: 3.0, y: 0.0, z: 0.0}));
+}
+
+#[test]
+fn test_moving_instance_box_holds_the_whole_motion() {
+    let rod = Instance::share(Cuboid{
+        min: Vec3{x: 1.9, y: -0.1, z: -0.1},
+        max: Vec3{x: 2.1, y: 0.1, z: 0.1},
+        material: Box::new(Lambertian{ albedo: Color{r: 0.5, g: 0.5, b: 0.5} })
+    });
+    let inside = |outer: &Aabb, inner: &Aabb| {
+        outer.min.x <= inner.min.x && outer.min.y <= inner.min.y && outer.min.z <= inner.min.z
+            && inner.max.x <= outer.max.x && inner.max.y <= outer.max.y && inner.max.z <= outer.max.z
+    };
+
+    // the corners of the turning rod peak between any few sampled times
+    let mut end = Pose::new(Vec3{x: 1.0, y: 0.0, z: 0.0});
+    end.degrees = 180.0;
+    let spin = AnimatedTransform{ start: Pose::new(Vec3{x: 0.0, y: 0.0, z: 0.0}), end, time0: 0.0, time1: 1.0 };
+    let bounds = MovingInstance::new(rod.clone(), spin).bounding_box().unwrap();
+    for i in 0..=1000 {
+        let at = Instance::new(rod.clone(), spin.at(i as f32 / 1000.0)).bounding_box().unwrap();
+        assert!(inside(&bounds, &at), "{:?} outside {:?} at {}", at, bounds, i);
+    }
+
+    // moving and growing without turning, the ends are enough
+    let mut end = Pose::new(Vec3{x: 0.0, y: 3.0, z: 0.0});
+    end.scale = Vec3{x: 2.0, y: 2.0, z: 2.0};
+    let slide = AnimatedTransform{ start: Pose::new(Vec3{x: 0.0, y: 0.0, z: 0.0}), end, time0: 0.0, time1: 1.0 };
+    let bounds = MovingInstance::new(rod.clone(), slide).bounding_box().unwrap();
+    let ends = Instance::new(rod.clone(), slide.at(0.0)).bounding_box().unwrap()
+        .surrounding(&Instance::new(rod.clone(), slide.at(1.0)).bounding_box().unwrap());
+    assert!(inside(&bounds, &ends) && inside(&ends, &bounds));
+}
@@ -0,0 +1,169 @@
+use hell::camera::Projection;
+use hell::color::Color;
+use hell::hittable::Hittable;
+use hell::hittable_list::HittableList;
+use hell::material::{Lambertian, Metal};
+use hell::scene::Scene;
+use hell::sky::Sky;
+use hell::sphere::Sphere;
+use hell::vec3::Vec3;
+use hell::viewer::Viewer;
+
+fn sphere(center: Vec3, radius: f32) -> Box<dyn Hittable> {
+    Box::new(Sphere{ center, radius, material: Box::new(Lambertian{ albedo: Color{r: 0.5, g: 0.5, b: 0.5} }) })
+}
+
+fn mirror(center: Vec3, radius: f32) -> Box<dyn Hittable> {
+    Box::new(Sphere{ center, radius, material: Box::new(Metal{ albedo: Color{r: 0.9, g: 0.9, b: 0.9}, fuzz: 0.0 }) })
+}
+
+// a sphere 5 units in front of the camera and a smaller one up and to the right, 10 units away
+fn viewer() -> Viewer {
+    viewer_looking_at(Vec3{x: 0.0, y: 0.0, z: -1.0})
+}
+
+fn viewer_looking_at(lookat: Vec3) -> Viewer {
+    let scene = Scene{
+        world: HittableList::new(vec![
+            sphere(Vec3{x: 0.0, y: 0.0, z: -5.0}, 1.0),
+            mirror(Vec3{x: 3.0, y: 2.0, z: -10.0}, 0.5)
+        ]),
+        sky: Sky::new(35.0, 30.0, 3.0),
+        fog: None
+    };
+    Viewer::from_scene(scene, 40, 40, Vec3{x: 0.0, y: 0.0, z: 0.0}, lookat).with_vfov(40.0)
+}
+
+#[test]
+fn test_focus_at_pixel() {
+    let mut viewer = viewer();
+    assert!((viewer.focus_distance() - 1.0).abs() < 1e-5);
+
+    let distance = viewer.focus_at(20, 20).unwrap();
+    assert!((distance - 4.0).abs() < 0.01);
+    assert_eq!(viewer.focus_distance(), distance);
+
+    // the second sphere is in the upper right, rows count from the top
+    let distance = viewer.focus_at(36, 9).unwrap();
+    assert!(distance > 9.0 && distance < 10.0, "focused at {}", distance);
+}
+
+#[test]
+fn test_focus_on_sky_keeps_focus() {
+    let mut viewer = viewer();
+    viewer.set_focus_distance(3.0);
+    assert_eq!(viewer.focus_at(0, 39), None);
+    assert_eq!(viewer.focus_distance(), 3.0);
+}
+
+#[test]
+fn test_render_fills_the_canvas() {
+    assert_eq!(viewer().render().len(), 40 * 40 * 4);
+}
+
+#[test]
+fn test_pick() {
+    let viewer = viewer();
+    let center = viewer.pick(20, 20).unwrap();
+    assert_eq!(center.object_id(), 0);
+    assert_eq!(center.material(), "lambertian");
+    assert!((center.distance() - 4.0).abs() < 0.01);
+    let position = center.position();
+    assert!(position[0].abs() < 0.05 && position[1].abs() < 0.05 && (position[2] + 4.0).abs() < 0.01);
+    assert!(center.normal()[2] > 0.99);
+
+    let corner = viewer.pick(36, 9).unwrap();
+    assert_eq!(corner.object_id(), 1);
+    assert_eq!(corner.material(), "metal");
+    assert!(viewer.pick(0, 39).is_none());
+}
+
+#[test]
+fn test_projection_can_be_set() {
+    // parallel rays past the right of the front sphere miss the small one, seen in perspective
+    let mut viewer = viewer().with_projection(Projection::Orthographic{ height: 4.0 });
+    assert!((viewer.pick(20, 20).unwrap().distance() - 4.0).abs() < 0.01);
+    assert!(viewer.pick(36, 9).is_none());
+
+    viewer.set_projection(Projection::Perspective{ vfov: 40.0, aperture: 0.0, focus_dist: 2.0 });
+    assert_eq!(viewer.pick(36, 9).unwrap().object_id(), 1);
+    assert_eq!(viewer.focus_distance(), 2.0);
+}
+
+#[test]
+fn test_ids_stay_when_objects_are_removed() {
+    let mut world = HittableList::new(vec![sphere(Vec3{x: 0.0, y: 0.0, z: -5.0}, 1.0), mirror(Vec3{x: 3.0, y: 0.0, z: -5.0}, 1.0)]);
+    let added = world.add(sphere(Vec3{x: -3.0, y: 0.0, z: -5.0}, 1.0));
+    assert_eq!(added, 2);
+    assert!(world.remove(0).is_some());
+    assert!(world.remove(0).is_none());
+    assert_eq!(world.ids(), &[1, 2]);
+    assert!(world.get(2).is_some());
+    // ids aren't reused
+    assert_eq!(world.add(sphere(Vec3{x: 0.0, y: 0.0, z: -5.0}, 1.0)), 3);
+}
+
+#[test]
+fn test_progressive_passes_reset_when_the_camera_moves() {
+    let mut viewer = viewer();
+    viewer.render_pass();
+    viewer.render_pass();
+    assert_eq!(viewer.passes(), 2);
+
+    // previews while dragging don't accumulate
+    viewer.orbit(10.0, 0.0);
+    assert_eq!(viewer.passes(), 0);
+    let preview = viewer.render_pass();
+    assert_eq!(preview.len(), 40 * 40 * 4);
+    // blocks of pixels share a color
+    assert_eq!(preview[0..4], preview[4..8]);
+    assert_eq!(viewer.passes(), 0);
+
+    viewer.stop_moving();
+    assert_eq!(viewer.render_pass().len(), 40 * 40 * 4);
+    assert_eq!(viewer.passes(), 1);
+    viewer.set_focus_distance(2.0);
+    assert_eq!(viewer.passes(), 0);
+
+    // RGB and spectral passes don't mix
+    viewer.render_pass();
+    viewer.set_spectral(true);
+    assert_eq!(viewer.passes(), 0);
+}
+
+#[test]
+fn test_sky_can_be_changed() {
+    let mut viewer = viewer();
+    viewer.render_pass();
+    let noon = viewer.render();
+    viewer.set_sky(3.0, 120.0, 8.0);
+    assert_eq!(viewer.passes(), 0);
+    // the sky fills the top left corner
+    assert!(viewer.render()[0..3] != noon[0..3]);
+}
+
+#[test]
+fn test_orbit_pan_dolly_and_roll_move_the_view() {
+    // orbiting around the sphere's center keeps it in the middle of the image
+    let mut viewer = viewer_looking_at(Vec3{x: 0.0, y: 0.0, z: -5.0});
+    viewer.orbit(30.0, 20.0);
+    let center = viewer.pick(20, 20).unwrap();
+    assert_eq!(center.object_id(), 0);
+
+    // dragging right carries the sphere along with the cursor, looking at its front
+    let mut viewer = viewer_looking_at(Vec3{x: 0.0, y: 0.0, z: -4.0});
+    viewer.pan(15.0, 0.0);
+    assert!(viewer.pick(20, 20).is_none());
+    assert_eq!(viewer.pick(34, 20).unwrap().object_id(), 0);
+
+    let mut viewer = self::viewer();
+    let before = viewer.pick(20, 20).unwrap().distance();
+    viewer.dolly(0.5);
+    assert!(viewer.focus_distance() < 1.0);
+    assert!(viewer.pick(20, 20).unwrap().distance() < before);
+
+    // turning the camera a quarter counterclockwise turns the image clockwise, the sphere in the upper right goes to the lower right
+    let mut viewer = self::viewer();
+    viewer.roll(90.0);
+    assert_eq!(viewer.pick(31, 36).unwrap().object_id(), 1);
+}